@@ -1 +1,31 @@
-# Example API app
+# examples/api_app/app.py
+from haske import Haske, Request, ApiKeys, api_key_required, install_error_handlers
+from haske.middleware import APIKeyMiddleware
+
+app = Haske(__name__)
+install_error_handlers(app)
+
+# Keys live in memory here; store the ApiKey records in your database instead.
+api_keys = ApiKeys("change-me")
+key, record = api_keys.create(scopes=["posts:read"], name="demo")
+api_keys.add(record)
+print(f"[api_app] Try: curl -H 'Authorization: Bearer {key}' http://localhost:8000/api/posts")
+
+app.middleware(APIKeyMiddleware, api_keys=api_keys)
+
+posts = [{"id": 1, "title": "Hello, Haske!"}]
+
+@app.route("/api/posts", methods=["GET"])
+@api_key_required("posts:read")
+async def list_posts(request: Request):
+    return {"posts": posts, "key": request.state.api_key.name}
+
+@app.route("/api/posts", methods=["POST"])
+@api_key_required("posts:write")
+async def create_post(request: Request):
+    post = await request.json()
+    posts.append(post)
+    return post
+
+if __name__ == "__main__":
+    app.run(host="0.0.0.0", port=8000)
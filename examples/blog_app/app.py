@@ -1,5 +1,6 @@
 from haske import Haske, render_template_async, RedirectResponse, url_for, request
 from haske.auth import AuthManager
+from haske.middleware import CSRFMiddleware
 from haske.orm import AsyncORM
 import bcrypt
 
@@ -12,6 +13,11 @@ orm = AsyncORM()
 # ==== Auth Manager ====
 auth = AuthManager(secret_key="abc")  # Manual session handling
 
+# ==== CSRF protection for every POST form, including @app.route handlers ====
+# It also sets up the csrf_token() global the templates render into each form.
+# cookie_secure=False lets the cookie work over plain HTTP during development.
+app.middleware(CSRFMiddleware, secret_key="abc", cookie_secure=False)
+
 # ====== initialize and create all database tables
 @app.on_startup
 async def create_db():
@@ -3,16 +3,29 @@
 Haske - High-performance Python web framework with Rust acceleration.
 """
 
+import sys
+
+# The native extension ships inside this package as `haske.haske`. Register it
+# under the legacy `_haske_core` name so submodules pick it up without needing
+# the separate haske_core wheel.
+if "_haske_core" not in sys.modules:
+    try:
+        from . import haske as _native
+        sys.modules["_haske_core"] = _native
+    except ImportError:
+        pass
+
 from .app import Haske
 from .request import Request
-from .response import Response, JSONResponse, HTMLResponse, RedirectResponse, StreamingResponse, FileResponse, APIResponse
+from .response import Response, JSONResponse, HTMLResponse, RedirectResponse, StreamingResponse, JSONStreamResponse, FileResponse, APIResponse
 from .response import ok_response, created_response, error_response, not_found_response, validation_error_response
 from .templates import render_template, render_template_async, template_response, TemplateEngine, get_url as url_for
 from .auth import create_session_token, verify_session_token, create_password_hash, verify_password_hash
-from .auth import generate_csrf_token, validate_csrf_token, AuthManager
+from .auth import create_jwt, verify_jwt, verify_id_token
+from .auth import generate_csrf_token, validate_csrf_token, api_key_required, AuthManager
 from .exceptions import HaskeError, ValidationError, AuthenticationError, PermissionError, NotFoundError, RateLimitError, ServerError
 from .exceptions import haske_error_handler, http_error_handler, validation_error_handler, install_error_handlers
-from .middleware import Middleware, SessionMiddlewareFactory, CORSMiddlewareFactory, CompressionMiddlewareFactory, RateLimitMiddlewareFactory
+from .middleware import Middleware, SessionMiddlewareFactory, CORSMiddlewareFactory, CompressionMiddlewareFactory, RateLimitMiddlewareFactory, CSRFMiddleware, CSRFMiddlewareFactory, csrf_token, APIKeyMiddleware, APIKeyMiddlewareFactory
 from .admin import generate_admin_index, generate_admin_api
 from .routing import Route, PathConverter, IntConverter, FloatConverter, UUIDConverter, PathConverterRegistry, convert_path
 from .cli import cli
@@ -24,10 +37,17 @@ from .ws import WebSocket, WebSocketBroadcaster, WebSocketHandler, LiveSessionMa
 try:
     from _haske_core import (
         HaskeApp as RustRouter, 
-        HaskeCache as RustCache,
+        HaskeCache as RustCache, SharedCache,
         compile_path, match_path,
         json_loads_bytes, json_dumps_obj, json_is_valid, json_extract_field,
-        sign_cookie, verify_cookie, hash_password, verify_password, generate_random_bytes,
+        JsonSchema, SchemaError, compile_schema,
+        SessionStore, Session, SessionStoreError,
+        JwtKey, JwtError, jwt_encode, jwt_decode, jwt_decode_header, load_jwks, load_jwks_file,
+        Jwks, IdTokenVerifier, generate_pkce_verifier, pkce_challenge, verify_pkce,
+        Keyring, CsrfGuard, ApiKeys, ApiKey, sign_cookie, verify_cookie, encrypt_cookie, decrypt_cookie, generate_random_bytes,
+        PasswordPolicy, hash_password, verify_password, needs_rehash,
+        Policy, Decision, PolicyError,
+        Totp, Hotp, generate_otp_secret, generate_recovery_codes, hash_recovery_code, verify_recovery_code,
         gzip_compress, gzip_decompress, zstd_compress, zstd_decompress, brotli_compress, brotli_decompress,
         prepare_query, prepare_queries,
         websocket_accept_key, validate_websocket_frame, get_frame_type,
@@ -52,15 +72,16 @@ except ImportError:
 __version__ = "0.2.13"
 __all__ = [
     "Haske", "Request", "Response", "JSONResponse", "HTMLResponse", "RedirectResponse", 
-    "StreamingResponse", "FileResponse", "APIResponse", "ok_response", "created_response", 
+    "StreamingResponse", "JSONStreamResponse", "FileResponse", "APIResponse", "ok_response", "created_response", 
     "error_response", "not_found_response", "validation_error_response", "render_template", 
     "render_template_async", "template_response", "TemplateEngine",
     "create_session_token", "verify_session_token", "create_password_hash", "verify_password_hash",
-    "generate_csrf_token", "validate_csrf_token", "AuthManager", "HaskeError", "ValidationError",
+    "create_jwt", "verify_jwt", "verify_id_token",
+    "generate_csrf_token", "validate_csrf_token", "api_key_required", "AuthManager", "HaskeError", "ValidationError",
     "AuthenticationError", "PermissionError", "NotFoundError", "RateLimitError", "ServerError",
     "haske_error_handler", "http_error_handler", "validation_error_handler", "install_error_handlers",
     "Middleware", "SessionMiddlewareFactory", "CORSMiddlewareFactory", "CompressionMiddlewareFactory",
-    "RateLimitMiddlewareFactory", "generate_admin_index", "generate_admin_api", "Route", "PathConverter",
+    "RateLimitMiddlewareFactory", "CSRFMiddleware", "CSRFMiddlewareFactory", "csrf_token", "APIKeyMiddleware", "APIKeyMiddlewareFactory", "generate_admin_index", "generate_admin_api", "Route", "PathConverter",
     "IntConverter", "FloatConverter", "UUIDConverter", "PathConverterRegistry", "convert_path", "cli",
     "Cache", "get_default_cache", "FrontendServer", "FrontendDevelopmentServer", "FrontendManager", 
     "create_frontend_config", "WebSocket", "WebSocketBroadcaster", "WebSocketHandler", "LiveSessionManager",
@@ -21,7 +21,7 @@ from pathlib import Path
 from typing import Any, Callable, Awaitable, Dict, List, Optional, Union
 
 from starlette.applications import Starlette
-from starlette.responses import JSONResponse, HTMLResponse, Response
+from starlette.responses import JSONResponse, HTMLResponse, PlainTextResponse, Response
 from starlette.routing import Route, Mount, WebSocketRoute
 from starlette.middleware import Middleware as StarletteMiddleware
 from starlette.staticfiles import StaticFiles
@@ -32,6 +32,7 @@ from starlette.websockets import WebSocket as StarletteWebSocket
 
 # Import WebSocket utilities
 from .ws import WebSocket, websocket_route as ws_route_decorator, get_broadcaster
+from .routing import to_starlette_path
 
 # Import Rust router if available
 try:
@@ -141,6 +142,16 @@ def create_reverse_proxy(
     ])
 
 
+# Headers added to every response produced by a route handler
+CORS_HEADERS = {
+    "Access-Control-Allow-Origin": "*",
+    "Access-Control-Allow-Methods": "GET, POST, PUT, DELETE, OPTIONS, PATCH, HEAD",
+    "Access-Control-Allow-Headers": "Content-Type, Authorization, Accept, X-Requested-With",
+    "Access-Control-Allow-Credentials": "true",
+    "Access-Control-Max-Age": "86400",
+}
+
+
 # Currennt app logic
 _current_app = None
 
@@ -151,6 +162,50 @@ def get_current_app():
     return _current_app
 
 
+def _default_http_exception_handler(request, exc: HTTPException) -> Response:
+    """Mirror Starlette's plain response for unhandled HTTPExceptions."""
+    headers = getattr(exc, "headers", None)
+    if exc.status_code in {204, 304}:
+        return Response(status_code=exc.status_code, headers=headers)
+    return PlainTextResponse(exc.detail, status_code=exc.status_code, headers=headers)
+
+
+class RustDispatchMiddleware:
+    """
+    Innermost middleware: serve HTTP requests from the Rust router and fall
+    through to the Starlette router on a miss.
+
+    Sitting inside the user middleware stack means CSRF, API key, session and
+    other middleware see every request, whichever router handles it.
+    """
+
+    def __init__(self, app, haske: "Haske"):
+        self.app = app
+        self.haske = haske
+
+    async def __call__(self, scope, receive, send) -> None:
+        if scope["type"] != "http":
+            await self.app(scope, receive, send)
+            return
+
+        dispatch = self.haske._rust_router.dispatch(scope, receive, send)
+        try:
+            if await dispatch:
+                return
+        except Exception as exc:
+            handler = self.haske._lookup_exception_handler(exc)
+            if handler is None or dispatch.response_started:
+                raise
+            from .request import Request
+            response = handler(Request(scope, receive, send), exc)
+            if inspect.isawaitable(response):
+                response = await response
+            await response(scope, receive, send)
+            return
+
+        await self.app(scope, receive, send)
+
+
 # --------------------------------------------------------------------------
 # Haske application
 # --------------------------------------------------------------------------
@@ -188,6 +243,11 @@ class Haske:
 
         # Rust router (optional)
         self._rust_router = RustRouter() if HAS_RUST_ROUTER else None
+        if self._rust_router is not None:
+            self._rust_router.set_default_headers(list(CORS_HEADERS.items()))
+
+        # Exception handlers, shared by Rust-dispatched and Starlette routes
+        self.exception_handlers: Dict[Any, Callable] = {}
 
         # Frontend integration state
         self._frontend_mode: str = "production"
@@ -324,16 +384,48 @@ class Haske:
                 result = await func(request)
                 return self._convert_to_response(result)
 
-            self.routes.append(Route(path, endpoint, methods=methods, name=name))
+            route_name = name or func.__name__
+            self.routes.append(Route(to_starlette_path(path), endpoint, methods=methods, name=route_name))
 
             if self._rust_router is not None:
-                from .routing import convert_path
-                regex_path = convert_path(path.replace("<", ":").replace(">", ""))
-                self._rust_router.add_route(",".join(methods), regex_path, func)
+                self._rust_router.add_route(",".join(methods), path, func, route_name)
             return func
 
         return decorator
     
+    # ---------------------------
+    # EXCEPTION HANDLERS
+    # ---------------------------
+    def add_exception_handler(self, exc_class_or_status_code: Union[int, type], handler: Callable) -> None:
+        """
+        Register a handler for an exception class or HTTP status code.
+
+        The handler is called as `handler(request, exc)` and may be async.
+        """
+        self.exception_handlers[exc_class_or_status_code] = handler
+        if self.starlette_app:
+            self.starlette_app.add_exception_handler(exc_class_or_status_code, handler)
+
+    def exception_handler(self, exc_class_or_status_code: Union[int, type]) -> Callable:
+        """
+        Decorator form of `add_exception_handler`.
+        """
+        def decorator(func: Callable) -> Callable:
+            self.add_exception_handler(exc_class_or_status_code, func)
+            return func
+
+        return decorator
+
+    def _lookup_exception_handler(self, exc: Exception) -> Optional[Callable]:
+        if isinstance(exc, HTTPException) and exc.status_code in self.exception_handlers:
+            return self.exception_handlers[exc.status_code]
+        for cls in type(exc).__mro__:
+            if cls in self.exception_handlers:
+                return self.exception_handlers[cls]
+        if isinstance(exc, HTTPException):
+            return _default_http_exception_handler
+        return None
+
     def on_startup(self, func: Callable[..., Awaitable[Any]]):
         """
         Register a coroutine function to be called on app startup.
@@ -515,11 +607,8 @@ class Haske:
         return response
 
     def _add_cors_headers(self, response: Response) -> None:
-        response.headers["Access-Control-Allow-Origin"] = "*"
-        response.headers["Access-Control-Allow-Methods"] = "GET, POST, PUT, DELETE, OPTIONS, PATCH, HEAD"
-        response.headers["Access-Control-Allow-Headers"] = "Content-Type, Authorization, Accept, X-Requested-With"
-        response.headers["Access-Control-Allow-Credentials"] = "true"
-        response.headers["Access-Control-Max-Age"] = "86400"
+        for key, value in CORS_HEADERS.items():
+            response.headers[key] = value
 
     # ---------------------------
     # ROUTER MATCH
@@ -532,6 +621,23 @@ class Haske:
                 return handler, params
         return None, None
 
+    def url_path_for(self, name: str, /, **params: Any) -> str:
+        """
+        Build the URL path for a named route.
+
+        Path parameters are formatted by their converters and percent-encoded;
+        any other keyword arguments are appended as a query string.
+
+        Raises:
+            LookupError: If no route has this name
+            ValueError: If a path parameter is missing or invalid
+        """
+        if self._rust_router is not None:
+            return self._rust_router.url_path_for(name, **params)
+        if self.starlette_app is None:
+            self.build()
+        return str(self.starlette_app.url_path_for(name, **params))
+
     # ---------------------------
     # STARLETTE APP
     # ---------------------------
@@ -540,11 +646,17 @@ class Haske:
         # (e.g. "/about", "/docs", "/contact") are matched first.
         self._reorder_routes([])
 
+        # Rust dispatch goes innermost so user middleware wraps both routers
+        middleware = list(self.middleware_stack)
+        if self._rust_router is not None:
+            middleware.append(StarletteMiddleware(RustDispatchMiddleware, haske=self))
+
         # Create Starlette app with the current routes & middleware
         self.starlette_app = Starlette(
             debug=os.getenv("HASKE_DEBUG", "False").lower() == "true",
             routes=self.routes,
-            middleware=self.middleware_stack,
+            middleware=middleware,
+            exception_handlers=self.exception_handlers,
         )
         if self._frontend_shutdown_cb:
             self.starlette_app.add_event_handler("shutdown", self._frontend_shutdown_cb)
@@ -559,22 +671,6 @@ class Haske:
     async def __call__(self, scope, receive, send) -> None:
         if self.starlette_app is None:
             self.build()
-
-        if scope["type"] == "http" and self._rust_router:
-            method = scope["method"]
-            path = scope["path"]
-            handler, params = self.match_request(method, path)
-            if handler:
-                from .request import Request
-                request = Request(scope, receive, send, params)
-                try:
-                    result = await handler(request)
-                    response = self._convert_to_response(result)
-                    await response(scope, receive, send)
-                    return
-                except Exception:
-                    pass
-
         await self.starlette_app(scope, receive, send)
 
     # ---------------------------
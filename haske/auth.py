@@ -8,21 +8,59 @@ session tokens, password hashing, CSRF protection, and role-based access control
 
 import time
 import json
-from typing import Dict, Any, Optional
+from typing import Dict, Any, List, Optional, Tuple, Union
 
 # Import Rust crypto functions if available
 try:
-    from _haske_core import sign_cookie, verify_cookie, hash_password, verify_password, generate_random_bytes
+    from _haske_core import Keyring, sign_cookie, verify_cookie, encrypt_cookie, decrypt_cookie, generate_random_bytes
     HAS_RUST_CRYPTO = True
 except ImportError:
     HAS_RUST_CRYPTO = False
 
-def create_session_token(secret: str, payload: dict, expires_in: int = 3600) -> str:
+# Import Rust password hashing if available
+try:
+    from _haske_core import PasswordPolicy, hash_password, verify_password, needs_rehash
+    HAS_RUST_PASSWORDS = True
+except ImportError:
+    HAS_RUST_PASSWORDS = False
+
+# Import Rust server-side sessions if available
+try:
+    from _haske_core import SessionStore
+    HAS_RUST_SESSIONS = True
+except ImportError:
+    HAS_RUST_SESSIONS = False
+
+# Import Rust authorization policies if available
+try:
+    from _haske_core import Policy, PolicyError
+    HAS_RUST_POLICY = True
+except ImportError:
+    HAS_RUST_POLICY = False
+
+# Import Rust JWT support if available
+try:
+    from _haske_core import JwtKey, JwtError, jwt_encode, jwt_decode
+    HAS_RUST_JWT = True
+except ImportError:
+    HAS_RUST_JWT = False
+
+# Import Rust OpenID Connect support if available
+try:
+    from _haske_core import IdTokenVerifier
+    HAS_RUST_OIDC = True
+except ImportError:
+    HAS_RUST_OIDC = False
+
+# Iteration count of the tuple-based hashes from `create_password_hash`.
+LEGACY_PBKDF2_ROUNDS = 100000
+
+def create_session_token(secret: Union[str, "Keyring"], payload: dict, expires_in: int = 3600) -> str:
     """
     Create a signed session token.
     
     Args:
-        secret: Secret key for signing
+        secret: Secret key for signing, or a `Keyring` to sign with its active key
         payload: Token payload data
         expires_in: Token expiration time in seconds, defaults to 3600 (1 hour)
         
@@ -36,6 +74,8 @@ def create_session_token(secret: str, payload: dict, expires_in: int = 3600) ->
     payload["exp"] = int(time.time()) + expires_in
     payload_json = json.dumps(payload, separators=(",", ":"), ensure_ascii=False)
     
+    if HAS_RUST_CRYPTO and isinstance(secret, Keyring):
+        return secret.sign(payload_json)
     if HAS_RUST_CRYPTO:
         return sign_cookie(secret, payload_json)
     else:
@@ -58,12 +98,12 @@ def create_session_token(secret: str, payload: dict, expires_in: int = 3600) ->
         
         return f"{encoded_payload}.{encoded_signature}"
 
-def verify_session_token(secret: str, token: str) -> Optional[Dict[str, Any]]:
+def verify_session_token(secret: Union[str, "Keyring"], token: str) -> Optional[Dict[str, Any]]:
     """
     Verify and decode a session token.
     
     Args:
-        secret: Secret key used for signing
+        secret: Secret key used for signing, or a `Keyring` holding it
         token: Token to verify
         
     Returns:
@@ -72,7 +112,10 @@ def verify_session_token(secret: str, token: str) -> Optional[Dict[str, Any]]:
     Example:
         >>> payload = verify_session_token("secret", token)
     """
-    if HAS_RUST_CRYPTO:
+    if HAS_RUST_CRYPTO and isinstance(secret, Keyring):
+        verified = secret.verify(token)
+        payload_str = verified[0] if verified else None
+    elif HAS_RUST_CRYPTO:
         payload_str = verify_cookie(secret, token)
     else:
         # Fallback Python implementation
@@ -107,6 +150,10 @@ def verify_session_token(secret: str, token: str) -> Optional[Dict[str, Any]]:
         except Exception:
             return None
     
+    return _load_session_payload(payload_str)
+
+def _load_session_payload(payload_str: Optional[str]) -> Optional[Dict[str, Any]]:
+    """Decode a verified token payload, rejecting it once expired."""
     if payload_str is None:
         return None
     
@@ -119,9 +166,137 @@ def verify_session_token(secret: str, token: str) -> Optional[Dict[str, Any]]:
     except json.JSONDecodeError:
         return None
 
+def create_encrypted_session_token(secret: Union[str, "Keyring"], name: str, payload: dict,
+                                   expires_in: int = 3600, cipher: str = "aes-256-gcm") -> str:
+    """
+    Create an encrypted session token whose contents cannot be read by the client.
+    
+    Args:
+        secret: Application secret or `Keyring`; the cipher key is derived from it
+        name: Name of the cookie the token is stored in, bound to the ciphertext
+        payload: Token payload data
+        expires_in: Token expiration time in seconds, defaults to 3600 (1 hour)
+        cipher: "aes-256-gcm" (default) or "xchacha20-poly1305"
+        
+    Returns:
+        str: Encrypted session token
+        
+    Example:
+        >>> token = create_encrypted_session_token("secret", "session", {"email": "a@example.com"})
+    """
+    if not HAS_RUST_CRYPTO:
+        raise RuntimeError("encrypted sessions require the haske native extension")
+    
+    payload = payload.copy()
+    payload["exp"] = int(time.time()) + expires_in
+    payload_json = json.dumps(payload, separators=(",", ":"), ensure_ascii=False)
+    return encrypt_cookie(secret, name, payload_json, cipher)
+
+def verify_encrypted_session_token(secret: Union[str, "Keyring"], name: str, token: str) -> Optional[Dict[str, Any]]:
+    """
+    Decrypt a token from `create_encrypted_session_token`.
+    
+    Args:
+        secret: Application secret or `Keyring` used for encryption
+        name: Name of the cookie the token was read from
+        token: Token to decrypt
+        
+    Returns:
+        Optional[dict]: Decoded payload if authentic and unexpired, None otherwise
+    """
+    if not HAS_RUST_CRYPTO:
+        raise RuntimeError("encrypted sessions require the haske native extension")
+    
+    return _load_session_payload(decrypt_cookie(secret, name, token))
+
+def create_jwt(key: "JwtKey", claims: dict, expires_in: Optional[int] = 3600,
+               headers: Optional[Dict[str, Any]] = None) -> str:
+    """
+    Create a signed JWT.
+    
+    Args:
+        key: `JwtKey` to sign with (HMAC, RSA, ECDSA or Ed25519)
+        claims: Token claims
+        expires_in: Seconds until `exp`, defaults to 3600; None for no expiry
+        headers: Additional JOSE header members
+        
+    Returns:
+        str: Compact JWT; `iat` and `exp` are added unless already present
+        
+    Example:
+        >>> key = JwtKey.from_pem_file("private.pem", kid="2024-01")
+        >>> token = create_jwt(key, {"sub": "123", "aud": "mobile"})
+    """
+    if not HAS_RUST_JWT:
+        raise RuntimeError("JWT support requires the haske native extension")
+    
+    claims = dict(claims)
+    now = int(time.time())
+    claims.setdefault("iat", now)
+    if expires_in is not None:
+        claims.setdefault("exp", now + expires_in)
+    return jwt_encode(claims, key, headers)
+
+def verify_jwt(token: str, keys: Any, audience: Any = None, issuer: Any = None,
+               leeway: float = 0, algorithms: Optional[list] = None) -> Optional[Dict[str, Any]]:
+    """
+    Verify a JWT and validate its claims.
+    
+    Args:
+        token: Compact JWT
+        keys: `JwtKey` or list of keys; the header `kid` selects among them
+        audience: Expected `aud` value or list of values
+        issuer: Expected `iss` value or list of values
+        leeway: Clock skew tolerance in seconds for `exp`/`nbf`/`iat`
+        algorithms: Allowed `alg` values, defaults to each key's algorithm
+        
+    Returns:
+        Optional[dict]: Claims if the token is valid, None otherwise
+        
+    Example:
+        >>> claims = verify_jwt(token, load_jwks_file("jwks.json"), audience="mobile")
+    """
+    if not HAS_RUST_JWT:
+        raise RuntimeError("JWT support requires the haske native extension")
+    
+    try:
+        return jwt_decode(token, keys, algorithms=algorithms, audience=audience,
+                          issuer=issuer, leeway=leeway)
+    except JwtError:
+        return None
+
+def verify_id_token(token: str, verifier: "IdTokenVerifier", nonce: Optional[str] = None,
+                    access_token: Optional[str] = None,
+                    max_age: Optional[float] = None) -> Optional[Dict[str, Any]]:
+    """
+    Verify an OpenID Connect ID token from an external identity provider.
+    
+    Args:
+        token: ID token returned by the provider
+        verifier: `IdTokenVerifier` holding the provider's keys, issuer and client id
+        nonce: Nonce sent in the authorization request, checked against the token
+        access_token: Access token issued alongside, checked against `at_hash`
+        max_age: Maximum seconds since the user authenticated (`auth_time`)
+        
+    Returns:
+        Optional[dict]: Claims if the token is valid, None otherwise
+        
+    Example:
+        >>> verifier = IdTokenVerifier(Jwks.from_file("idp-jwks.json"),
+        ...                            issuer="https://idp.example.com", client_id="haske-app")
+        >>> claims = verify_id_token(tokens["id_token"], verifier, nonce=session["nonce"])
+    """
+    if not HAS_RUST_OIDC:
+        raise RuntimeError("OpenID Connect support requires the haske native extension")
+    
+    try:
+        return verifier.verify(token, nonce=nonce, access_token=access_token, max_age=max_age)
+    except JwtError:
+        return None
+
 def create_password_hash(password: str) -> tuple:
     """
-    Create a password hash and salt.
+    Create a legacy PBKDF2-SHA256 password hash and salt.
     
     Args:
         password: Plain text password
@@ -129,24 +304,24 @@ def create_password_hash(password: str) -> tuple:
     Returns:
         tuple: (hash_bytes, salt_bytes) tuple
         
+    Note:
+        Kept for existing stored hashes. New code should use
+        `AuthManager.hash_password`, which stores the algorithm and cost
+        in a single PHC string.
+        
     Example:
         >>> hash_val, salt = create_password_hash("password123")
     """
-    if HAS_RUST_CRYPTO:
-        return hash_password(password)
-    else:
-        # Fallback Python implementation
-        import hashlib
-        import os
-        import binascii
-        
-        salt = os.urandom(16)
-        hash_val = hashlib.pbkdf2_hmac('sha256', password.encode(), salt, 100000)
-        return hash_val, salt
+    import hashlib
+    import os
+    
+    salt = os.urandom(16)
+    hash_val = hashlib.pbkdf2_hmac('sha256', password.encode(), salt, LEGACY_PBKDF2_ROUNDS)
+    return hash_val, salt
 
 def verify_password_hash(password: str, hash_val: bytes, salt: bytes) -> bool:
     """
-    Verify a password against a hash.
+    Verify a password against a legacy hash from `create_password_hash`.
     
     Args:
         password: Plain text password to verify
@@ -159,14 +334,29 @@ def verify_password_hash(password: str, hash_val: bytes, salt: bytes) -> bool:
     Example:
         >>> is_valid = verify_password_hash("password123", hash_val, salt)
     """
-    if HAS_RUST_CRYPTO:
-        return verify_password(password, hash_val, salt)
-    else:
-        # Fallback Python implementation
-        import hashlib
+    import hashlib
+    import hmac
+    
+    test_hash = hashlib.pbkdf2_hmac('sha256', password.encode(), salt, LEGACY_PBKDF2_ROUNDS)
+    return hmac.compare_digest(hash_val, test_hash)
+
+def legacy_hash_to_phc(hash_val: bytes, salt: bytes) -> str:
+    """
+    Encode a `create_password_hash` result as a PHC string.
+    
+    Args:
+        hash_val: Legacy password hash
+        salt: Legacy salt
         
-        test_hash = hashlib.pbkdf2_hmac('sha256', password.encode(), salt, 100000)
-        return hmac.compare_digest(hash_val, test_hash)
+    Returns:
+        str: `$pbkdf2-sha256$...` string accepted by `verify_password`
+    """
+    import base64
+    
+    def b64(data: bytes) -> str:
+        return base64.b64encode(data).decode().rstrip("=")
+    
+    return f"$pbkdf2-sha256$i={LEGACY_PBKDF2_ROUNDS},l={len(hash_val)}${b64(salt)}${b64(hash_val)}"
 
 def generate_csrf_token() -> str:
     """
@@ -209,6 +399,45 @@ def validate_csrf_token(token: str, expected: str) -> bool:
         result |= ord(x) ^ ord(y)
     return result == 0
 
+def api_key_required(*scopes: str):
+    """
+    Decorator to require an API key with the given scopes.
+    
+    The key is authenticated by `APIKeyMiddleware`, which must be installed;
+    its `ApiKey` record is available as `request.state.api_key`.
+    
+    Args:
+        *scopes: Scopes the key must grant, e.g. "posts:read"
+        
+    Returns:
+        Callable: Decorator function
+        
+    Raises:
+        AuthenticationError: If the request carries no valid API key
+        PermissionError: If the key lacks a required scope
+        
+    Example:
+        @app.route("/api/posts")
+        @api_key_required("posts:read")
+        async def list_posts(request):
+            return {"posts": [...]}
+    """
+    from functools import wraps
+    from .exceptions import AuthenticationError, PermissionError
+    
+    def decorator(handler):
+        @wraps(handler)
+        async def wrapper(request, *args, **kwargs):
+            api_key = getattr(request.state, "api_key", None)
+            if api_key is None:
+                raise AuthenticationError("API key required")
+            if not all(api_key.has_scope(scope) for scope in scopes):
+                raise PermissionError("API key lacks the required scopes")
+            return await handler(request, *args, **kwargs)
+        
+        return wrapper
+    return decorator
+
 class AuthManager:
     """
     Comprehensive authentication manager.
@@ -218,12 +447,21 @@ class AuthManager:
     
     Attributes:
         secret_key (str): Secret key for token signing
+        keyring (Keyring): `secret_key` plus retired keys, when the Rust extension is available
+        encrypt_sessions (bool): Whether session cookies are encrypted rather than only signed
+        session_store (SessionStore): Server-side store; when set the cookie only holds a session id
         session_cookie_name (str): Session cookie name, defaults to "session"
         session_expiry (int): Session expiration in seconds, defaults to 3600
+        password_policy (PasswordPolicy): Algorithm and cost for new password hashes
+        pepper (str): Optional server-side secret mixed into password hashes
+        policy (Policy): Role and attribute rules behind `can` and `permission_required`
     """
     
     def __init__(self, secret_key: str, session_cookie_name: str = "session", 
-                 session_expiry: int = 3600):
+                 session_expiry: int = 3600, password_policy: Optional["PasswordPolicy"] = None,
+                 pepper: Optional[str] = None, previous_secret_keys: Optional[List[str]] = None,
+                 encrypt_sessions: bool = False, session_store: Optional["SessionStore"] = None,
+                 policy: Union[str, "Policy", None] = None):
         """
         Initialize authentication manager.
         
@@ -231,10 +469,80 @@ class AuthManager:
             secret_key: Secret key for token signing
             session_cookie_name: Session cookie name, defaults to "session"
             session_expiry: Session expiration in seconds, defaults to 3600
+            password_policy: Policy for new password hashes, defaults to Argon2id
+            pepper: Server-side secret for password hashes, defaults to None
+            previous_secret_keys: Retired secret keys still accepted for
+                sessions issued before a rotation, defaults to None
+            encrypt_sessions: Encrypt session cookies so their contents stay
+                private, defaults to False; requires the Rust extension
+            session_store: Keep session data server-side in this store,
+                enabling revocation; defaults to None (data in the cookie)
+            policy: Authorization policy, or the path of a TOML/JSON policy
+                file; requires the Rust extension
         """
         self.secret_key = secret_key
+        self.keyring = Keyring(secret_key, list(previous_secret_keys or [])) if HAS_RUST_CRYPTO else None
+        self.encrypt_sessions = encrypt_sessions
+        self.session_store = session_store
+        if encrypt_sessions and not HAS_RUST_CRYPTO:
+            raise RuntimeError("encrypted sessions require the haske native extension")
         self.session_cookie_name = session_cookie_name
         self.session_expiry = session_expiry
+        self.password_policy = password_policy
+        self.pepper = pepper
+        if isinstance(policy, str):
+            if not HAS_RUST_POLICY:
+                raise RuntimeError("authorization policies require the haske native extension")
+            policy = Policy.from_file(policy)
+        self.policy = policy
+    
+    def hash_password(self, password: str) -> str:
+        """
+        Hash a password with the configured policy and pepper.
+        
+        Args:
+            password: Plain text password
+            
+        Returns:
+            str: PHC-format hash to store
+        """
+        if not HAS_RUST_PASSWORDS:
+            raise RuntimeError("password hashing requires the haske native extension")
+        return hash_password(password, self.password_policy, self.pepper)
+    
+    def verify_password(self, password: str, stored_hash: Any) -> Tuple[bool, Optional[str]]:
+        """
+        Verify a password and upgrade its hash when the policy has changed.
+        
+        Args:
+            password: Plain text password from the login form
+            stored_hash: Stored PHC/bcrypt string, or a legacy
+                `(hash, salt)` tuple from `create_password_hash`
+            
+        Returns:
+            tuple: `(valid, new_hash)`. `new_hash` is set when the password
+            is valid but the stored hash uses another algorithm or cost;
+            persist it in place of the old one.
+            
+        Example:
+            >>> valid, new_hash = auth.verify_password(form["password"], user.password_hash)
+            >>> if new_hash:
+            ...     user.password_hash = new_hash
+        """
+        if not HAS_RUST_PASSWORDS:
+            raise RuntimeError("password hashing requires the haske native extension")
+        
+        if isinstance(stored_hash, tuple):
+            # Legacy hashes predate the pepper.
+            valid = verify_password(password, legacy_hash_to_phc(*stored_hash))
+        else:
+            valid = verify_password(password, stored_hash, self.pepper)
+        if not valid:
+            return False, None
+        
+        if isinstance(stored_hash, tuple) or needs_rehash(stored_hash, self.password_policy):
+            return True, self.hash_password(password)
+        return True, None
     
     def create_session(self, response, user_id: Any, user_data: Dict[str, Any] = None) -> None:
         """
@@ -252,24 +560,28 @@ class AuthManager:
         if user_data:
             payload.update(user_data)
         
-        token = create_session_token(self.secret_key, payload, self.session_expiry)
+        if self.session_store is not None:
+            # Expiry is enforced (and extended) by the store, so the cookie
+            # lasts for the browser session.
+            session = self.session_store.create(payload, user_id=user_id)
+            self._set_session_cookie(response, session.id, None)
+            return
         
-        # Set cookie on response
-        response.set_cookie(
-            self.session_cookie_name,
-            token,
-            max_age=self.session_expiry,
-            httponly=True,
-            secure=True,  # Should be True in production
-            samesite="lax"
-        )
+        if self.encrypt_sessions:
+            token = create_encrypted_session_token(self.keyring, self.session_cookie_name, payload,
+                                                   self.session_expiry)
+        else:
+            token = create_session_token(self.keyring or self.secret_key, payload, self.session_expiry)
+        self._set_session_cookie(response, token, self.session_expiry)
     
-    def get_session(self, request) -> Optional[Dict[str, Any]]:
+    def get_session(self, request, response=None) -> Optional[Dict[str, Any]]:
         """
         Get session from request.
         
         Args:
             request: Request object
+            response: Optional response; when given, a session signed with a
+                retired key is re-issued with the current one
             
         Returns:
             Optional[dict]: Session data if valid, None otherwise
@@ -278,15 +590,93 @@ class AuthManager:
         if not token:
             return None
         
-        return verify_session_token(self.secret_key, token)
+        if self.session_store is not None:
+            session = self.session_store.get(token)
+            return session.data if session else None
+        if self.encrypt_sessions:
+            return verify_encrypted_session_token(self.keyring, self.session_cookie_name, token)
+        if self.keyring is None:
+            return verify_session_token(self.secret_key, token)
+        
+        verified = self.keyring.verify(token)
+        if verified is None:
+            return None
+        payload_str, kid = verified
+        payload = _load_session_payload(payload_str)
+        if payload is not None and response is not None and kid != self.keyring.active_kid:
+            # Keep the original expiry; only the signature changes.
+            remaining = int(payload.get("exp", time.time() + self.session_expiry) - time.time())
+            self._set_session_cookie(response, self.keyring.sign(payload_str), max(remaining, 0))
+        return payload
     
-    def clear_session(self, response) -> None:
+    def get_stored_session(self, request) -> Optional["Session"]:
+        """
+        Load the server-side session for a request.
+        
+        Only available with a `session_store`. Mutate `session.data` and
+        pass the session to `save_session` to persist changes.
+        
+        Args:
+            request: Request object
+            
+        Returns:
+            Optional[Session]: The live session, None if missing, expired or revoked
+        """
+        if self.session_store is None:
+            raise RuntimeError("get_stored_session requires a session_store")
+        token = request.cookies.get(self.session_cookie_name)
+        return self.session_store.get(token) if token else None
+    
+    def save_session(self, session: "Session") -> bool:
+        """
+        Persist changes to a server-side session.
+        
+        Args:
+            session: Session from `get_stored_session`
+            
+        Returns:
+            bool: True if written; unchanged sessions are not written
+        """
+        return self.session_store.save(session)
+    
+    def revoke_user_sessions(self, user_id: Any) -> int:
+        """
+        End every server-side session of a user ("log out everywhere").
+        
+        Args:
+            user_id: User identifier passed to `create_session`
+            
+        Returns:
+            int: Number of sessions revoked
+        """
+        if self.session_store is None:
+            raise RuntimeError("revoke_user_sessions requires a session_store")
+        return self.session_store.revoke_user(user_id)
+    
+    def _set_session_cookie(self, response, token: str, max_age: Optional[int]) -> None:
+        """Set the session cookie on a response."""
+        response.set_cookie(
+            self.session_cookie_name,
+            token,
+            max_age=max_age,
+            httponly=True,
+            secure=True,  # Should be True in production
+            samesite="lax"
+        )
+    
+    def clear_session(self, response, request=None) -> None:
         """
         Clear session cookie.
         
         Args:
             response: Response object to clear cookie from
+            request: Optional request; with a `session_store` its session
+                is revoked server-side as well
         """
+        if self.session_store is not None and request is not None:
+            token = request.cookies.get(self.session_cookie_name)
+            if token:
+                self.session_store.revoke(token)
         response.delete_cookie(self.session_cookie_name)
     
     def login_required(self, handler):
@@ -313,6 +703,18 @@ class AuthManager:
         
         @wraps(handler)
         async def wrapper(request, *args, **kwargs):
+            if self.session_store is not None:
+                stored = self.get_stored_session(request)
+                if stored is None:
+                    raise AuthenticationError("Authentication required")
+                
+                request.user = stored.data
+                try:
+                    return await handler(request, *args, **kwargs)
+                finally:
+                    # Written back only if the handler changed it.
+                    self.session_store.save(stored)
+            
             session = self.get_session(request)
             if not session:
                 raise AuthenticationError("Authentication required")
@@ -327,6 +729,8 @@ class AuthManager:
         """
         Decorator to require specific roles.
         
+        With a `policy`, roles inherited through it count as well.
+        
         Args:
             *roles: Required role names
             
@@ -344,7 +748,7 @@ class AuthManager:
                 return {"message": "Welcome admin"}
         """
         from functools import wraps
-        from .exceptions import PermissionError
+        from .exceptions import AuthenticationError, PermissionError
         
         def decorator(handler):
             @wraps(handler)
@@ -353,7 +757,10 @@ class AuthManager:
                 if not session:
                     raise AuthenticationError("Authentication required")
                 
-                user_roles = session.get("roles", [])
+                if self.policy is not None:
+                    user_roles = self.policy.effective_roles(session)
+                else:
+                    user_roles = session.get("roles", [])
                 if not any(role in user_roles for role in roles):
                     raise PermissionError("Insufficient permissions")
                 
@@ -361,4 +768,76 @@ class AuthManager:
                 return await handler(request, *args, **kwargs)
             
             return wrapper
-        return decorator
\ No newline at end of file
+        return decorator
+    
+    def can(self, subject: Any, action: str, resource: Any = None, context: Any = None) -> bool:
+        """
+        Check an action against the authorization policy.
+        
+        Args:
+            subject: The acting user, usually the session data
+            action: Action name, e.g. "posts:update"
+            resource: Object acted on, defaults to None
+            context: Extra attributes for `context.*` conditions, defaults to None
+            
+        Returns:
+            bool: True if the policy allows the action
+            
+        Example:
+            >>> auth.can(request.user, "posts:update", post)
+        """
+        return self._require_policy().can(subject, action, resource, context)
+    
+    def explain(self, subject: Any, action: str, resource: Any = None, context: Any = None) -> "Decision":
+        """
+        Like `can`, but return the policy's `Decision`, whose `rule`, `role`
+        and `reason` say what allowed or denied the action.
+        """
+        return self._require_policy().explain(subject, action, resource, context)
+    
+    def _require_policy(self) -> "Policy":
+        if self.policy is None:
+            raise RuntimeError("AuthManager has no authorization policy")
+        return self.policy
+    
+    def permission_required(self, action: str, resource: Any = None):
+        """
+        Decorator to require a permission from the authorization policy.
+        
+        Args:
+            action: Action name, e.g. "posts:update"
+            resource: Object the action applies to, or a callable taking the
+                request and returning it (awaited if it returns an awaitable),
+                defaults to None
+            
+        Returns:
+            Callable: Decorator function
+            
+        Raises:
+            AuthenticationError: If no valid session is found
+            PermissionError: If the policy denies the action
+            
+        Example:
+            @app.route("/posts/<int:post_id>", methods=["PUT"])
+            @auth.permission_required("posts:update", resource=load_post)
+            async def update_post(request):
+                ...
+        """
+        import inspect
+        from functools import wraps
+        from .exceptions import PermissionError
+        
+        policy = self._require_policy()
+        
+        def decorator(handler):
+            @wraps(handler)
+            async def check(request, *args, **kwargs):
+                target = resource(request) if callable(resource) else resource
+                if inspect.isawaitable(target):
+                    target = await target
+                if not policy.can(request.user, action, target):
+                    raise PermissionError("Insufficient permissions")
+                return await handler(request, *args, **kwargs)
+            
+            return self.login_required(check)
+        return decorator
@@ -6,7 +6,11 @@ This module provides high-performance caching using Rust-accelerated
 cache implementation with automatic fallback to Python.
 """
 
-from typing import Any, Optional, Union
+from typing import Any, Awaitable, Callable, Iterable, Optional, Union
+from collections import OrderedDict
+import asyncio
+import inspect
+import pickle
 import time
 
 # Import Rust cache if available
@@ -16,6 +20,12 @@ try:
 except ImportError:
     HAS_RUST_CACHE = False
 
+try:
+    from _haske_core import SharedCache
+    HAS_RUST_SHARED_CACHE = True
+except ImportError:
+    HAS_RUST_SHARED_CACHE = False
+
 class Cache:
     """
     High-performance cache with Rust acceleration.
@@ -24,23 +34,70 @@ class Cache:
     to Python implementation if Rust extensions are not available.
     """
     
-    def __init__(self, max_size: int = 1000, ttl: int = 300):
+    def __init__(self, max_size: int = 1000, ttl: int = 300, policy: str = "lru",
+                 max_weight: Optional[int] = None,
+                 weigher: Optional[Callable[[str, Any], int]] = None,
+                 shared: Optional[str] = None, slot_size: int = 4096,
+                 disk_path: Optional[str] = None, disk_max_size: int = 1 << 30):
         """
         Initialize cache.
         
         Args:
             max_size: Maximum number of items in cache
             ttl: Time to live in seconds for cache items
+            policy: Eviction policy, "lru", "lfu" or "tinylfu", defaults to
+                "lru"; the Python fallback always evicts LRU
+            max_weight: Maximum total weight of the items, defaults to None
+                (only `max_size` applies)
+            weigher: Function of (key, value) returning an item's weight;
+                defaults to the length of str and bytes values, 1 otherwise
+            shared: Name of a shared-memory segment holding the items, so
+                every worker process on the host opening the same name
+                shares them; values are pickled. Defaults to None (a cache
+                private to this process)
+            slot_size: Most bytes of key plus pickled value a shared cache
+                stores per item, defaults to 4096
+            disk_path: Directory of an on-disk tier holding pickled items
+                beyond memory and across restarts, defaults to None (memory
+                only). One process at a time may use a directory
+            disk_max_size: Most bytes the on-disk tier takes, defaults to 1 GiB
+        
+        Raises:
+            RuntimeError: If `shared` or `disk_path` is given without the
+                native extension
+            ValueError: If both `shared` and `disk_path` are given
         """
-        if HAS_RUST_CACHE:
-            self._rust_cache = RustCache(max_size, ttl)
+        if shared is not None and disk_path is not None:
+            raise ValueError("a shared cache cannot have an on-disk tier")
+        if disk_path is not None and not HAS_RUST_CACHE:
+            raise RuntimeError("on-disk caching requires the haske native extension")
+        self._shared_cache = None
+        if shared is not None:
+            if not HAS_RUST_SHARED_CACHE:
+                raise RuntimeError("shared caches require the haske native extension")
+            self._shared_cache = SharedCache(shared, max_entries=max_size,
+                                             slot_size=slot_size, time_to_live=ttl)
+            self._rust_cache = None
+            self._fallback_cache = None
+            self._loading = {}
+        elif HAS_RUST_CACHE:
+            self._rust_cache = RustCache(max_size, ttl, policy=policy,
+                                         max_weight=max_weight, weigher=weigher,
+                                         disk_path=disk_path, disk_max_size=disk_max_size)
             self._fallback_cache = None
         else:
             self._rust_cache = None
-            self._fallback_cache = {}
-            self._max_size = max_size
+            # key -> (value, expires_at, weight, tags), least recently used first
+            self._fallback_cache = OrderedDict()
+            # tag -> keys stored with it
+            self._tags = {}
+            self._max_size = max(max_size, 1)
             self._ttl = ttl
-            self._timestamps = {}
+            self._max_weight = max_weight
+            self._weigher = weigher
+            self._weight = 0
+            # key -> future of the load in progress
+            self._loading = {}
     
     def get(self, key: str) -> Optional[Any]:
         """
@@ -54,38 +111,147 @@ class Cache:
         """
         if self._rust_cache is not None:
             return self._rust_cache.get(key)
+        elif self._shared_cache is not None:
+            data = self._shared_cache.get(key)
+            return None if data is None else pickle.loads(data)
         else:
             # Fallback Python implementation
-            if key not in self._fallback_cache:
+            item = self._fallback_cache.get(key)
+            if item is None:
                 return None
                 
             # Check if expired
-            if time.time() - self._timestamps[key] > self._ttl:
+            if time.time() >= item[1]:
                 self.delete(key)
                 return None
                 
-            return self._fallback_cache[key]
+            self._fallback_cache.move_to_end(key)
+            return item[0]
     
-    def set(self, key: str, value: Any) -> None:
+    def set(self, key: str, value: Any, ttl: Optional[float] = None,
+            weight: Optional[int] = None, tags: Optional[Iterable[str]] = None) -> bool:
         """
         Set item in cache.
         
         Args:
             key: Cache key
             value: Value to cache
+            ttl: Time to live in seconds for this item, defaults to the cache's
+            weight: Weight of this item, defaults to the weigher's
+            tags: Tags for `invalidate_tag`, such as "user:42"; not
+                supported by shared caches
+            
+        Returns:
+            bool: False if the item was too heavy, did not fit a shared
+                cache's slot or was not admitted by the policy
         """
+        tags = None if tags is None else list(tags)
         if self._rust_cache is not None:
-            self._rust_cache.set(key, value)
+            return self._rust_cache.set(key, value, ttl=ttl, weight=weight, tags=tags)
+        elif self._shared_cache is not None:
+            if tags:
+                raise ValueError("shared caches do not support tags")
+            data = pickle.dumps(value, pickle.HIGHEST_PROTOCOL)
+            return self._shared_cache.set(key, data, ttl=ttl)
         else:
             # Fallback Python implementation
-            # Check if we need to evict due to size
-            if len(self._fallback_cache) >= self._max_size:
-                # Remove oldest item
-                oldest_key = min(self._timestamps.keys(), key=lambda k: self._timestamps[k])
-                self.delete(oldest_key)
+            if weight is None:
+                weight = self._weigh(key, value)
+            self.delete(key)
+            if self._max_weight is not None and weight > self._max_weight:
+                return False
             
-            self._fallback_cache[key] = value
-            self._timestamps[key] = time.time()
+            expires_at = time.time() + (self._ttl if ttl is None else ttl)
+            tags = frozenset(tags or ())
+            self._fallback_cache[key] = (value, expires_at, weight, tags)
+            self._weight += weight
+            for tag in tags:
+                self._tags.setdefault(tag, set()).add(key)
+            
+            # Evict least recently used items until both limits hold
+            while (len(self._fallback_cache) > self._max_size or
+                   (self._max_weight is not None and self._weight > self._max_weight)):
+                self.delete(next(iter(self._fallback_cache)))
+            return True
+    
+    async def get_or_set(self, key: str,
+                         loader: Callable[[], Union[Any, Awaitable[Any]]],
+                         ttl: Optional[float] = None, weight: Optional[int] = None,
+                         stale_ttl: float = 0, beta: float = 0,
+                         tags: Optional[Iterable[str]] = None) -> Any:
+        """
+        Get item from cache, loading and storing it on a miss.
+        
+        Concurrent calls for the same key run the loader once; the others
+        wait for its result, or its exception.
+        
+        Args:
+            key: Cache key
+            loader: Function called without arguments returning the value or
+                an awaitable of it
+            ttl: Time to live in seconds for the item, defaults to the cache's
+            weight: Weight of the item, defaults to the weigher's
+            stale_ttl: Seconds an expired item is still returned while it is
+                reloaded in the background, defaults to 0
+            beta: Probabilistic early expiration factor; above 0 a read may
+                reload the item in the background before it expires, more
+                likely the closer it is to expiring. 1.0 is a good start.
+                Ignored, like `stale_ttl`, by the Python fallback and
+                shared caches
+            tags: Tags for `invalidate_tag`, such as "user:42"
+            
+        Returns:
+            Any: Cached or loaded value
+            
+        Example:
+            user = await cache.get_or_set(f"user:{user_id}",
+                                          lambda: load_user(user_id), ttl=60)
+        """
+        tags = None if tags is None else list(tags)
+        if self._rust_cache is not None:
+            return await self._rust_cache.get_or_set(
+                key, loader, ttl=ttl, weight=weight, stale_ttl=stale_ttl, beta=beta,
+                tags=tags
+            )
+        else:
+            # Fallback Python implementation, also used by shared caches
+            value = self.get(key)
+            if value is not None:
+                return value
+            
+            loading = self._loading.get(key)
+            if loading is not None:
+                return await asyncio.shield(loading)
+            
+            loading = asyncio.get_running_loop().create_future()
+            self._loading[key] = loading
+            try:
+                value = loader()
+                if inspect.isawaitable(value):
+                    value = await value
+                self.set(key, value, ttl=ttl, weight=weight, tags=tags)
+            except BaseException as exc:
+                loading.set_exception(exc)
+                # Mark the exception retrieved when nobody waited for it
+                loading.exception()
+                raise
+            else:
+                loading.set_result(value)
+                return value
+            finally:
+                del self._loading[key]
+    
+    def _weigh(self, key: str, value: Any) -> int:
+        """Weight of an item in the Python fallback."""
+        if self._max_weight is None:
+            return 1
+        if self._weigher is not None:
+            return self._weigher(key, value)
+        if isinstance(value, str):
+            return len(value.encode())
+        if isinstance(value, (bytes, bytearray)):
+            return len(value)
+        return 1
     
     def delete(self, key: str) -> None:
         """
@@ -96,11 +262,58 @@ class Cache:
         """
         if self._rust_cache is not None:
             self._rust_cache.delete(key)
+        elif self._shared_cache is not None:
+            self._shared_cache.delete(key)
+        else:
+            # Fallback Python implementation
+            item = self._fallback_cache.pop(key, None)
+            if item is not None:
+                self._weight -= item[2]
+                for tag in item[3]:
+                    keys = self._tags[tag]
+                    keys.discard(key)
+                    if not keys:
+                        del self._tags[tag]
+    
+    def invalidate_tag(self, tag: str) -> int:
+        """
+        Delete every item stored with a tag.
+        
+        Args:
+            tag: Tag given to `set` or `get_or_set`
+            
+        Returns:
+            int: Number of items deleted
+            
+        Raises:
+            ValueError: If the cache is shared
+            
+        Example:
+            cache.set(f"user:{user.id}:profile", html, tags=[f"user:{user.id}"])
+            cache.invalidate_tag(f"user:{user.id}")  # after saving the user
+        """
+        if self._rust_cache is not None:
+            return self._rust_cache.invalidate_tag(tag)
+        elif self._shared_cache is not None:
+            raise ValueError("shared caches do not support tags")
         else:
             # Fallback Python implementation
-            if key in self._fallback_cache:
-                del self._fallback_cache[key]
-                del self._timestamps[key]
+            keys = list(self._tags.get(tag, ()))
+            for key in keys:
+                self.delete(key)
+            return len(keys)
+    
+    def namespace(self, name: str) -> "CacheNamespace":
+        """
+        Get a view of the cache whose keys are prefixed with `name:`.
+        
+        Args:
+            name: Namespace name, such as "users"
+            
+        Returns:
+            CacheNamespace: View sharing this cache's capacity
+        """
+        return CacheNamespace(self, name)
     
     def clear(self) -> None:
         """
@@ -108,10 +321,21 @@ class Cache:
         """
         if self._rust_cache is not None:
             self._rust_cache.clear()
+        elif self._shared_cache is not None:
+            self._shared_cache.clear()
         else:
             # Fallback Python implementation
             self._fallback_cache.clear()
-            self._timestamps.clear()
+            self._tags.clear()
+            self._weight = 0
+    
+    def compact(self) -> None:
+        """
+        Reclaim the space of replaced, deleted and expired items in the
+        on-disk tier; does nothing without one.
+        """
+        if self._rust_cache is not None:
+            self._rust_cache.compact()
     
     def size(self) -> int:
         """
@@ -122,8 +346,110 @@ class Cache:
         """
         if self._rust_cache is not None:
             return self._rust_cache.size()
+        elif self._shared_cache is not None:
+            return self._shared_cache.size()
         else:
             return len(self._fallback_cache)
+    
+    def _keys(self) -> Iterable[str]:
+        """Keys of the items, for namespaces without a Rust view."""
+        if self._shared_cache is not None:
+            return self._shared_cache.keys()
+        return list(self._fallback_cache)
+
+class CacheNamespace:
+    """
+    View of a `Cache` whose keys are prefixed with `name:`.
+    
+    Namespaces share the cache's capacity and eviction. Tags are not
+    prefixed: `Cache.invalidate_tag` deletes tagged items in every
+    namespace, while a namespace's `invalidate_tag` and `clear` only delete
+    its own items.
+    """
+    
+    def __init__(self, cache: Cache, name: str):
+        """
+        Initialize namespace; prefer `Cache.namespace(name)`.
+        
+        Args:
+            cache: Cache holding the items
+            name: Namespace name
+        
+        Raises:
+            ValueError: If name is empty
+        """
+        if not name:
+            raise ValueError("namespace name must not be empty")
+        self.name = name
+        self._cache = cache
+        self._prefix = f"{name}:"
+        if cache._rust_cache is not None:
+            self._rust_namespace = cache._rust_cache.namespace(name)
+        else:
+            self._rust_namespace = None
+    
+    def get(self, key: str) -> Optional[Any]:
+        """Get item from the namespace, None if not found/expired."""
+        if self._rust_namespace is not None:
+            return self._rust_namespace.get(key)
+        return self._cache.get(self._prefix + key)
+    
+    def set(self, key: str, value: Any, ttl: Optional[float] = None,
+            weight: Optional[int] = None, tags: Optional[Iterable[str]] = None) -> bool:
+        """Set item in the namespace; see `Cache.set`."""
+        if self._rust_namespace is not None:
+            tags = None if tags is None else list(tags)
+            return self._rust_namespace.set(key, value, ttl=ttl, weight=weight, tags=tags)
+        return self._cache.set(self._prefix + key, value, ttl=ttl, weight=weight, tags=tags)
+    
+    async def get_or_set(self, key: str,
+                         loader: Callable[[], Union[Any, Awaitable[Any]]],
+                         ttl: Optional[float] = None, weight: Optional[int] = None,
+                         stale_ttl: float = 0, beta: float = 0,
+                         tags: Optional[Iterable[str]] = None) -> Any:
+        """Get item from the namespace, loading it on a miss; see `Cache.get_or_set`."""
+        return await self._cache.get_or_set(
+            self._prefix + key, loader, ttl=ttl, weight=weight,
+            stale_ttl=stale_ttl, beta=beta, tags=tags
+        )
+    
+    def delete(self, key: str) -> None:
+        """Delete item from the namespace."""
+        self._cache.delete(self._prefix + key)
+    
+    def invalidate_tag(self, tag: str) -> int:
+        """Delete the namespace's items stored with a tag, returning how many."""
+        if self._rust_namespace is not None:
+            return self._rust_namespace.invalidate_tag(tag)
+        if self._cache._shared_cache is not None:
+            raise ValueError("shared caches do not support tags")
+        keys = [key for key in self._cache._tags.get(tag, ())
+                if key.startswith(self._prefix)]
+        for key in keys:
+            self._cache.delete(key)
+        return len(keys)
+    
+    def namespace(self, name: str) -> "CacheNamespace":
+        """Get a nested namespace, with keys prefixed with `self.name:name:`."""
+        if not name:
+            raise ValueError("namespace name must not be empty")
+        return CacheNamespace(self._cache, f"{self.name}:{name}")
+    
+    def clear(self) -> None:
+        """Delete every item in the namespace."""
+        if self._rust_namespace is not None:
+            self._rust_namespace.clear()
+        else:
+            for key in [key for key in self._cache._keys()
+                        if key.startswith(self._prefix)]:
+                self._cache.delete(key)
+    
+    def size(self) -> int:
+        """Get the number of items in the namespace."""
+        if self._rust_namespace is not None:
+            return self._rust_namespace.size()
+        return sum(1 for key in self._cache._keys()
+                   if key.startswith(self._prefix))
 
 # Global cache instance
 _default_cache = None
@@ -7,7 +7,7 @@ scenarios and corresponding error handlers for consistent error responses.
 """
 
 from starlette.exceptions import HTTPException
-from typing import Any, Dict, Optional
+from typing import Any, Dict, List, Optional
 
 class HaskeError(HTTPException):
     """
@@ -43,17 +43,23 @@ class ValidationError(HaskeError):
     Validation error for invalid request data.
     
     Raised when request data fails validation against a schema.
+    
+    Attributes:
+        errors (list): Structured errors, each a dict with `pointer`,
+            `keyword` and `message` as produced by `JsonSchema.validate`
     """
     
-    def __init__(self, detail: Any = None, **kwargs):
+    def __init__(self, detail: Any = None, errors: Optional[List[Dict[str, Any]]] = None, **kwargs):
         """
         Initialize validation error.
         
         Args:
             detail: Validation error details
+            errors: Structured validation errors, defaults to an empty list
             **kwargs: Additional validation context
         """
         super().__init__(detail or "Validation error", 400, "VALIDATION_ERROR", **kwargs)
+        self.errors = list(errors or [])
 
 class AuthenticationError(HaskeError):
     """
@@ -167,6 +173,10 @@ def haske_error_handler(request, exc: HaskeError):
     if exc.extra:
         response_data["error"]["details"] = exc.extra
     
+    # Structured validation errors are passed through untouched.
+    if getattr(exc, "errors", None):
+        response_data["error"]["errors"] = exc.errors
+    
     return JSONResponse(response_data, status_code=exc.status_code)
 
 def http_error_handler(request, exc: HTTPException):
@@ -199,7 +209,8 @@ def validation_error_handler(request, exc: ValidationError):
         exc: ValidationError instance
         
     Returns:
-        JSONResponse: Formatted validation error response
+        JSONResponse: Formatted validation error response, with structured
+        `errors` emitted as-is under `error.errors`
     """
     return haske_error_handler(request, exc)
 
@@ -213,12 +224,6 @@ def install_error_handlers(app: 'Haske'):
     Example:
         >>> install_error_handlers(app)
     """
-    from starlette.exceptions import HTTPException as StarletteHTTPException
-    
-    app.middleware_stack.append(
-        Middleware(StarletteHTTPException, handlers={
-            HaskeError: haske_error_handler,
-            ValidationError: validation_error_handler,
-            StarletteHTTPException: http_error_handler,
-        })
-    )
\ No newline at end of file
+    app.add_exception_handler(HaskeError, haske_error_handler)
+    app.add_exception_handler(ValidationError, validation_error_handler)
+    app.add_exception_handler(HTTPException, http_error_handler)
\ No newline at end of file
@@ -7,11 +7,14 @@ and custom middleware implementations for enhanced functionality.
 """
 
 import time
+import fnmatch
+import inspect
+from contextvars import ContextVar
 from starlette.middleware import Middleware as StarletteMiddleware
 from starlette.middleware.sessions import SessionMiddleware
 from starlette.middleware.cors import CORSMiddleware
 from starlette.middleware.gzip import GZipMiddleware
-from typing import Callable, Any
+from typing import Callable, Any, Iterable, Optional
 
 # Import Rust compression if available
 try:
@@ -20,6 +23,24 @@ try:
 except ImportError:
     HAS_RUST_COMPRESSION = False
 
+# Import Rust CSRF protection if available
+try:
+    from _haske_core import (CsrfGuard, MultipartParser, MultipartError, MultipartLimitError,
+                             parse_cookie_header, parse_query_string)
+    HAS_RUST_CSRF = True
+except ImportError:
+    HAS_RUST_CSRF = False
+
+# Import Rust API keys if available
+try:
+    from _haske_core import ApiKeys
+    HAS_RUST_API_KEYS = True
+except ImportError:
+    HAS_RUST_API_KEYS = False
+
+# Guard and cookie of the request being handled, for `csrf_token()`
+_csrf_state: ContextVar[Optional[tuple]] = ContextVar("haske_csrf_state", default=None)
+
 class Middleware(StarletteMiddleware):
     """
     Haske Middleware wrapper around Starlette's Middleware.
@@ -331,4 +352,398 @@ class RateLimitMiddlewareFactory:
         Returns:
             tuple: (Middleware class, options dictionary)
         """
-        return self.middleware_cls, self.options
\ No newline at end of file
+        return self.middleware_cls, self.options
+
+def csrf_token() -> str:
+    """
+    Return a CSRF token for the current request.
+    
+    Each call returns a differently masked token for the same secret, so it
+    is safe to render into every form. Available as the `csrf_token()`
+    template global.
+    
+    Returns:
+        str: Token for the `csrf_token` form field or `X-CSRF-Token` header
+        
+    Raises:
+        RuntimeError: If called outside a request handled by `CSRFMiddleware`
+        
+    Example:
+        <input type="hidden" name="csrf_token" value="{{ csrf_token() }}">
+    """
+    state = _csrf_state.get()
+    if state is None:
+        raise RuntimeError("csrf_token() requires CSRFMiddleware")
+    guard, cookie = state
+    return guard.mask(cookie)
+
+class CSRFMiddleware:
+    """
+    CSRF protection middleware.
+    
+    Unsafe requests (anything but GET, HEAD, OPTIONS and TRACE) must come
+    from a trusted Origin/Referer and carry a token from `csrf_token()`,
+    either in the `X-CSRF-Token` header or in the `csrf_token` field of a
+    url-encoded or multipart form. The per-client secret lives in a signed
+    cookie that is set on the first response.
+    
+    Attributes:
+        app: ASGI application
+        guard: Rust `CsrfGuard` that issues and checks tokens
+        exempt: Path patterns (fnmatch syntax) that skip the checks
+    """
+    
+    SAFE_METHODS = frozenset({"GET", "HEAD", "OPTIONS", "TRACE"})
+    
+    def __init__(self, app, secret_key: Any, exempt: Iterable[str] = (),
+                 trusted_origins: Iterable[str] = (), cookie_name: str = "csrftoken",
+                 header_name: str = "X-CSRF-Token", field_name: str = "csrf_token",
+                 cookie_secure: bool = True, cookie_samesite: str = "lax",
+                 cookie_max_age: int = 365 * 24 * 3600, max_body_size: int = 1024 * 1024):
+        """
+        Initialize CSRF middleware.
+        
+        Args:
+            app: ASGI application to wrap
+            secret_key: Secret or `Keyring` used to sign the CSRF cookie
+            exempt: Paths or patterns such as "/webhooks/*" that are not checked
+            trusted_origins: Extra origins allowed to post, e.g. "https://*.example.com"
+            cookie_name: Cookie holding the signed secret, defaults to "csrftoken"
+            header_name: Header carrying the token, defaults to "X-CSRF-Token"
+            field_name: Form field carrying the token, defaults to "csrf_token"
+            cookie_secure: Send the cookie over HTTPS only, defaults to True
+            cookie_samesite: SameSite attribute of the cookie, defaults to "lax"
+            cookie_max_age: Cookie lifetime in seconds, defaults to one year
+            max_body_size: Most body bytes read while looking for the form
+                token, defaults to 1 MiB; larger forms get a 413
+        """
+        if not HAS_RUST_CSRF:
+            raise RuntimeError("CSRFMiddleware requires the haske native extension")
+        self.app = app
+        self.guard = CsrfGuard(secret_key, list(trusted_origins))
+        self.exempt = list(exempt)
+        self.cookie_name = cookie_name
+        self.header_name = header_name.lower()
+        self.field_name = field_name
+        self.cookie_secure = cookie_secure
+        self.cookie_samesite = cookie_samesite
+        self.cookie_max_age = cookie_max_age
+        self.max_body_size = max_body_size
+    
+    async def __call__(self, scope, receive, send) -> None:
+        """
+        ASGI middleware implementation.
+        
+        Args:
+            scope: ASGI scope
+            receive: ASGI receive function
+            send: ASGI send function
+            
+        Returns:
+            None: Processes request or returns a 403 error
+        """
+        if scope["type"] != "http":
+            return await self.app(scope, receive, send)
+        
+        headers = {name.decode("latin-1").lower(): value.decode("latin-1")
+                   for name, value in scope.get("headers", [])}
+        cookie = parse_cookie_header(headers.get("cookie", "")).get(self.cookie_name)
+        new_cookie = None
+        if cookie is None or not self.guard.is_valid_cookie(cookie):
+            cookie = new_cookie = self.guard.new_cookie()
+        
+        if scope["method"] not in self.SAFE_METHODS and not self._is_exempt(scope["path"]):
+            status, reason, receive = await self._check(scope, receive, headers,
+                                                        new_cookie is None, cookie)
+            if reason is not None:
+                from starlette.responses import JSONResponse
+                response = JSONResponse(
+                    {"error": f"CSRF verification failed: {reason}"},
+                    status_code=status
+                )
+                return await response(scope, receive, send)
+        
+        async def send_with_cookie(message):
+            if message["type"] == "http.response.start" and new_cookie is not None:
+                message = dict(message)
+                message["headers"] = list(message.get("headers", [])) + [
+                    (b"set-cookie", self._cookie_header(new_cookie).encode("latin-1"))
+                ]
+            await send(message)
+        
+        state = _csrf_state.set((self.guard, cookie))
+        try:
+            await self.app(scope, receive, send_with_cookie)
+        finally:
+            _csrf_state.reset(state)
+    
+    def _is_exempt(self, path: str) -> bool:
+        """Check whether a path matches an exemption pattern."""
+        return any(fnmatch.fnmatchcase(path, pattern) for pattern in self.exempt)
+    
+    async def _check(self, scope, receive, headers, has_cookie: bool, cookie: str):
+        """
+        Validate an unsafe request.
+        
+        Returns:
+            tuple: (failure status or None, failure reason or None, receive
+            function for the app)
+        """
+        host = headers.get("host") or "{}:{}".format(*scope.get("server", ("localhost", 80)))
+        reason = self.guard.check_origin(headers.get("origin"), headers.get("referer"),
+                                         f"{scope.get('scheme', 'http')}://{host}")
+        if reason is not None:
+            return 403, reason, receive
+        if not has_cookie:
+            return 403, "CSRF cookie missing", receive
+        
+        token = headers.get(self.header_name)
+        if token is None:
+            try:
+                token, receive = await self._form_token(receive, headers.get("content-type", ""))
+            except MultipartLimitError:
+                return 413, "form body too large", receive
+        if not token:
+            return 403, "CSRF token missing", receive
+        if not self.guard.verify(cookie, token):
+            return 403, "CSRF token incorrect", receive
+        return None, None, receive
+    
+    async def _form_token(self, receive, content_type: str):
+        """
+        Read the token from a form body, which is then replayed to the app.
+        
+        Multipart bodies are only read up to the token field; the rest is
+        left on the channel for the app. At most `max_body_size` bytes are
+        read.
+        
+        Returns:
+            tuple: (token or None, receive function replaying the body)
+            
+        Raises:
+            MultipartLimitError: If the token is not found within `max_body_size`
+        """
+        is_urlencoded = "application/x-www-form-urlencoded" in content_type
+        if not is_urlencoded and "multipart/form-data" not in content_type:
+            return None, receive
+        
+        parser = None
+        if not is_urlencoded:
+            try:
+                # Uploads before the token stay in memory, bounded by max_body_size
+                parser = MultipartParser(content_type, max_total_size=self.max_body_size,
+                                         spool_size=self.max_body_size)
+            except (MultipartError, ValueError):
+                return None, receive
+        
+        chunks = []
+        size = 0
+        token = None
+        more_body = True
+        while more_body:
+            message = await receive()
+            if message["type"] != "http.request":
+                return None, receive
+            chunk = message.get("body", b"")
+            chunks.append(chunk)
+            more_body = message.get("more_body", False)
+            size += len(chunk)
+            if size > self.max_body_size:
+                raise MultipartLimitError(f"form body exceeds {self.max_body_size} bytes")
+            if parser is not None:
+                try:
+                    parser.feed(chunk)
+                except MultipartLimitError:
+                    raise
+                except (MultipartError, ValueError):
+                    break
+                token = parser.field(self.field_name)
+                if token is not None:
+                    break
+        body = b"".join(chunks)
+        
+        replayed = False
+        
+        async def replay():
+            nonlocal replayed
+            if replayed:
+                return await receive()
+            replayed = True
+            return {"type": "http.request", "body": body, "more_body": more_body}
+        
+        if is_urlencoded:
+            return parse_query_string(body).get(self.field_name), replay
+        return token, replay
+    
+    def _cookie_header(self, value: str) -> str:
+        """Build the Set-Cookie header for a new CSRF cookie."""
+        parts = [f"{self.cookie_name}={value}", "Path=/", f"Max-Age={self.cookie_max_age}",
+                 "HttpOnly", f"SameSite={self.cookie_samesite}"]
+        if self.cookie_secure:
+            parts.append("Secure")
+        return "; ".join(parts)
+
+class CSRFMiddlewareFactory:
+    """
+    Factory for CSRF middleware configuration.
+    
+    Example:
+        CSRFMiddlewareFactory(secret_key="...", exempt=["/webhooks/*"])
+    """
+    
+    def __init__(self, secret_key: Any, **options):
+        """
+        Initialize CSRF middleware factory.
+        
+        Args:
+            secret_key: Secret or `Keyring` used to sign the CSRF cookie
+            **options: Additional `CSRFMiddleware` options
+        """
+        self.middleware_cls = CSRFMiddleware
+        self.options = {"secret_key": secret_key, **options}
+    
+    def __call__(self):
+        """
+        Create CSRF middleware instance.
+        
+        Returns:
+            tuple: (Middleware class, options dictionary)
+        """
+        return self.middleware_cls, self.options
+
+class APIKeyMiddleware:
+    """
+    API key authentication middleware.
+    
+    Reads a key from `Authorization: Bearer <key>` or the `X-API-Key`
+    header, verifies it with an `ApiKeys` instance and stores the matching
+    `ApiKey` record as `request.state.api_key`. Requests with an invalid or
+    expired key get a 401; requests without one pass through unauthenticated
+    (`request.state.api_key` is None) unless `required` is set. Bearer tokens
+    that are not API keys, such as JWTs, are left for other authentication.
+    
+    Attributes:
+        app: ASGI application
+        api_keys: Rust `ApiKeys` that parses and verifies keys
+        lookup: Callable returning the stored `ApiKey` for a key id
+        required: Whether requests without a key are rejected
+        exempt: Path patterns (fnmatch syntax) that never require a key
+    """
+    
+    def __init__(self, app, api_keys: "ApiKeys", lookup: Optional[Callable] = None,
+                 header_name: str = "X-API-Key", required: bool = False,
+                 exempt: Iterable[str] = ()):
+        """
+        Initialize API key middleware.
+        
+        Args:
+            app: ASGI application to wrap
+            api_keys: `ApiKeys` instance the keys were minted with
+            lookup: Function (sync or async) taking a key id and returning
+                its stored `ApiKey` or None; defaults to the in-memory
+                registry of `api_keys`
+            header_name: Header carrying the key, defaults to "X-API-Key"
+            required: Reject requests without a key, defaults to False
+            exempt: Paths or patterns such as "/health" that never require a key
+        """
+        if not HAS_RUST_API_KEYS:
+            raise RuntimeError("APIKeyMiddleware requires the haske native extension")
+        self.app = app
+        self.api_keys = api_keys
+        self.lookup = lookup
+        self.header_name = header_name.lower()
+        self.required = required
+        self.exempt = list(exempt)
+    
+    async def __call__(self, scope, receive, send) -> None:
+        """
+        ASGI middleware implementation.
+        
+        Args:
+            scope: ASGI scope
+            receive: ASGI receive function
+            send: ASGI send function
+            
+        Returns:
+            None: Processes request or returns a 401 error
+        """
+        if scope["type"] != "http":
+            return await self.app(scope, receive, send)
+        
+        headers = {name.decode("latin-1").lower(): value.decode("latin-1")
+                   for name, value in scope.get("headers", [])}
+        key = self._presented_key(headers)
+        record = None
+        if key is not None:
+            record = await self._authenticate(key)
+            if record is None:
+                return await self._reject(scope, receive, send, "Invalid API key")
+        elif self.required and not any(fnmatch.fnmatchcase(scope["path"], pattern)
+                                       for pattern in self.exempt):
+            return await self._reject(scope, receive, send, "API key required")
+        
+        scope.setdefault("state", {})["api_key"] = record
+        return await self.app(scope, receive, send)
+    
+    def _presented_key(self, headers) -> Optional[str]:
+        """Find the API key in the request headers."""
+        key = headers.get(self.header_name)
+        if key:
+            return key.strip()
+        scheme, _, token = headers.get("authorization", "").partition(" ")
+        token = token.strip()
+        if scheme.lower() == "bearer" and self.api_keys.key_id(token) is not None:
+            return token
+        return None
+    
+    async def _authenticate(self, key: str):
+        """Return the stored record for a valid key, or None."""
+        if self.lookup is None:
+            return self.api_keys.authenticate(key)
+        key_id = self.api_keys.key_id(key)
+        if key_id is None:
+            return None
+        record = self.lookup(key_id)
+        if inspect.isawaitable(record):
+            record = await record
+        if record is None or not self.api_keys.verify(key, record):
+            return None
+        return record
+    
+    async def _reject(self, scope, receive, send, message: str) -> None:
+        """Send a 401 response."""
+        from starlette.responses import JSONResponse
+        response = JSONResponse(
+            {"error": message},
+            status_code=401,
+            headers={"WWW-Authenticate": "Bearer"}
+        )
+        return await response(scope, receive, send)
+
+class APIKeyMiddlewareFactory:
+    """
+    Factory for API key middleware configuration.
+    
+    Example:
+        APIKeyMiddlewareFactory(api_keys=ApiKeys("secret"), lookup=find_api_key)
+    """
+    
+    def __init__(self, api_keys: "ApiKeys", **options):
+        """
+        Initialize API key middleware factory.
+        
+        Args:
+            api_keys: `ApiKeys` instance the keys were minted with
+            **options: Additional `APIKeyMiddleware` options
+        """
+        self.middleware_cls = APIKeyMiddleware
+        self.options = {"api_keys": api_keys, **options}
+    
+    def __call__(self):
+        """
+        Create API key middleware instance.
+        
+        Returns:
+            tuple: (Middleware class, options dictionary)
+        """
+        return self.middleware_cls, self.options
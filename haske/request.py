@@ -8,6 +8,7 @@ JSON parsing, form handling, and convenient access to request data.
 
 import json
 from typing import Dict, Any, Optional
+from starlette.datastructures import State
 from starlette.requests import Request as StarletteReq
 
 # Import Rust JSON functions if available
@@ -17,6 +18,27 @@ try:
 except ImportError:
     HAS_RUST_JSON = False
 
+# Import Rust JSON Schema validator if available
+try:
+    from _haske_core import JsonSchema, compile_schema
+    HAS_RUST_SCHEMA = True
+except ImportError:
+    HAS_RUST_SCHEMA = False
+
+# Import Rust query string and cookie parsers if available
+try:
+    from _haske_core import parse_query_string, parse_cookie_header
+    HAS_RUST_PARSERS = True
+except ImportError:
+    HAS_RUST_PARSERS = False
+
+# Import Rust multipart parser if available
+try:
+    from _haske_core import MultipartParser, MultipartError, MultipartLimitError
+    HAS_RUST_MULTIPART = True
+except ImportError:
+    HAS_RUST_MULTIPART = False
+
 class Request:
     """
     Enhanced request class with Rust acceleration.
@@ -33,6 +55,7 @@ class Request:
         _json: Cached JSON data
         _form: Cached form data
         _cookies: Cached cookies
+        _query_params: Cached query parameters
     """
     
     def __init__(self, scope, receive, send, path_params: Dict[str, Any] = None, body_bytes: bytes = None):
@@ -54,6 +77,8 @@ class Request:
         self._json = None
         self._form = None
         self._cookies = None
+        self._query_params = None
+        self._state = None
 
     @property
     def method(self) -> str:
@@ -75,6 +100,18 @@ class Request:
         """
         return self.scope["path"]
 
+    @property
+    def state(self) -> State:
+        """
+        Get request state, shared with middleware through `scope["state"]`.
+
+        Returns:
+            State: Attribute access to the request state
+        """
+        if self._state is None:
+            self._state = State(self.scope.setdefault("state", {}))
+        return self._state
+
     def get_path_param(self, key: str, default: Any = None) -> Any:
         """
         Get path parameter by key.
@@ -153,26 +190,81 @@ class Request:
         body = await self.body()
         return body.decode("utf-8", errors="replace")
 
-    async def form(self) -> Dict[str, Any]:
+    async def form(self, max_part_size: Optional[int] = None,
+                   max_total_size: Optional[int] = None,
+                   spool_size: int = 1024 * 1024,
+                   max_field_size: int = 1024 * 1024,
+                   max_parts: int = 1000) -> Dict[str, Any]:
         """
         Parse request body as form data.
         
+        Args:
+            max_part_size: Largest accepted multipart part in bytes, defaults to no limit
+            max_total_size: Largest accepted multipart body in bytes, defaults to no limit
+            spool_size: Uploads larger than this are moved to a temporary file
+            max_field_size: Largest accepted non-file multipart field in bytes, defaults to 1 MiB
+            max_parts: Most multipart parts accepted, defaults to 1000
+        
         Returns:
-            Dict[str, Any]: Parsed form data
+            Dict[str, Any]: Parsed form data; repeated names map to lists
+            
+        Raises:
+            HaskeError: 400 for a malformed multipart body, 413 when a limit is exceeded
             
         Note:
-            Only works for application/x-www-form-urlencoded content type
+            Handles application/x-www-form-urlencoded and multipart/form-data.
+            Multipart bodies are parsed by the Rust extension as they arrive;
+            file parts become `UploadFile` objects.
         """
         if self._form is None:
-            body = await self.text()
-            if "application/x-www-form-urlencoded" in self.headers.get("content-type", ""):
+            content_type = self.headers.get("content-type", "")
+            if "application/x-www-form-urlencoded" in content_type:
                 from urllib.parse import parse_qs
+                body = await self.text()
                 self._form = {k: v[0] if len(v) == 1 else v for k, v in parse_qs(body).items()}
+            elif "multipart/form-data" in content_type and HAS_RUST_MULTIPART:
+                pairs = await self._parse_multipart(content_type, max_part_size, max_total_size, spool_size,
+                                                    max_field_size, max_parts)
+                self._form = {}
+                for name, value in pairs:
+                    if name not in self._form:
+                        self._form[name] = value
+                    elif isinstance(self._form[name], list):
+                        self._form[name].append(value)
+                    else:
+                        self._form[name] = [self._form[name], value]
             else:
                 self._form = {}
         # self._form = dict(self._form)
         return self._form
 
+    async def _parse_multipart(self, content_type: str, max_part_size: Optional[int],
+                               max_total_size: Optional[int], spool_size: int,
+                               max_field_size: int, max_parts: int):
+        """
+        Feed the body to the Rust multipart parser, streaming from `receive`
+        unless the body was already read.
+        """
+        from .exceptions import HaskeError
+        try:
+            parser = MultipartParser(content_type, max_part_size, max_total_size, spool_size,
+                                     max_field_size, max_parts)
+            if self._body is not None:
+                parser.feed(self._body)
+            else:
+                more_body = True
+                while more_body:
+                    message = await self.receive()
+                    parser.feed(message.get("body", b""))
+                    more_body = message.get("more_body", False)
+                # The body was consumed without being kept
+                self._body = b""
+            return parser.finish()
+        except MultipartLimitError as e:
+            raise HaskeError(str(e), 413, "PAYLOAD_TOO_LARGE")
+        except MultipartError as e:
+            raise HaskeError(str(e), 400, "INVALID_MULTIPART")
+
     @property
     def headers(self) -> Dict[str, str]:
         """
@@ -189,27 +281,47 @@ class Request:
         Get request cookies.
         
         Returns:
-            Dict[str, str]: Cookies dictionary
+            Dict[str, str]: Cookies mapping; a `MultiDict` when the Rust
+            extension is available, so repeated names are kept
+            
+        Note:
+            Parsed once per request
         """
         if self._cookies is None:
-            self._cookies = {}
             cookie_header = self.headers.get("cookie", "")
-            if cookie_header:
-                from http.cookies import SimpleCookie
-                c = SimpleCookie()
-                c.load(cookie_header)
-                self._cookies = {k: v.value for k, v in c.items()}
+            if HAS_RUST_PARSERS:
+                self._cookies = parse_cookie_header(cookie_header)
+            else:
+                self._cookies = {}
+                if cookie_header:
+                    from http.cookies import SimpleCookie
+                    c = SimpleCookie()
+                    c.load(cookie_header)
+                    self._cookies = {k: v.value for k, v in c.items()}
         return self._cookies
 
     @property
     def query_params(self) -> Dict[str, Any]:
         """
-        Get query parameters as string.
+        Get query parameters.
         
         Returns:
-            str: Raw query string
+            Dict[str, Any]: Query parameter mapping; a `MultiDict` with
+            `get`, `getlist`, `get_int` and `get_bool` when the Rust
+            extension is available
+            
+        Note:
+            Parsed once per request
         """
-        return self.scope.get("query_string", b"").decode()
+        if self._query_params is None:
+            query_string = self.scope.get("query_string", b"")
+            if HAS_RUST_PARSERS:
+                self._query_params = parse_query_string(query_string)
+            else:
+                from urllib.parse import parse_qs
+                params = parse_qs(query_string.decode(), keep_blank_values=True)
+                self._query_params = {k: v[0] for k, v in params.items()}
+        return self._query_params
 
     def get_query_param(self, key: str, default: Any = None) -> Any:
         """
@@ -220,15 +332,12 @@ class Request:
             default: Default value if parameter not found
             
         Returns:
-            Any: Query parameter value or default
+            Any: First value of the query parameter, or default
             
         Example:
             >>> page = request.get_query_param("page", 1)
         """
-        from urllib.parse import parse_qs
-        query_string = self.scope.get("query_string", b"").decode()
-        params = parse_qs(query_string)
-        return params.get(key, [default])[0] if params.get(key) else default
+        return self.query_params.get(key, default)
 
     def is_json(self) -> bool:
         """
@@ -300,17 +409,37 @@ class Request:
         Validate JSON against a schema.
         
         Args:
-            schema: Validation schema (Pydantic, Marshmallow, etc.)
+            schema: Validation schema: a JSON Schema (draft 2020-12) given as
+                a dict or compiled `JsonSchema`, or a Pydantic/Marshmallow-style
+                schema class
             
         Returns:
             Any: Validated data
             
         Raises:
-            ValidationError: If validation fails
+            ValidationError: If validation fails. For JSON Schema, `errors`
+                lists each failure with its `pointer`, `keyword` and `message`
             
         Example:
             >>> data = await request.validate_json(UserSchema)
+            >>> data = await request.validate_json({"type": "object", "required": ["name"]})
         """
+        from .exceptions import ValidationError
+
+        if HAS_RUST_SCHEMA and isinstance(schema, (JsonSchema, dict, bool)):
+            # JSON Schema documents are validated in Rust straight from the body.
+            validator = schema if isinstance(schema, JsonSchema) else compile_schema(schema)
+            body = await self.body()
+            try:
+                errors = validator.validate(body or b"null")
+            except ValueError as e:
+                raise ValidationError("Invalid JSON body", errors=[
+                    {"pointer": "", "keyword": "json", "message": str(e)}
+                ])
+            if errors:
+                raise ValidationError("Validation failed", errors=errors)
+            return await self.json()
+
         data = await self.json()
         
         if schema is not None:
@@ -318,14 +447,12 @@ class Request:
                 # Marshmallow-like schema
                 errors = schema.validate(data)
                 if errors:
-                    from .exceptions import ValidationError
                     raise ValidationError("Validation failed", details=errors)
             elif hasattr(schema, "parse_obj"):
                 # Pydantic-like schema
                 try:
                     data = schema.parse_obj(data)
                 except Exception as e:
-                    from .exceptions import ValidationError
                     raise ValidationError("Validation failed", details=str(e))
         
         return data
@@ -23,6 +23,13 @@ try:
 except ImportError:
     HAS_RUST_COMPRESSION = False
 
+# Import Rust streaming JSON encoder if available
+try:
+    from _haske_core import stream_json
+    HAS_RUST_JSON_STREAM = True
+except ImportError:
+    HAS_RUST_JSON_STREAM = False
+
 class Response(StarletteResponse):
     """
     Base Haske Response with compression support.
@@ -174,6 +181,77 @@ class StreamingResponse(StarletteStreamingResponse):
         """
         super().__init__(content, status_code, headers, media_type, **kwargs)
 
+class JSONStreamResponse(StreamingResponse):
+    """
+    Streaming JSON response built from rows.
+
+    Rows come from an iterable or async iterator and are encoded in Rust
+    into chunks, so large result sets never sit in memory as one payload.
+    `datetime`, `date`, `time`, `Decimal`, `UUID`, `Enum` and dataclass
+    values are encoded natively.
+    """
+
+    def __init__(self, rows: Any, format: str = "array", status_code: int = 200,
+                 headers: Dict[str, str] = None, chunk_size: int = 64 * 1024, **kwargs):
+        """
+        Initialize streaming JSON response.
+
+        Args:
+            rows: Iterable or async iterable of JSON-serializable rows
+            format: "array" for one JSON array, "ndjson" for one row per line
+            status_code: HTTP status code, defaults to 200
+            headers: Response headers, defaults to None
+            chunk_size: Approximate size of each body chunk in bytes
+            **kwargs: Additional streaming response options
+        """
+        if format not in ("array", "ndjson"):
+            raise ValueError(f"unknown JSON stream format {format!r}")
+        if HAS_RUST_JSON_STREAM:
+            content = stream_json(rows, format, chunk_size)
+        else:
+            content = _stream_json_fallback(rows, format)
+        media_type = "application/x-ndjson" if format == "ndjson" else "application/json"
+        super().__init__(content, status_code, headers, media_type, **kwargs)
+
+def _json_default(value: Any) -> Any:
+    """Mirror the types the Rust stream encoder handles natively."""
+    import dataclasses
+    import datetime
+    import decimal
+    import enum
+    import uuid
+
+    if isinstance(value, (datetime.date, datetime.time)):
+        return value.isoformat()
+    if isinstance(value, enum.Enum):
+        return value.value
+    if isinstance(value, (decimal.Decimal, uuid.UUID)):
+        return str(value)
+    if dataclasses.is_dataclass(value) and not isinstance(value, type):
+        return {f.name: getattr(value, f.name) for f in dataclasses.fields(value)}
+    raise TypeError(f"Object of type {type(value).__name__} is not JSON serializable")
+
+async def _stream_json_fallback(rows: Any, format: str):
+    import json
+
+    def frame(row, first):
+        data = json.dumps(row, default=_json_default, separators=(",", ":")).encode()
+        if format == "ndjson":
+            return data + b"\n"
+        return (b"[" if first else b",") + data
+
+    count = 0
+    if hasattr(rows, "__aiter__"):
+        async for row in rows:
+            yield frame(row, count == 0)
+            count += 1
+    else:
+        for row in rows:
+            yield frame(row, count == 0)
+            count += 1
+    if format == "array":
+        yield b"]" if count else b"[]"
+
 class FileResponse(StarletteFileResponse):
     """
     File Response wrapper for Haske.
@@ -7,12 +7,13 @@ for path parameters and flexible route pattern matching.
 """
 
 from typing import Callable, List, Any, Dict
+from starlette.convertors import Convertor, register_url_convertor
 from starlette.routing import Route as StarletteRoute
 import re
 
 # Import Rust path functions if available
 try:
-    from _haske_core import compile_path, match_path
+    from _haske_core import compile_path, match_path, register_converter as rust_register_converter
     HAS_RUST_ROUTING = True
 except ImportError:
     HAS_RUST_ROUTING = False
@@ -28,6 +29,14 @@ def get_url(endpoint, **values):
     from haske.app import get_current_app  # import the global app
     app = get_current_app()
 
+    # Routes registered with @app.route are resolved by the Rust router;
+    # mounts and other Starlette routes fall through to the scan below.
+    if getattr(app, "_rust_router", None) is not None:
+        try:
+            return app._rust_router.url_path_for(endpoint, **values)
+        except LookupError:
+            pass
+
     for route in getattr(app, "routes", []):
         # Step 1: Try normal names
         route_name = getattr(route, "name", None)
@@ -43,7 +52,7 @@ def get_url(endpoint, **values):
         if endpoint in {route_name, ep_name}:
             url_path = route.path
             for key, value in values.items():
-                url_path = url_path.replace(f"{{{key}}}", str(value))
+                url_path = re.sub(rf"\{{{key}(?::\w+)?\}}", lambda _: str(value), url_path)
             return url_path
 
     raise ValueError(f"[Haske get_url] No route found with name '{endpoint}'")
@@ -68,20 +77,10 @@ class Route(StarletteRoute):
             name: Route name, defaults to None
             **kwargs: Additional route options
         """
-        # Convert path to regex using Rust if available
         if name is None:
             name = endpoint.__name__
 
-        if HAS_RUST_ROUTING:
-            try:
-                regex_path = compile_path(path)
-                super().__init__(regex_path, endpoint, methods=methods or ["GET"], name=name, **kwargs)
-                return
-            except Exception:
-                pass
-        
-        # Fall back to Starlette path handling
-        super().__init__(path, endpoint, methods=methods or ["GET"], name=name, **kwargs)
+        super().__init__(to_starlette_path(path), endpoint, methods=methods or ["GET"], name=name, **kwargs)
 
 class PathConverter:
     """
@@ -244,11 +243,18 @@ class PathConverterRegistry:
         """
         Register a custom converter.
         
+        The converter is also registered with the Rust router and with
+        Starlette, so `<name:param>` behaves the same whichever one
+        matches the request.
+        
         Args:
             name: Converter name
             converter: Converter instance
         """
         self.converters[name] = converter
+        if HAS_RUST_ROUTING:
+            rust_register_converter(name, converter.regex, converter.to_python, converter.to_string)
+        register_url_convertor(name, _starlette_convertor(converter))
     
     def get_converter(self, name: str) -> PathConverter:
         """
@@ -290,9 +296,45 @@ class PathConverterRegistry:
         
         return re.sub(pattern, replacer, path)
 
+def _starlette_convertor(converter: PathConverter) -> Convertor:
+    """
+    Wrap a Haske converter in a Starlette convertor.
+    """
+    class _Convertor(Convertor):
+        regex = converter.regex
+
+        def convert(self, value: str) -> Any:
+            return converter.to_python(value)
+
+        def to_string(self, value: Any) -> str:
+            return converter.to_string(value)
+
+    return _Convertor()
+
 # Global converter registry
 default_converter_registry = PathConverterRegistry()
 
+def to_starlette_path(path: str) -> str:
+    """
+    Translate Haske route syntax into Starlette's.
+    
+    Args:
+        path: URL path using `<converter:name>`, `<name>` or `:name`
+        
+    Returns:
+        str: Equivalent path using `{name:converter}` placeholders
+        
+    Example:
+        >>> to_starlette_path("/user/<int:id>")
+        '/user/{id:int}'
+    """
+    path = re.sub(r"/:(\w+)(?=/|$)", r"/{\1}", path)
+    return re.sub(
+        r"<(?:(\w+):)?(\w+)>",
+        lambda m: f"{{{m.group(2)}:{m.group(1)}}}" if m.group(1) else f"{{{m.group(2)}}}",
+        path,
+    )
+
 def convert_path(path: str) -> str:
     """
     Convert a path with converters to regex pattern.
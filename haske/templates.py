@@ -13,6 +13,7 @@ from typing import Optional
 from jinja2 import Environment, FileSystemLoader, select_autoescape
 
 from haske.routing import get_url
+from haske.middleware import csrf_token
 
 # Try loading Rust-powered template functions
 try:
@@ -70,6 +71,7 @@ def get_env(template_dir: Optional[str] = None, static_dir: Optional[str] = None
         # Inject static_url helper
         _env.globals["static_url"] = lambda filename: f"/static/{filename}"
         _env.globals["url_for"] = get_url
+        _env.globals["csrf_token"] = csrf_token
 
         print(f"[Haske] Using templates from: {abs_template}")
         print(f"[Haske] Static files served from: {abs_static}")
@@ -0,0 +1,265 @@
+use std::collections::HashMap;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use data_encoding::BASE32_NOPAD;
+use parking_lot::RwLock;
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+use pyo3::types::PyDict;
+
+use crate::crypto::{hmac_sha256, hmac_sha256_verify, random_bytes, secret_bytes};
+use crate::keyring::Keyring;
+
+/// Random bytes in a key id, the public part used to look a key up.
+const KEY_ID_BYTES: usize = 10;
+
+/// Random bytes in the secret part of a key.
+const SECRET_BYTES: usize = 32;
+
+/// Lowercase base32 without padding; it has no `_`, the key separator.
+fn encode(bytes: &[u8]) -> String {
+    BASE32_NOPAD.encode(bytes).to_ascii_lowercase()
+}
+
+fn now() -> f64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0.0, |elapsed| elapsed.as_secs_f64())
+}
+
+/// `*` matches every scope, `posts:*` every scope starting with `posts:`.
+fn scope_matches(granted: &str, scope: &str) -> bool {
+    match granted.strip_suffix('*') {
+        Some(prefix) => scope.starts_with(prefix),
+        None => granted == scope,
+    }
+}
+
+/// An API key as stored server-side: its public id, a keyed hash of the key,
+/// scopes and expiry. The key itself is never kept.
+///
+/// Persist the fields (or `to_dict()`) and rebuild the record with
+/// `ApiKey(key_id, hash, ...)` when a request presents the key.
+#[pyclass(frozen, module = "haske")]
+pub struct ApiKey {
+    #[pyo3(get)]
+    key_id: String,
+    #[pyo3(get)]
+    hash: String,
+    #[pyo3(get)]
+    prefix: String,
+    #[pyo3(get)]
+    scopes: Vec<String>,
+    /// Unix timestamp after which the key is refused, or `None`.
+    #[pyo3(get)]
+    expires_at: Option<f64>,
+    #[pyo3(get)]
+    name: Option<String>,
+}
+
+#[pymethods]
+impl ApiKey {
+    #[new]
+    #[pyo3(signature = (key_id, hash, *, prefix="hk_live".to_owned(), scopes=Vec::new(), expires_at=None, name=None))]
+    fn new(
+        key_id: String,
+        hash: String,
+        prefix: String,
+        scopes: Vec<String>,
+        expires_at: Option<f64>,
+        name: Option<String>,
+    ) -> Self {
+        Self {
+            key_id,
+            hash,
+            prefix,
+            scopes,
+            expires_at,
+            name,
+        }
+    }
+
+    /// Whether the key grants `scope`; granted scopes may end in `*`.
+    fn has_scope(&self, scope: &str) -> bool {
+        self.scopes
+            .iter()
+            .any(|granted| scope_matches(granted, scope))
+    }
+
+    /// Whether the key has expired at `now` (defaults to the current time).
+    #[pyo3(signature = (now=None))]
+    fn is_expired(&self, now: Option<f64>) -> bool {
+        let now = now.unwrap_or_else(self::now);
+        self.expires_at.is_some_and(|expires_at| now >= expires_at)
+    }
+
+    /// The record's fields, for storage.
+    fn to_dict<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyDict>> {
+        let dict = PyDict::new(py);
+        dict.set_item("key_id", &self.key_id)?;
+        dict.set_item("hash", &self.hash)?;
+        dict.set_item("prefix", &self.prefix)?;
+        dict.set_item("scopes", &self.scopes)?;
+        dict.set_item("expires_at", self.expires_at)?;
+        dict.set_item("name", &self.name)?;
+        Ok(dict)
+    }
+
+    fn __repr__(&self) -> String {
+        let scopes: Vec<String> = self
+            .scopes
+            .iter()
+            .map(|scope| format!("'{scope}'"))
+            .collect();
+        format!(
+            "ApiKey(key_id='{}', prefix='{}', scopes=[{}])",
+            self.key_id,
+            self.prefix,
+            scopes.join(", ")
+        )
+    }
+}
+
+/// Mints and verifies API keys such as `hk_live_<id>_<secret>`.
+///
+/// Only a keyed hash (HMAC-SHA256 under the application secret) of each key
+/// is stored, so a leaked table cannot be used to authenticate. The public
+/// id embedded in the key finds the stored record, whose hash is then
+/// compared in constant time.
+///
+/// Records can live in your database (look them up with `key_id(key)` and
+/// check them with `verify`) or in this object's in-memory registry
+/// (`add`, `revoke` and `authenticate`).
+#[pyclass(frozen, module = "haske")]
+pub struct ApiKeys {
+    /// Hashing keys, active key first.
+    keys: Vec<Vec<u8>>,
+    prefix: String,
+    records: RwLock<HashMap<String, Py<ApiKey>>>,
+}
+
+impl ApiKeys {
+    /// The key id inside `key`, if it has the shape of one of our keys.
+    fn split<'k>(&self, key: &'k str) -> Option<&'k str> {
+        let rest = key.strip_prefix(self.prefix.as_str())?.strip_prefix('_')?;
+        let (key_id, secret) = rest.split_once('_')?;
+        let decodes = |part: &str| {
+            BASE32_NOPAD
+                .decode(part.to_ascii_uppercase().as_bytes())
+                .ok()
+        };
+        if decodes(key_id)?.len() != KEY_ID_BYTES || decodes(secret)?.len() != SECRET_BYTES {
+            return None;
+        }
+        Some(key_id)
+    }
+}
+
+#[pymethods]
+impl ApiKeys {
+    /// `secret_key` is `str`, `bytes` or a `Keyring`; with a keyring, keys
+    /// hashed under a retired secret still verify.
+    #[new]
+    #[pyo3(signature = (secret_key, prefix="hk_live"))]
+    fn new(secret_key: &Bound<'_, PyAny>, prefix: &str) -> PyResult<Self> {
+        if prefix.is_empty()
+            || !prefix
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '_')
+        {
+            return Err(PyValueError::new_err(format!(
+                "invalid API key prefix '{prefix}': use letters, digits and '_'"
+            )));
+        }
+        let keys = match secret_key.downcast::<Keyring>() {
+            Ok(keyring) => keyring.get().secrets().map(<[u8]>::to_vec).collect(),
+            Err(_) => vec![secret_bytes(secret_key)?],
+        };
+        Ok(Self {
+            keys,
+            prefix: prefix.to_owned(),
+            records: RwLock::new(HashMap::new()),
+        })
+    }
+
+    #[getter]
+    fn prefix(&self) -> &str {
+        &self.prefix
+    }
+
+    /// Mint a key, returning `(key, record)`. Show the key to its owner
+    /// once and store only the record.
+    #[pyo3(signature = (scopes=Vec::new(), expires_in=None, name=None))]
+    fn create(
+        &self,
+        scopes: Vec<String>,
+        expires_in: Option<f64>,
+        name: Option<String>,
+    ) -> (String, ApiKey) {
+        let key_id = encode(&random_bytes(KEY_ID_BYTES));
+        let key = format!(
+            "{}_{key_id}_{}",
+            self.prefix,
+            encode(&random_bytes(SECRET_BYTES))
+        );
+        let record = ApiKey {
+            hash: URL_SAFE_NO_PAD.encode(hmac_sha256(&self.keys[0], key.as_bytes())),
+            key_id,
+            prefix: self.prefix.clone(),
+            scopes,
+            expires_at: expires_in.map(|seconds| now() + seconds),
+            name,
+        };
+        (key, record)
+    }
+
+    /// The public id inside `key`, or `None` if it is not one of our keys.
+    fn key_id(&self, key: &str) -> Option<String> {
+        self.split(key).map(str::to_owned)
+    }
+
+    /// Whether `key` matches `record` and the record has not expired.
+    fn verify(&self, key: &str, record: &ApiKey) -> bool {
+        if self.split(key) != Some(record.key_id.as_str()) || record.is_expired(None) {
+            return false;
+        }
+        let Ok(hash) = URL_SAFE_NO_PAD.decode(&record.hash) else {
+            return false;
+        };
+        self.keys
+            .iter()
+            .any(|secret| hmac_sha256_verify(secret, key.as_bytes(), &hash))
+    }
+
+    /// Register a record in the in-memory registry.
+    fn add(&self, record: Py<ApiKey>) {
+        let key_id = record.get().key_id.clone();
+        self.records.write().insert(key_id, record);
+    }
+
+    /// Remove a record from the registry, returning whether it was there.
+    fn revoke(&self, key_id: &str) -> bool {
+        self.records.write().remove(key_id).is_some()
+    }
+
+    /// The registered record `key` belongs to, if the key is valid.
+    fn authenticate(&self, py: Python<'_>, key: &str) -> Option<Py<ApiKey>> {
+        let key_id = self.split(key)?;
+        let record = self.records.read().get(key_id)?.clone_ref(py);
+        self.verify(key, record.get()).then_some(record)
+    }
+
+    fn __len__(&self) -> usize {
+        self.records.read().len()
+    }
+
+    fn __repr__(&self) -> String {
+        format!(
+            "ApiKeys(prefix='{}', registered={})",
+            self.prefix,
+            self.__len__()
+        )
+    }
+}
@@ -0,0 +1,1429 @@
+use std::collections::hash_map::RandomState;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::hash::BuildHasher;
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+
+use parking_lot::Mutex;
+use pyo3::exceptions::{PyRuntimeError, PyStopIteration, PyValueError};
+use pyo3::intern;
+use pyo3::prelude::*;
+use pyo3::sync::PyOnceLock;
+use pyo3::types::{PyByteArray, PyBytes, PyString, PyType};
+
+use crate::disk_cache::{self, DiskStore};
+use crate::dispatch::Resume;
+
+static FUTURE: PyOnceLock<Py<PyType>> = PyOnceLock::new();
+static WRAP_FUTURE: PyOnceLock<Py<PyAny>> = PyOnceLock::new();
+static ENSURE_FUTURE: PyOnceLock<Py<PyAny>> = PyOnceLock::new();
+static PICKLE_DUMPS: PyOnceLock<Py<PyAny>> = PyOnceLock::new();
+static PICKLE_LOADS: PyOnceLock<Py<PyAny>> = PyOnceLock::new();
+
+/// Default cap on the size of the disk tier, 1 GiB.
+const DISK_MAX_SIZE: u64 = 1 << 30;
+
+struct Entry {
+    value: Py<PyAny>,
+    expires_at: Instant,
+    /// End of the window in which `get_or_set` still serves the value
+    /// while refreshing it; `expires_at` unless a `stale_ttl` was given.
+    stale_until: Instant,
+    /// How long the loader took to produce the value.
+    delta: Duration,
+    weight: u64,
+    tags: Vec<String>,
+}
+
+impl Entry {
+    /// Whether `get_or_set` should refresh the value before it expires.
+    ///
+    /// This is XFetch ("Optimal Probabilistic Cache Stampede Prevention"):
+    /// the closer the entry is to expiring, and the longer it took to load,
+    /// the more likely a read is to trigger the refresh, so one early
+    /// caller reloads it instead of every caller at once at expiry.
+    fn expires_early(&self, now: Instant, beta: f64) -> bool {
+        if beta <= 0.0 || self.delta.is_zero() {
+            return false;
+        }
+        let draw = 1.0 - rand::random::<f64>();
+        let gap = self.delta.as_secs_f64() * beta * -draw.ln();
+        Duration::try_from_secs_f64(gap).map_or(true, |gap| now + gap >= self.expires_at)
+    }
+}
+
+/// A `get_or_set` whose loader is running.
+struct Flight {
+    /// `concurrent.futures.Future` resolved with the loader's outcome;
+    /// concurrent callers await it instead of running the loader.
+    future: Py<PyAny>,
+    /// The background task of a refresh, kept alive until it lands.
+    task: Option<Py<PyAny>>,
+}
+
+/// What a `get_or_set` call does, decided under the lock.
+enum Plan {
+    /// Return the stored value.
+    Hit(Py<PyAny>),
+    /// Return the stored value and refresh it in the background.
+    Refresh(Py<PyAny>, Py<PyAny>),
+    /// Run the loader, publishing its outcome to the future.
+    Lead(Py<PyAny>),
+    /// Await the future of the caller running the loader.
+    Wait(Py<PyAny>),
+}
+
+/// Per-call options of `set` and `get_or_set`.
+#[derive(Clone)]
+struct LoadOptions {
+    ttl: Duration,
+    stale_ttl: Duration,
+    weight: Option<u64>,
+    beta: f64,
+    tags: Vec<String>,
+}
+
+/// Bookkeeping of an eviction policy. The cache tells it about every key it
+/// stores, reads and drops, and asks it for victims when over capacity.
+trait Eviction: Send {
+    fn name(&self) -> &'static str;
+
+    /// A new key was stored.
+    fn admit(&mut self, key: &str, weight: u64);
+
+    /// A stored key was read or overwritten.
+    fn hit(&mut self, key: &str);
+
+    /// A stored key was overwritten with a value of another weight.
+    fn reweigh(&mut self, _key: &str, _weight: u64) {}
+
+    /// A key was looked up, whether or not it is stored.
+    fn record(&mut self, _key: &str) {}
+
+    /// A stored key was deleted or expired.
+    fn forget(&mut self, key: &str);
+
+    /// Pick a stored key to evict and forget it.
+    fn victim(&mut self) -> Option<String>;
+
+    fn clear(&mut self);
+}
+
+/// Keys in recency order, least recently used first, with their weights.
+#[derive(Default)]
+struct Recency {
+    /// Key to `(tick, weight)`.
+    entries: HashMap<String, (u64, u64)>,
+    order: BTreeMap<u64, String>,
+    next: u64,
+    weight: u64,
+}
+
+impl Recency {
+    fn push(&mut self, key: String, weight: u64) {
+        self.next += 1;
+        self.order.insert(self.next, key.clone());
+        self.entries.insert(key, (self.next, weight));
+        self.weight += weight;
+    }
+
+    /// Move `key` to the most recent end; false if it is not here.
+    fn touch(&mut self, key: &str) -> bool {
+        let Some((tick, _)) = self.entries.get_mut(key) else {
+            return false;
+        };
+        self.next += 1;
+        let old = std::mem::replace(tick, self.next);
+        if let Some(key) = self.order.remove(&old) {
+            self.order.insert(self.next, key);
+        }
+        true
+    }
+
+    fn reweigh(&mut self, key: &str, weight: u64) -> bool {
+        let Some((_, old)) = self.entries.get_mut(key) else {
+            return false;
+        };
+        self.weight = self.weight - *old + weight;
+        *old = weight;
+        true
+    }
+
+    /// Remove `key`, returning its weight.
+    fn remove(&mut self, key: &str) -> Option<u64> {
+        let (tick, weight) = self.entries.remove(key)?;
+        self.order.remove(&tick);
+        self.weight -= weight;
+        Some(weight)
+    }
+
+    fn oldest(&self) -> Option<&str> {
+        self.order.first_key_value().map(|(_, key)| key.as_str())
+    }
+
+    fn pop_oldest(&mut self) -> Option<(String, u64)> {
+        let (_, key) = self.order.pop_first()?;
+        let (_, weight) = self.entries.remove(&key)?;
+        self.weight -= weight;
+        Some((key, weight))
+    }
+
+    fn clear(&mut self) {
+        *self = Self::default();
+    }
+}
+
+/// Least recently used.
+#[derive(Default)]
+struct Lru(Recency);
+
+impl Eviction for Lru {
+    fn name(&self) -> &'static str {
+        "lru"
+    }
+
+    fn admit(&mut self, key: &str, weight: u64) {
+        self.0.push(key.to_owned(), weight);
+    }
+
+    fn hit(&mut self, key: &str) {
+        self.0.touch(key);
+    }
+
+    fn forget(&mut self, key: &str) {
+        self.0.remove(key);
+    }
+
+    fn victim(&mut self) -> Option<String> {
+        self.0.pop_oldest().map(|(key, _)| key)
+    }
+
+    fn clear(&mut self) {
+        self.0.clear();
+    }
+}
+
+/// Least frequently used, the least recently used among equals first.
+#[derive(Default)]
+struct Lfu {
+    /// Key to `(hits, tick)`.
+    entries: HashMap<String, (u64, u64)>,
+    order: BTreeMap<(u64, u64), String>,
+    next: u64,
+}
+
+impl Eviction for Lfu {
+    fn name(&self) -> &'static str {
+        "lfu"
+    }
+
+    fn admit(&mut self, key: &str, _weight: u64) {
+        self.next += 1;
+        self.order.insert((1, self.next), key.to_owned());
+        self.entries.insert(key.to_owned(), (1, self.next));
+    }
+
+    fn hit(&mut self, key: &str) {
+        let Some(rank) = self.entries.get_mut(key) else {
+            return;
+        };
+        self.next += 1;
+        let old = std::mem::replace(rank, (rank.0.saturating_add(1), self.next));
+        if let Some(key) = self.order.remove(&old) {
+            self.order.insert(*rank, key);
+        }
+    }
+
+    fn forget(&mut self, key: &str) {
+        if let Some(rank) = self.entries.remove(key) {
+            self.order.remove(&rank);
+        }
+    }
+
+    fn victim(&mut self) -> Option<String> {
+        let (_, key) = self.order.pop_first()?;
+        self.entries.remove(&key);
+        Some(key)
+    }
+
+    fn clear(&mut self) {
+        self.entries.clear();
+        self.order.clear();
+    }
+}
+
+/// Count-min sketch of recent key frequencies with four rows of counters
+/// capped at 15, all halved once `sample` increments have been counted so
+/// old popularity fades.
+struct Sketch {
+    counters: Vec<u8>,
+    mask: usize,
+    hasher: RandomState,
+    additions: usize,
+    sample: usize,
+}
+
+impl Sketch {
+    const ROWS: usize = 4;
+    const MAX: u8 = 15;
+
+    fn new(capacity: usize) -> Self {
+        let width = capacity.clamp(16, 1 << 24).next_power_of_two();
+        Self {
+            counters: vec![0; Self::ROWS * width],
+            mask: width - 1,
+            hasher: RandomState::new(),
+            additions: 0,
+            sample: 10 * width,
+        }
+    }
+
+    fn slots(&self, key: &str) -> [usize; Self::ROWS] {
+        let hash = self.hasher.hash_one(key);
+        let (h1, h2) = (hash as usize, ((hash >> 32) as usize) | 1);
+        let width = self.mask + 1;
+        std::array::from_fn(|row| row * width + (h1.wrapping_add(row.wrapping_mul(h2)) & self.mask))
+    }
+
+    fn increment(&mut self, key: &str) {
+        let mut added = false;
+        for slot in self.slots(key) {
+            if self.counters[slot] < Self::MAX {
+                self.counters[slot] += 1;
+                added = true;
+            }
+        }
+        if added {
+            self.additions += 1;
+            if self.additions >= self.sample {
+                self.counters.iter_mut().for_each(|c| *c /= 2);
+                self.additions /= 2;
+            }
+        }
+    }
+
+    fn frequency(&self, key: &str) -> u8 {
+        self.slots(key)
+            .into_iter()
+            .map(|slot| self.counters[slot])
+            .min()
+            .unwrap_or(0)
+    }
+}
+
+/// Window TinyLFU: new keys enter a small LRU window; keys leaving it join
+/// the probation segment of a segmented LRU only if the frequency sketch
+/// rates them above the entry they would push out. Keys hit in probation
+/// are promoted to the protected segment.
+struct TinyLfu {
+    window: Recency,
+    probation: Recency,
+    protected: Recency,
+    window_capacity: u64,
+    protected_capacity: u64,
+    sketch: Sketch,
+    /// Keys moved from the window to probation since the last `admit`,
+    /// still to be weighed against probation's own victims.
+    candidates: Vec<String>,
+}
+
+impl TinyLfu {
+    fn new(capacity: u64, entries: usize) -> Self {
+        let window_capacity = (capacity / 100).max(1);
+        Self {
+            window: Recency::default(),
+            probation: Recency::default(),
+            protected: Recency::default(),
+            window_capacity,
+            protected_capacity: (capacity - window_capacity.min(capacity)) / 5 * 4,
+            sketch: Sketch::new(entries),
+            candidates: Vec::new(),
+        }
+    }
+
+    fn take(&mut self, key: &str) -> Option<String> {
+        self.window
+            .remove(key)
+            .or_else(|| self.probation.remove(key))
+            .or_else(|| self.protected.remove(key))
+            .map(|_| key.to_owned())
+    }
+}
+
+impl Eviction for TinyLfu {
+    fn name(&self) -> &'static str {
+        "tinylfu"
+    }
+
+    fn admit(&mut self, key: &str, weight: u64) {
+        self.candidates.clear();
+        self.sketch.increment(key);
+        self.window.push(key.to_owned(), weight);
+        while self.window.weight > self.window_capacity {
+            let Some((key, weight)) = self.window.pop_oldest() else {
+                break;
+            };
+            self.probation.push(key.clone(), weight);
+            self.candidates.push(key);
+        }
+    }
+
+    fn hit(&mut self, key: &str) {
+        if self.window.touch(key) || self.protected.touch(key) {
+            return;
+        }
+        let Some(weight) = self.probation.remove(key) else {
+            return;
+        };
+        self.protected.push(key.to_owned(), weight);
+        while self.protected.weight > self.protected_capacity {
+            let Some((key, weight)) = self.protected.pop_oldest() else {
+                break;
+            };
+            self.probation.push(key, weight);
+        }
+    }
+
+    fn reweigh(&mut self, key: &str, weight: u64) {
+        let _ = self.window.reweigh(key, weight)
+            || self.probation.reweigh(key, weight)
+            || self.protected.reweigh(key, weight);
+    }
+
+    fn record(&mut self, key: &str) {
+        self.sketch.increment(key);
+    }
+
+    fn forget(&mut self, key: &str) {
+        self.candidates.retain(|candidate| candidate != key);
+        self.take(key);
+    }
+
+    fn victim(&mut self) -> Option<String> {
+        if let Some(candidate) = self.candidates.pop() {
+            let victim = self
+                .probation
+                .oldest()
+                .filter(|&oldest| oldest != candidate)
+                .map(str::to_owned);
+            let loser = match victim {
+                Some(victim)
+                    if self.sketch.frequency(&candidate) > self.sketch.frequency(&victim) =>
+                {
+                    self.candidates.push(candidate);
+                    victim
+                }
+                _ => candidate,
+            };
+            self.candidates.retain(|candidate| *candidate != loser);
+            return self.take(&loser);
+        }
+        self.probation
+            .pop_oldest()
+            .or_else(|| self.protected.pop_oldest())
+            .or_else(|| self.window.pop_oldest())
+            .map(|(key, _)| key)
+    }
+
+    fn clear(&mut self) {
+        self.window.clear();
+        self.probation.clear();
+        self.protected.clear();
+        self.candidates.clear();
+    }
+}
+
+struct Inner {
+    map: HashMap<String, Entry>,
+    policy: Box<dyn Eviction>,
+    weight: u64,
+    flights: HashMap<String, Flight>,
+    /// Tag to the keys stored with it.
+    tags: HashMap<String, HashSet<String>>,
+}
+
+impl Inner {
+    fn link(&mut self, key: &str, tags: &[String]) {
+        for tag in tags {
+            self.tags
+                .entry(tag.clone())
+                .or_default()
+                .insert(key.to_owned());
+        }
+    }
+
+    fn unlink(&mut self, key: &str, tags: &[String]) {
+        for tag in tags {
+            if let Some(keys) = self.tags.get_mut(tag) {
+                keys.remove(key);
+                if keys.is_empty() {
+                    self.tags.remove(tag);
+                }
+            }
+        }
+    }
+
+    fn remove(&mut self, key: &str) -> Option<Entry> {
+        let entry = self.map.remove(key)?;
+        self.policy.forget(key);
+        self.weight -= entry.weight;
+        self.unlink(key, &entry.tags);
+        Some(entry)
+    }
+
+    /// Remove the entries tagged `tag` whose key starts with `prefix`,
+    /// returning their keys.
+    fn invalidate(&mut self, tag: &str, prefix: &str) -> Vec<String> {
+        let keys: Vec<String> = self
+            .tags
+            .get(tag)
+            .into_iter()
+            .flatten()
+            .filter(|key| key.starts_with(prefix))
+            .cloned()
+            .collect();
+        for key in &keys {
+            self.remove(key);
+        }
+        keys
+    }
+
+    fn remove_prefix(&mut self, prefix: &str) {
+        let keys: Vec<String> = self
+            .map
+            .keys()
+            .filter(|key| key.starts_with(prefix))
+            .cloned()
+            .collect();
+        for key in keys {
+            self.remove(&key);
+        }
+    }
+
+    /// Evict until both the entry count and the total weight fit.
+    fn evict(&mut self, max_capacity: usize, max_weight: u64) {
+        while self.map.len() > max_capacity || self.weight > max_weight {
+            let Some(key) = self.policy.victim() else {
+                break;
+            };
+            if let Some(entry) = self.map.remove(&key) {
+                self.weight -= entry.weight;
+                self.unlink(&key, &entry.tags);
+            }
+        }
+    }
+}
+
+/// Thread-safe cache of arbitrary Python objects with a default time-to-live.
+///
+/// `policy` chooses what is evicted once `max_capacity` entries (and, if
+/// set, `max_weight`) are exceeded:
+///
+/// - `"lru"`: the least recently used entry.
+/// - `"lfu"`: the least frequently used entry.
+/// - `"tinylfu"`: Window TinyLFU, which admits a new entry into the main
+///   cache only if it is requested more often than the entry it would
+///   replace, so one-off keys cannot flush out hot ones.
+///
+/// Each entry has a weight, 1 unless `set` is given one. With `max_weight`,
+/// weights default to the length of `str`, `bytes` and `bytearray` values
+/// (1 for other types), or to `weigher(key, value)` when given.
+///
+/// `get_or_set` coalesces concurrent loads of a key: one caller runs the
+/// loader and the others await its result.
+///
+/// Entries can be stored with `tags`; `invalidate_tag` then removes every
+/// entry carrying a tag, in time proportional to their number.
+/// `namespace(name)` returns a view whose keys are prefixed with `name:`.
+///
+/// With `disk_path`, entries are also pickled into an on-disk tier of at
+/// most `disk_max_size` bytes that survives restarts: `set` writes through
+/// to it and a `get` that misses memory reads it back. Values that cannot
+/// be pickled stay in memory only.
+#[pyclass(frozen)]
+pub struct HaskeCache {
+    inner: Mutex<Inner>,
+    disk: Option<Mutex<DiskStore>>,
+    max_capacity: usize,
+    max_weight: Option<u64>,
+    weigher: Option<Py<PyAny>>,
+    ttl: Duration,
+}
+
+impl HaskeCache {
+    fn duration(seconds: f64, name: &str) -> PyResult<Duration> {
+        Duration::try_from_secs_f64(seconds)
+            .map_err(|_| PyValueError::new_err(format!("{name} must be a non-negative number")))
+    }
+
+    fn options(
+        &self,
+        ttl: Option<f64>,
+        weight: Option<u64>,
+        stale_ttl: f64,
+        beta: f64,
+        tags: Option<Vec<String>>,
+    ) -> PyResult<LoadOptions> {
+        if !(beta >= 0.0 && beta.is_finite()) {
+            return Err(PyValueError::new_err("beta must be a non-negative number"));
+        }
+        let mut tags = tags.unwrap_or_default();
+        tags.sort_unstable();
+        tags.dedup();
+        Ok(LoadOptions {
+            ttl: ttl.map_or(Ok(self.ttl), |seconds| Self::duration(seconds, "ttl"))?,
+            stale_ttl: Self::duration(stale_ttl, "stale_ttl")?,
+            weight,
+            beta,
+            tags,
+        })
+    }
+
+    fn store(
+        &self,
+        key: String,
+        value: &Bound<'_, PyAny>,
+        options: &LoadOptions,
+        delta: Duration,
+    ) -> PyResult<bool> {
+        let kept = self.store_memory(key.clone(), value, options, delta)?;
+        let Some(disk) = &self.disk else {
+            return Ok(kept);
+        };
+        let py = value.py();
+        let pickled = PICKLE_DUMPS
+            .import(py, "pickle", "dumps")?
+            .call1((value, -1))
+            .and_then(|data| Ok(data.downcast_into::<PyBytes>()?.as_bytes().to_vec()));
+        let Ok(data) = pickled else {
+            // Do not leave an older value on disk to be read back.
+            py.detach(|| disk.lock().delete(&key))?;
+            return Ok(kept);
+        };
+        let expires_at = disk_cache::now() + options.ttl.as_secs_f64();
+        let written = py.detach(|| disk.lock().put(&key, &data, expires_at, &options.tags))?;
+        Ok(kept || written)
+    }
+
+    /// Load `key` from the disk tier into memory, if it is there.
+    fn promote(&self, py: Python<'_>, key: &str) -> PyResult<Option<Py<PyAny>>> {
+        let Some(disk) = &self.disk else {
+            return Ok(None);
+        };
+        let Some(stored) = py.detach(|| disk.lock().get(key))? else {
+            return Ok(None);
+        };
+        let value = match PICKLE_LOADS
+            .import(py, "pickle", "loads")?
+            .call1((PyBytes::new(py, &stored.value),))
+        {
+            Ok(value) => value,
+            Err(_) => {
+                // Written by code whose classes have since changed.
+                py.detach(|| disk.lock().delete(key))?;
+                return Ok(None);
+            }
+        };
+        let options = LoadOptions {
+            ttl: Duration::try_from_secs_f64(stored.expires_at - disk_cache::now())
+                .unwrap_or_default(),
+            stale_ttl: Duration::ZERO,
+            weight: None,
+            beta: 0.0,
+            tags: stored.tags,
+        };
+        self.store_memory(key.to_owned(), &value, &options, Duration::ZERO)?;
+        Ok(Some(value.unbind()))
+    }
+
+    fn store_memory(
+        &self,
+        key: String,
+        value: &Bound<'_, PyAny>,
+        options: &LoadOptions,
+        delta: Duration,
+    ) -> PyResult<bool> {
+        let weight = match options.weight {
+            Some(weight) => weight,
+            None => self.weight_of(&key, value)?,
+        };
+        let max_weight = self.max_weight.unwrap_or(u64::MAX);
+
+        let mut inner = self.inner.lock();
+        if weight > max_weight {
+            inner.remove(&key);
+            return Ok(false);
+        }
+        let expires_at = Instant::now() + options.ttl;
+        let entry = Entry {
+            value: value.clone().unbind(),
+            expires_at,
+            stale_until: expires_at + options.stale_ttl,
+            delta,
+            weight,
+            tags: options.tags.clone(),
+        };
+        match inner.map.insert(key.clone(), entry) {
+            Some(old) => {
+                inner.unlink(&key, &old.tags);
+                inner.link(&key, &options.tags);
+                inner.weight = inner.weight - old.weight + weight;
+                inner.policy.hit(&key);
+                inner.policy.reweigh(&key, weight);
+            }
+            None => {
+                inner.link(&key, &options.tags);
+                inner.weight += weight;
+                inner.policy.admit(&key, weight);
+            }
+        }
+        inner.evict(self.max_capacity, max_weight);
+        Ok(inner.map.contains_key(&key))
+    }
+
+    /// Decide what a `get_or_set` of `key` does. Starting a flight needs a
+    /// fresh future, which is created outside the lock, so `None` asks the
+    /// caller to come back with one in `spare`.
+    fn plan(
+        &self,
+        py: Python<'_>,
+        key: &str,
+        beta: f64,
+        spare: &mut Option<Py<PyAny>>,
+    ) -> Option<Plan> {
+        let now = Instant::now();
+        let mut inner = self.inner.lock();
+        inner.policy.record(key);
+        let flying = inner.flights.get(key).map(|f| f.future.clone_ref(py));
+        let stored = match inner.map.get(key) {
+            Some(entry) if entry.stale_until > now => Some((
+                entry.value.clone_ref(py),
+                entry.expires_at > now && !entry.expires_early(now, beta),
+            )),
+            Some(_) => {
+                inner.remove(key);
+                None
+            }
+            None => None,
+        };
+        let plan = match (stored, flying) {
+            (Some((value, true)), _) | (Some((value, false)), Some(_)) => Plan::Hit(value),
+            (None, Some(future)) => return Some(Plan::Wait(future)),
+            (stored, None) => {
+                let future = spare.take()?;
+                inner.flights.insert(
+                    key.to_owned(),
+                    Flight {
+                        future: future.clone_ref(py),
+                        task: None,
+                    },
+                );
+                match stored {
+                    Some((value, _)) => Plan::Refresh(value, future),
+                    None => return Some(Plan::Lead(future)),
+                }
+            }
+        };
+        inner.policy.hit(key);
+        Some(plan)
+    }
+
+    /// Store the loader's outcome, end the flight and resolve its future.
+    fn land<'py>(
+        &self,
+        py: Python<'py>,
+        key: &str,
+        future: &Py<PyAny>,
+        outcome: PyResult<Bound<'py, PyAny>>,
+        options: &LoadOptions,
+        delta: Duration,
+    ) -> PyResult<Bound<'py, PyAny>> {
+        let outcome = outcome.and_then(|value| {
+            self.store(key.to_owned(), &value, options, delta)?;
+            Ok(value)
+        });
+        {
+            let mut inner = self.inner.lock();
+            if inner
+                .flights
+                .get(key)
+                .is_some_and(|flight| flight.future.is(future))
+            {
+                inner.flights.remove(key);
+            }
+        }
+        let future = future.bind(py);
+        match &outcome {
+            Ok(value) => future.call_method1(intern!(py, "set_result"), (value,))?,
+            Err(err) => future.call_method1(intern!(py, "set_exception"), (err.value(py),))?,
+        };
+        outcome
+    }
+
+    /// Remove the entries tagged `tag` whose key starts with `prefix` from
+    /// both tiers, returning how many keys were removed.
+    fn invalidate(&self, py: Python<'_>, tag: &str, prefix: &str) -> PyResult<usize> {
+        let mut keys: HashSet<String> = self
+            .inner
+            .lock()
+            .invalidate(tag, prefix)
+            .into_iter()
+            .collect();
+        if let Some(disk) = &self.disk {
+            keys.extend(py.detach(|| disk.lock().invalidate(tag, prefix))?);
+        }
+        Ok(keys.len())
+    }
+
+    fn weight_of(&self, key: &str, value: &Bound<'_, PyAny>) -> PyResult<u64> {
+        if self.max_weight.is_none() {
+            return Ok(1);
+        }
+        if let Some(weigher) = &self.weigher {
+            return weigher.bind(value.py()).call1((key, value))?.extract();
+        }
+        Ok(if let Ok(text) = value.downcast::<PyString>() {
+            text.to_cow()?.len() as u64
+        } else if let Ok(bytes) = value.downcast::<PyBytes>() {
+            bytes.as_bytes().len() as u64
+        } else if let Ok(bytes) = value.downcast::<PyByteArray>() {
+            bytes.len() as u64
+        } else {
+            1
+        })
+    }
+}
+
+#[pymethods]
+impl HaskeCache {
+    #[new]
+    #[pyo3(signature = (max_capacity, time_to_live, *, policy="lru", max_weight=None, weigher=None, disk_path=None, disk_max_size=DISK_MAX_SIZE))]
+    pub fn new(
+        max_capacity: usize,
+        time_to_live: u64,
+        policy: &str,
+        max_weight: Option<u64>,
+        weigher: Option<Py<PyAny>>,
+        disk_path: Option<PathBuf>,
+        disk_max_size: u64,
+    ) -> PyResult<Self> {
+        let max_capacity = max_capacity.max(1);
+        let policy: Box<dyn Eviction> = match policy.to_ascii_lowercase().as_str() {
+            "lru" => Box::<Lru>::default(),
+            "lfu" => Box::<Lfu>::default(),
+            "tinylfu" | "w-tinylfu" => Box::new(TinyLfu::new(
+                max_weight.unwrap_or(max_capacity as u64),
+                max_capacity,
+            )),
+            _ => {
+                return Err(PyValueError::new_err(format!(
+                    "unknown eviction policy '{policy}'; expected 'lru', 'lfu' or 'tinylfu'"
+                )))
+            }
+        };
+        Ok(Self {
+            inner: Mutex::new(Inner {
+                map: HashMap::new(),
+                policy,
+                weight: 0,
+                flights: HashMap::new(),
+                tags: HashMap::new(),
+            }),
+            disk: disk_path
+                .map(|path| DiskStore::open(&path, disk_max_size).map(Mutex::new))
+                .transpose()?,
+            max_capacity,
+            max_weight,
+            weigher,
+            ttl: Duration::from_secs(time_to_live),
+        })
+    }
+
+    /// Name of the eviction policy.
+    #[getter]
+    fn policy(&self) -> &'static str {
+        self.inner.lock().policy.name()
+    }
+
+    /// Sum of the weights of stored entries.
+    #[getter]
+    fn weight(&self) -> u64 {
+        self.inner.lock().weight
+    }
+
+    /// Bytes used by the disk tier, or `None` without one.
+    #[getter]
+    fn disk_size(&self) -> Option<u64> {
+        self.disk.as_ref().map(|disk| disk.lock().size())
+    }
+
+    /// Entries in the disk tier, or `None` without one.
+    #[getter]
+    fn disk_len(&self) -> Option<usize> {
+        self.disk.as_ref().map(|disk| disk.lock().len())
+    }
+
+    fn get(&self, py: Python<'_>, key: &str) -> PyResult<Option<Py<PyAny>>> {
+        {
+            let mut inner = self.inner.lock();
+            inner.policy.record(key);
+            if let Some(entry) = inner.map.get(key) {
+                let now = Instant::now();
+                if entry.expires_at > now {
+                    let value = entry.value.clone_ref(py);
+                    inner.policy.hit(key);
+                    return Ok(Some(value));
+                }
+                // Values `get_or_set` may still serve stale are kept.
+                if entry.stale_until <= now {
+                    inner.remove(key);
+                }
+                return Ok(None);
+            }
+        }
+        self.promote(py, key)
+    }
+
+    /// Store `value`, returning whether it was kept.
+    ///
+    /// `ttl` (seconds) overrides the default time-to-live and `weight` the
+    /// computed weight. A value heavier than `max_weight`, or one TinyLFU
+    /// declines to admit, is not kept. `tags` label the entry for
+    /// `invalidate_tag`.
+    #[pyo3(signature = (key, value, *, ttl=None, weight=None, tags=None))]
+    pub fn set(
+        &self,
+        key: String,
+        value: Bound<'_, PyAny>,
+        ttl: Option<f64>,
+        weight: Option<u64>,
+        tags: Option<Vec<String>>,
+    ) -> PyResult<bool> {
+        let options = self.options(ttl, weight, 0.0, 0.0, tags)?;
+        self.store(key, &value, &options, Duration::ZERO)
+    }
+
+    /// Return the value stored under `key`, or load, store and return it.
+    ///
+    /// `loader` is called without arguments and may return the value or an
+    /// awaitable of it. The result is awaitable: concurrent calls for the
+    /// same key share a single run of the loader, and if it raises, every
+    /// waiting caller gets the exception.
+    ///
+    /// `stale_ttl` (seconds) keeps serving an expired value for that long
+    /// while the loader refreshes it in the background. With `beta` > 0, a
+    /// read may start that refresh before the value expires, more likely
+    /// the closer it is to expiring and the longer the loader took
+    /// (probabilistic early expiration); 1.0 is a good start. A background
+    /// refresh that fails leaves the stored value in place.
+    #[allow(clippy::too_many_arguments)]
+    #[pyo3(signature = (key, loader, *, ttl=None, weight=None, stale_ttl=0.0, beta=0.0, tags=None))]
+    fn get_or_set(
+        slf: Bound<'_, Self>,
+        key: String,
+        loader: Py<PyAny>,
+        ttl: Option<f64>,
+        weight: Option<u64>,
+        stale_ttl: f64,
+        beta: f64,
+        tags: Option<Vec<String>>,
+    ) -> PyResult<CacheLoad> {
+        let options = slf.get().options(ttl, weight, stale_ttl, beta, tags)?;
+        Ok(CacheLoad {
+            cache: slf.unbind(),
+            key,
+            loader,
+            options,
+            stage: Stage::Start,
+            awaiting: None,
+        })
+    }
+
+    /// Alias of `set` kept for the original `insert` API.
+    fn insert(&self, key: String, value: Bound<'_, PyAny>) -> PyResult<bool> {
+        self.set(key, value, None, None, None)
+    }
+
+    /// Remove every entry tagged `tag`, returning how many there were.
+    fn invalidate_tag(&self, py: Python<'_>, tag: &str) -> PyResult<usize> {
+        self.invalidate(py, tag, "")
+    }
+
+    /// Rewrite the disk tier's segments to reclaim the space of deleted,
+    /// replaced and expired entries. This also happens on its own as the
+    /// tier fills up.
+    fn compact(&self, py: Python<'_>) -> PyResult<()> {
+        if let Some(disk) = &self.disk {
+            py.detach(|| disk.lock().compact())?;
+        }
+        Ok(())
+    }
+
+    /// A view of the entries whose keys start with `name:`.
+    fn namespace(slf: Bound<'_, Self>, name: &str) -> PyResult<CacheNamespace> {
+        CacheNamespace::new(slf.unbind(), String::new(), name)
+    }
+
+    fn delete(&self, py: Python<'_>, key: &str) -> PyResult<bool> {
+        let removed = self.inner.lock().remove(key).is_some();
+        let Some(disk) = &self.disk else {
+            return Ok(removed);
+        };
+        Ok(py.detach(|| disk.lock().delete(key))? || removed)
+    }
+
+    /// Alias of `delete` kept for the original `remove` API.
+    fn remove(&self, py: Python<'_>, key: &str) -> PyResult<bool> {
+        self.delete(py, key)
+    }
+
+    fn clear(&self, py: Python<'_>) -> PyResult<()> {
+        {
+            let mut inner = self.inner.lock();
+            inner.map.clear();
+            inner.policy.clear();
+            inner.weight = 0;
+            inner.tags.clear();
+        }
+        if let Some(disk) = &self.disk {
+            py.detach(|| disk.lock().clear())?;
+        }
+        Ok(())
+    }
+
+    fn size(&self) -> usize {
+        self.inner.lock().map.len()
+    }
+
+    fn len(&self) -> usize {
+        self.size()
+    }
+
+    fn is_empty(&self) -> bool {
+        self.size() == 0
+    }
+
+    fn __len__(&self) -> usize {
+        self.size()
+    }
+
+    fn __contains__(&self, key: &str) -> bool {
+        self.inner
+            .lock()
+            .map
+            .get(key)
+            .is_some_and(|entry| entry.expires_at > Instant::now())
+            || self
+                .disk
+                .as_ref()
+                .is_some_and(|disk| disk.lock().contains(key))
+    }
+}
+
+#[pyfunction]
+#[pyo3(signature = (max_capacity, time_to_live, *, policy="lru", max_weight=None))]
+pub fn create_cache(
+    max_capacity: usize,
+    time_to_live: u64,
+    policy: &str,
+    max_weight: Option<u64>,
+) -> PyResult<HaskeCache> {
+    HaskeCache::new(
+        max_capacity,
+        time_to_live,
+        policy,
+        max_weight,
+        None,
+        None,
+        DISK_MAX_SIZE,
+    )
+}
+
+/// A view of a `HaskeCache` whose keys are prefixed with `name:`, returned
+/// by `HaskeCache.namespace`.
+///
+/// Views of the same cache share its capacity and eviction. Tags are not
+/// prefixed, so `HaskeCache.invalidate_tag` reaches tagged entries in every
+/// namespace, while a view's `invalidate_tag` and `clear` only touch its
+/// own keys; `clear` and `len()` scan the whole cache.
+#[pyclass(frozen, module = "haske")]
+pub struct CacheNamespace {
+    cache: Py<HaskeCache>,
+    prefix: String,
+}
+
+impl CacheNamespace {
+    fn new(cache: Py<HaskeCache>, parent: String, name: &str) -> PyResult<Self> {
+        if name.is_empty() {
+            return Err(PyValueError::new_err("namespace name must not be empty"));
+        }
+        Ok(Self {
+            cache,
+            prefix: format!("{parent}{name}:"),
+        })
+    }
+
+    fn key(&self, key: &str) -> String {
+        format!("{}{key}", self.prefix)
+    }
+}
+
+#[pymethods]
+impl CacheNamespace {
+    /// The namespace's name, `users` or `app:users` when nested.
+    #[getter]
+    fn name(&self) -> &str {
+        &self.prefix[..self.prefix.len() - 1]
+    }
+
+    fn get(&self, py: Python<'_>, key: &str) -> PyResult<Option<Py<PyAny>>> {
+        self.cache.get().get(py, &self.key(key))
+    }
+
+    #[pyo3(signature = (key, value, *, ttl=None, weight=None, tags=None))]
+    fn set(
+        &self,
+        key: &str,
+        value: Bound<'_, PyAny>,
+        ttl: Option<f64>,
+        weight: Option<u64>,
+        tags: Option<Vec<String>>,
+    ) -> PyResult<bool> {
+        self.cache
+            .get()
+            .set(self.key(key), value, ttl, weight, tags)
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    #[pyo3(signature = (key, loader, *, ttl=None, weight=None, stale_ttl=0.0, beta=0.0, tags=None))]
+    fn get_or_set(
+        &self,
+        py: Python<'_>,
+        key: &str,
+        loader: Py<PyAny>,
+        ttl: Option<f64>,
+        weight: Option<u64>,
+        stale_ttl: f64,
+        beta: f64,
+        tags: Option<Vec<String>>,
+    ) -> PyResult<CacheLoad> {
+        HaskeCache::get_or_set(
+            self.cache.bind(py).clone(),
+            self.key(key),
+            loader,
+            ttl,
+            weight,
+            stale_ttl,
+            beta,
+            tags,
+        )
+    }
+
+    fn delete(&self, py: Python<'_>, key: &str) -> PyResult<bool> {
+        self.cache.get().delete(py, &self.key(key))
+    }
+
+    /// Remove this namespace's entries tagged `tag`.
+    fn invalidate_tag(&self, py: Python<'_>, tag: &str) -> PyResult<usize> {
+        self.cache.get().invalidate(py, tag, &self.prefix)
+    }
+
+    /// Remove every entry of this namespace.
+    fn clear(&self, py: Python<'_>) -> PyResult<()> {
+        let cache = self.cache.get();
+        cache.inner.lock().remove_prefix(&self.prefix);
+        if let Some(disk) = &cache.disk {
+            py.detach(|| disk.lock().delete_prefix(&self.prefix))?;
+        }
+        Ok(())
+    }
+
+    /// A nested view whose keys are prefixed with `name:` within this one.
+    fn namespace(&self, py: Python<'_>, name: &str) -> PyResult<CacheNamespace> {
+        CacheNamespace::new(self.cache.clone_ref(py), self.prefix.clone(), name)
+    }
+
+    fn size(&self) -> usize {
+        let cache = self.cache.get();
+        let inner = cache.inner.lock();
+        inner
+            .map
+            .keys()
+            .filter(|key| key.starts_with(&self.prefix))
+            .count()
+    }
+
+    fn __len__(&self) -> usize {
+        self.size()
+    }
+
+    fn __contains__(&self, key: &str) -> bool {
+        self.cache.get().__contains__(&self.key(key))
+    }
+
+    fn __repr__(&self) -> String {
+        format!("CacheNamespace('{}')", self.name())
+    }
+}
+
+enum Stage {
+    Start,
+    /// Running the loader; a background refresh starts here.
+    Lead {
+        future: Py<PyAny>,
+        started: Instant,
+        background: bool,
+    },
+    Wait,
+    Done,
+}
+
+/// Awaitable returned by `HaskeCache.get_or_set`.
+///
+/// Resolves at once with a stored value; otherwise it drives the loader's
+/// awaitable with `yield from` semantics, or awaits the caller that does.
+#[pyclass(module = "haske")]
+pub struct CacheLoad {
+    cache: Py<HaskeCache>,
+    key: String,
+    loader: Py<PyAny>,
+    options: LoadOptions,
+    stage: Stage,
+    awaiting: Option<Py<PyAny>>,
+}
+
+impl CacheLoad {
+    fn resume<'py>(&mut self, py: Python<'py>, mut input: Resume<'py>) -> PyResult<Py<PyAny>> {
+        loop {
+            let Some(awaiting) = self.awaiting.as_ref().map(|a| a.bind(py).clone()) else {
+                if let Resume::Throw(exc) = input {
+                    return Err(self.finish(py, Err(PyErr::from_value(exc))));
+                }
+                match self.begin(py) {
+                    Ok(Some(value)) => return Err(self.finish(py, Ok(value))),
+                    Ok(None) => {}
+                    Err(err) => return Err(self.finish(py, Err(err))),
+                }
+                input = Resume::Send(py.None().into_bound(py));
+                continue;
+            };
+            let result = match input {
+                Resume::Send(value) if value.is_none() => {
+                    awaiting.call_method0(intern!(py, "__next__"))
+                }
+                Resume::Send(value) => awaiting.call_method1(intern!(py, "send"), (value,)),
+                Resume::Throw(exc) => awaiting.call_method1(intern!(py, "throw"), (exc,)),
+            };
+            return match result {
+                Ok(yielded) => Ok(yielded.unbind()),
+                Err(err) if err.is_instance_of::<PyStopIteration>(py) => {
+                    self.awaiting = None;
+                    let value = err.value(py).getattr(intern!(py, "value"));
+                    Err(self.finish(py, value))
+                }
+                Err(err) => {
+                    self.awaiting = None;
+                    Err(self.finish(py, Err(err)))
+                }
+            };
+        }
+    }
+
+    /// Take the next step: `Some(value)` if the call completes at once,
+    /// `None` once `awaiting` is set.
+    fn begin<'py>(&mut self, py: Python<'py>) -> PyResult<Option<Bound<'py, PyAny>>> {
+        match self.stage {
+            Stage::Start => {}
+            Stage::Lead { .. } => return self.call_loader(py),
+            Stage::Wait | Stage::Done => {
+                return Err(PyRuntimeError::new_err(
+                    "cannot reuse already awaited get_or_set()",
+                ))
+            }
+        }
+        let cache = self.cache.get();
+        if cache.disk.is_some() && !cache.inner.lock().map.contains_key(&self.key) {
+            cache.promote(py, &self.key)?;
+        }
+        let mut spare = None;
+        let plan = loop {
+            if let Some(plan) = cache.plan(py, &self.key, self.options.beta, &mut spare) {
+                break plan;
+            }
+            let future = FUTURE.import(py, "concurrent.futures", "Future")?.call0()?;
+            // A running future cannot be cancelled, so a waiter that is
+            // cancelled does not take the other waiters down with it.
+            future.call_method0(intern!(py, "set_running_or_notify_cancel"))?;
+            spare = Some(future.unbind());
+        };
+        match plan {
+            Plan::Hit(value) => Ok(Some(value.into_bound(py))),
+            Plan::Refresh(value, future) => {
+                self.refresh(py, future)?;
+                Ok(Some(value.into_bound(py)))
+            }
+            Plan::Lead(future) => {
+                self.stage = Stage::Lead {
+                    future,
+                    started: Instant::now(),
+                    background: false,
+                };
+                self.call_loader(py)
+            }
+            Plan::Wait(future) => {
+                self.stage = Stage::Wait;
+                let waiting = WRAP_FUTURE
+                    .import(py, "asyncio", "wrap_future")?
+                    .call1((future,))?;
+                self.awaiting = Some(waiting.call_method0(intern!(py, "__await__"))?.unbind());
+                Ok(None)
+            }
+        }
+    }
+
+    fn call_loader<'py>(&mut self, py: Python<'py>) -> PyResult<Option<Bound<'py, PyAny>>> {
+        let value = self.loader.bind(py).call0()?;
+        if !value.hasattr(intern!(py, "__await__"))? {
+            return Ok(Some(value));
+        }
+        self.awaiting = Some(value.call_method0(intern!(py, "__await__"))?.unbind());
+        Ok(None)
+    }
+
+    /// Run the loader in a background task that lands the flight `future`.
+    fn refresh(&self, py: Python<'_>, future: Py<PyAny>) -> PyResult<()> {
+        let cache = self.cache.get();
+        let load = CacheLoad {
+            cache: self.cache.clone_ref(py),
+            key: self.key.clone(),
+            loader: self.loader.clone_ref(py),
+            options: self.options.clone(),
+            stage: Stage::Lead {
+                future: future.clone_ref(py),
+                started: Instant::now(),
+                background: true,
+            },
+            awaiting: None,
+        };
+        let task = Py::new(py, load).and_then(|load| {
+            ENSURE_FUTURE
+                .import(py, "asyncio", "ensure_future")?
+                .call1((load,))
+        });
+        match task {
+            Ok(task) => {
+                if let Some(flight) = cache.inner.lock().flights.get_mut(&self.key) {
+                    if flight.future.is(&future) {
+                        flight.task = Some(task.unbind());
+                    }
+                }
+                Ok(())
+            }
+            Err(err) => cache
+                .land(
+                    py,
+                    &self.key,
+                    &future,
+                    Err(err),
+                    &self.options,
+                    Duration::ZERO,
+                )
+                .map(drop),
+        }
+    }
+
+    /// Settle the call with `outcome`, returning what to raise: the
+    /// `StopIteration` carrying the value, or the error.
+    fn finish<'py>(&mut self, py: Python<'py>, outcome: PyResult<Bound<'py, PyAny>>) -> PyErr {
+        let outcome = match std::mem::replace(&mut self.stage, Stage::Done) {
+            Stage::Lead {
+                future,
+                started,
+                background,
+            } => {
+                let cache = self.cache.get();
+                let outcome = cache.land(
+                    py,
+                    &self.key,
+                    &future,
+                    outcome,
+                    &self.options,
+                    started.elapsed(),
+                );
+                if background {
+                    Ok(py.None().into_bound(py))
+                } else {
+                    outcome
+                }
+            }
+            _ => outcome,
+        };
+        match outcome {
+            Ok(value) => PyStopIteration::new_err((value.unbind(),)),
+            Err(err) => err,
+        }
+    }
+
+    /// Fail the flight of a call dropped before its loader finished, so its
+    /// waiters do not wait forever.
+    fn abandon(&mut self, py: Python<'_>) {
+        if let Stage::Lead { .. } = self.stage {
+            let err = PyRuntimeError::new_err("get_or_set() was closed before its loader finished");
+            let _ = self.finish(py, Err(err));
+        }
+    }
+}
+
+impl Drop for CacheLoad {
+    fn drop(&mut self) {
+        if let Stage::Lead { .. } = self.stage {
+            Python::attach(|py| self.abandon(py));
+        }
+    }
+}
+
+#[pymethods]
+impl CacheLoad {
+    fn __await__(slf: PyRef<'_, Self>) -> PyRef<'_, Self> {
+        slf
+    }
+
+    fn __iter__(slf: PyRef<'_, Self>) -> PyRef<'_, Self> {
+        slf
+    }
+
+    fn __next__(&mut self, py: Python<'_>) -> PyResult<Py<PyAny>> {
+        self.resume(py, Resume::Send(py.None().into_bound(py)))
+    }
+
+    fn send<'py>(&mut self, py: Python<'py>, value: Bound<'py, PyAny>) -> PyResult<Py<PyAny>> {
+        self.resume(py, Resume::Send(value))
+    }
+
+    #[pyo3(signature = (typ, val=None, _tb=None))]
+    fn throw<'py>(
+        &mut self,
+        py: Python<'py>,
+        typ: Bound<'py, PyAny>,
+        val: Option<Bound<'py, PyAny>>,
+        _tb: Option<Bound<'py, PyAny>>,
+    ) -> PyResult<Py<PyAny>> {
+        let exc = match val {
+            Some(val) if !val.is_none() => val,
+            _ if typ.is_instance_of::<PyType>() => typ.call0()?,
+            _ => typ,
+        };
+        self.resume(py, Resume::Throw(exc))
+    }
+
+    fn close(&mut self, py: Python<'_>) -> PyResult<()> {
+        if let Some(awaiting) = self.awaiting.take() {
+            if awaiting.bind(py).hasattr(intern!(py, "close"))? {
+                awaiting.bind(py).call_method0(intern!(py, "close"))?;
+            }
+        }
+        self.abandon(py);
+        self.stage = Stage::Done;
+        Ok(())
+    }
+}
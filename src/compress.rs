@@ -0,0 +1,97 @@
+use std::io::{Read, Write};
+
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use pyo3::exceptions::{PyIOError, PyValueError};
+use pyo3::prelude::*;
+use pyo3::types::PyBytes;
+
+#[pyfunction]
+#[pyo3(signature = (data, level=6))]
+pub fn gzip_compress<'py>(
+    py: Python<'py>,
+    data: &[u8],
+    level: u32,
+) -> PyResult<Bound<'py, PyBytes>> {
+    if level > 9 {
+        return Err(PyValueError::new_err("gzip level must be between 0 and 9"));
+    }
+    let out = py
+        .detach(|| {
+            let mut encoder =
+                GzEncoder::new(Vec::with_capacity(data.len() / 2), Compression::new(level));
+            encoder.write_all(data)?;
+            encoder.finish()
+        })
+        .map_err(|e| PyIOError::new_err(format!("gzip compression error: {e}")))?;
+    Ok(PyBytes::new(py, &out))
+}
+
+#[pyfunction]
+pub fn gzip_decompress<'py>(py: Python<'py>, data: &[u8]) -> PyResult<Bound<'py, PyBytes>> {
+    let out = py
+        .detach(|| {
+            let mut out = Vec::with_capacity(data.len() * 2);
+            GzDecoder::new(data).read_to_end(&mut out).map(|_| out)
+        })
+        .map_err(|e| PyIOError::new_err(format!("gzip decompression error: {e}")))?;
+    Ok(PyBytes::new(py, &out))
+}
+
+#[pyfunction]
+#[pyo3(signature = (data, level=3))]
+pub fn zstd_compress<'py>(
+    py: Python<'py>,
+    data: &[u8],
+    level: i32,
+) -> PyResult<Bound<'py, PyBytes>> {
+    let out = py
+        .detach(|| zstd::encode_all(data, level))
+        .map_err(|e| PyIOError::new_err(format!("zstd compression error: {e}")))?;
+    Ok(PyBytes::new(py, &out))
+}
+
+#[pyfunction]
+pub fn zstd_decompress<'py>(py: Python<'py>, data: &[u8]) -> PyResult<Bound<'py, PyBytes>> {
+    let out = py
+        .detach(|| zstd::decode_all(data))
+        .map_err(|e| PyIOError::new_err(format!("zstd decompression error: {e}")))?;
+    Ok(PyBytes::new(py, &out))
+}
+
+#[pyfunction]
+#[pyo3(signature = (data, level=5))]
+pub fn brotli_compress<'py>(
+    py: Python<'py>,
+    data: &[u8],
+    level: u32,
+) -> PyResult<Bound<'py, PyBytes>> {
+    if level > 11 {
+        return Err(PyValueError::new_err(
+            "brotli level must be between 0 and 11",
+        ));
+    }
+    let out = py
+        .detach(|| {
+            let mut out = Vec::with_capacity(data.len() / 2);
+            let params = brotli::enc::BrotliEncoderParams {
+                quality: level as i32,
+                ..Default::default()
+            };
+            brotli::BrotliCompress(&mut &data[..], &mut out, &params).map(|_| out)
+        })
+        .map_err(|e| PyIOError::new_err(format!("brotli compression error: {e}")))?;
+    Ok(PyBytes::new(py, &out))
+}
+
+#[pyfunction]
+pub fn brotli_decompress<'py>(py: Python<'py>, data: &[u8]) -> PyResult<Bound<'py, PyBytes>> {
+    let out = py
+        .detach(|| {
+            let mut out = Vec::with_capacity(data.len() * 3);
+            brotli::BrotliDecompress(&mut &data[..], &mut out).map(|_| out)
+        })
+        .map_err(|e| PyIOError::new_err(format!("brotli decompression error: {e}")))?;
+    Ok(PyBytes::new(py, &out))
+}
@@ -0,0 +1,197 @@
+use std::borrow::Cow;
+use std::sync::{Arc, LazyLock};
+
+use parking_lot::RwLock;
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+use pyo3::sync::PyOnceLock;
+use pyo3::types::{PyFloat, PyInt, PyString, PyType};
+use regex::Regex;
+
+use crate::path::is_identifier;
+
+static UUID_CLASS: PyOnceLock<Py<PyType>> = PyOnceLock::new();
+
+/// Converters registered from Python, indexed by `Converter::Custom`.
+/// Entries are replaced in place on re-registration, never removed, so
+/// indices held by compiled routes stay valid.
+static CUSTOM: LazyLock<RwLock<Vec<Arc<Custom>>>> = LazyLock::new(|| RwLock::new(Vec::new()));
+
+struct Custom {
+    name: String,
+    /// Fragment as given, embedded into larger patterns.
+    regex: String,
+    /// `regex` anchored to a whole segment.
+    full: Regex,
+    to_python: Option<Py<PyAny>>,
+    to_string: Option<Py<PyAny>>,
+}
+
+/// Path parameter converters, mirroring `haske.routing.PathConverterRegistry`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum Converter {
+    Str,
+    Int,
+    Float,
+    Uuid,
+    /// Matches the rest of the path, slashes included.
+    Path,
+    /// Registered with `register_converter`; matches within one segment.
+    Custom(usize),
+}
+
+/// Register a converter usable as `<name:param>` in route patterns.
+///
+/// `to_python` receives the matched string and `to_string` the value being
+/// formatted into a URL; either defaults to passing the string through.
+/// Registering an existing name replaces it, built-ins included.
+#[pyfunction]
+#[pyo3(signature = (name, regex, to_python=None, to_string=None))]
+pub fn register_converter(
+    name: &str,
+    regex: &str,
+    to_python: Option<Py<PyAny>>,
+    to_string: Option<Py<PyAny>>,
+) -> PyResult<()> {
+    if !is_identifier(name) {
+        return Err(PyValueError::new_err(format!(
+            "invalid converter name '{name}'"
+        )));
+    }
+    let full = Regex::new(&format!("^(?:{regex})$"))
+        .map_err(|e| PyValueError::new_err(format!("invalid regex for converter '{name}': {e}")))?;
+    let custom = Arc::new(Custom {
+        name: name.to_owned(),
+        regex: regex.to_owned(),
+        full,
+        to_python,
+        to_string,
+    });
+
+    let mut registry = CUSTOM.write();
+    match registry.iter().position(|c| c.name == name) {
+        Some(index) => registry[index] = custom,
+        None => registry.push(custom),
+    }
+    Ok(())
+}
+
+fn custom(index: usize) -> Arc<Custom> {
+    Arc::clone(&CUSTOM.read()[index])
+}
+
+impl Converter {
+    pub(crate) fn from_name(name: &str) -> Option<Self> {
+        if let Some(index) = CUSTOM.read().iter().position(|c| c.name == name) {
+            return Some(Self::Custom(index));
+        }
+        match name {
+            "str" | "string" => Some(Self::Str),
+            "int" => Some(Self::Int),
+            "float" => Some(Self::Float),
+            "uuid" => Some(Self::Uuid),
+            "path" => Some(Self::Path),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn name(self) -> Cow<'static, str> {
+        Cow::Borrowed(match self {
+            Self::Str => "str",
+            Self::Int => "int",
+            Self::Float => "float",
+            Self::Uuid => "uuid",
+            Self::Path => "path",
+            Self::Custom(index) => return Cow::Owned(custom(index).name.clone()),
+        })
+    }
+
+    /// Regex fragment for one value, without capture groups.
+    pub(crate) fn regex(self) -> Cow<'static, str> {
+        Cow::Borrowed(match self {
+            Self::Str => "[^/]+",
+            Self::Int => "[0-9]+",
+            Self::Float => r"[0-9]+(?:\.[0-9]+)?",
+            Self::Uuid => "[0-9a-f]{8}-[0-9a-f]{4}-[0-9a-f]{4}-[0-9a-f]{4}-[0-9a-f]{12}",
+            Self::Path => ".*",
+            Self::Custom(index) => return Cow::Owned(format!("(?:{})", custom(index).regex)),
+        })
+    }
+
+    /// Order in which sibling parameters are tried: more specific first.
+    pub(crate) fn priority(self) -> u8 {
+        match self {
+            Self::Uuid => 0,
+            Self::Int => 1,
+            Self::Float => 2,
+            Self::Custom(_) => 3,
+            Self::Str => 4,
+            Self::Path => 5,
+        }
+    }
+
+    /// Whether `value` is a complete match for this converter.
+    pub(crate) fn matches(self, value: &str) -> bool {
+        match self {
+            Self::Str => !value.is_empty() && !value.contains('/'),
+            Self::Int => is_digits(value),
+            Self::Float => match value.split_once('.') {
+                Some((int, frac)) => is_digits(int) && is_digits(frac),
+                None => is_digits(value),
+            },
+            Self::Uuid => is_uuid(value),
+            Self::Path => true,
+            Self::Custom(index) => custom(index).full.is_match(value),
+        }
+    }
+
+    /// Convert a matched string into its Python value.
+    pub(crate) fn to_python<'py>(
+        self,
+        py: Python<'py>,
+        value: &str,
+    ) -> PyResult<Bound<'py, PyAny>> {
+        match self {
+            Self::Str | Self::Path => Ok(PyString::new(py, value).into_any()),
+            Self::Int => match value.parse::<i64>() {
+                Ok(i) => Ok(PyInt::new(py, i).into_any()),
+                // Too large for i64: let Python parse the arbitrary precision int.
+                Err(_) => py.get_type::<PyInt>().call1((value,)),
+            },
+            Self::Float => value
+                .parse::<f64>()
+                .map(|f| PyFloat::new(py, f).into_any())
+                .map_err(|e| PyValueError::new_err(e.to_string())),
+            Self::Uuid => UUID_CLASS.import(py, "uuid", "UUID")?.call1((value,)),
+            Self::Custom(index) => match &custom(index).to_python {
+                Some(to_python) => to_python.bind(py).call1((value,)),
+                None => Ok(PyString::new(py, value).into_any()),
+            },
+        }
+    }
+
+    /// Format a Python value for use in a URL (not yet percent-encoded).
+    pub(crate) fn to_string(self, value: &Bound<'_, PyAny>) -> PyResult<String> {
+        let text = match self {
+            Self::Custom(index) => match &custom(index).to_string {
+                Some(to_string) => to_string.bind(value.py()).call1((value,))?.str()?,
+                None => value.str()?,
+            },
+            _ => value.str()?,
+        };
+        Ok(text.to_cow()?.into_owned())
+    }
+}
+
+fn is_digits(value: &str) -> bool {
+    !value.is_empty() && value.bytes().all(|b| b.is_ascii_digit())
+}
+
+fn is_uuid(value: &str) -> bool {
+    let bytes = value.as_bytes();
+    bytes.len() == 36
+        && bytes.iter().enumerate().all(|(i, b)| match i {
+            8 | 13 | 18 | 23 => *b == b'-',
+            _ => b.is_ascii_digit() || (b'a'..=b'f').contains(b),
+        })
+}
@@ -0,0 +1,72 @@
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use hmac::digest::KeyInit;
+use hmac::{Hmac, Mac};
+use pyo3::prelude::*;
+use pyo3::types::{PyBytes, PyString};
+use rand::RngCore;
+use sha2::Sha256;
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// Sign `payload` as `base64url(payload).base64url(hmac_sha256(secret, payload))`.
+#[pyfunction]
+pub fn sign_cookie(secret: &str, payload: &str) -> String {
+    let signature = hmac_sha256(secret.as_bytes(), payload.as_bytes());
+    format!(
+        "{}.{}",
+        URL_SAFE_NO_PAD.encode(payload),
+        URL_SAFE_NO_PAD.encode(signature)
+    )
+}
+
+/// Verify a token produced by `sign_cookie`, returning the payload if the signature matches.
+#[pyfunction]
+pub fn verify_cookie(secret: &str, token: &str) -> Option<String> {
+    let (encoded_payload, encoded_signature) = token.split_once('.')?;
+    let payload = URL_SAFE_NO_PAD.decode(encoded_payload).ok()?;
+    let signature = URL_SAFE_NO_PAD.decode(encoded_signature).ok()?;
+
+    if !hmac_sha256_verify(secret.as_bytes(), &payload, &signature) {
+        return None;
+    }
+    String::from_utf8(payload).ok()
+}
+
+#[pyfunction]
+pub fn generate_random_bytes(py: Python<'_>, length: usize) -> Bound<'_, PyBytes> {
+    PyBytes::new(py, &random_bytes(length))
+}
+
+pub(crate) fn hmac_sha256(key: &[u8], data: &[u8]) -> Vec<u8> {
+    hmac_sign::<HmacSha256>(key, data)
+}
+
+/// HMAC of `message` with any RustCrypto MAC, e.g. `Hmac<Sha512>`.
+pub(crate) fn hmac_sign<M: Mac + KeyInit>(secret: &[u8], message: &[u8]) -> Vec<u8> {
+    let mut mac = <M as KeyInit>::new_from_slice(secret).expect("HMAC accepts keys of any length");
+    mac.update(message);
+    mac.finalize().into_bytes().to_vec()
+}
+
+/// Constant-time check of an HMAC-SHA256 `signature` over `data`.
+pub(crate) fn hmac_sha256_verify(key: &[u8], data: &[u8], signature: &[u8]) -> bool {
+    let mut mac =
+        <HmacSha256 as KeyInit>::new_from_slice(key).expect("HMAC accepts keys of any length");
+    mac.update(data);
+    mac.verify_slice(signature).is_ok()
+}
+
+/// Key material given from Python as `bytes` or `str`.
+pub(crate) fn secret_bytes(obj: &Bound<'_, PyAny>) -> PyResult<Vec<u8>> {
+    match obj.downcast::<PyBytes>() {
+        Ok(bytes) => Ok(bytes.as_bytes().to_vec()),
+        Err(_) => Ok(obj.downcast::<PyString>()?.to_cow()?.as_bytes().to_vec()),
+    }
+}
+
+pub(crate) fn random_bytes(length: usize) -> Vec<u8> {
+    let mut buf = vec![0u8; length];
+    rand::thread_rng().fill_bytes(&mut buf);
+    buf
+}
@@ -0,0 +1,177 @@
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+use subtle::ConstantTimeEq;
+
+use crate::crypto::{hmac_sha256, hmac_sha256_verify, random_bytes, secret_bytes};
+use crate::keyring::Keyring;
+
+/// Bytes in a CSRF secret, and so in the one-time pad that masks it.
+const SECRET_LEN: usize = 32;
+
+/// CSRF secrets and tokens for `CSRFMiddleware`.
+///
+/// Each client gets a random secret, kept in a cookie signed with the
+/// application key so a sibling subdomain cannot plant one. Forms and
+/// headers carry *masked* tokens: the secret XORed with a fresh random pad,
+/// pad included. Every rendered token differs, which keeps the secret from
+/// being recovered through response compression (BREACH).
+#[pyclass(frozen, module = "haske")]
+pub struct CsrfGuard {
+    /// Signing keys, active key first.
+    keys: Vec<Vec<u8>>,
+    trusted_origins: Vec<String>,
+}
+
+impl CsrfGuard {
+    /// The secret inside a cookie from `new_cookie`, if its signature holds.
+    fn cookie_secret(&self, cookie: &str) -> Option<Vec<u8>> {
+        let (encoded_secret, encoded_signature) = cookie.split_once('.')?;
+        let signature = URL_SAFE_NO_PAD.decode(encoded_signature).ok()?;
+        if !self
+            .keys
+            .iter()
+            .any(|key| hmac_sha256_verify(key, encoded_secret.as_bytes(), &signature))
+        {
+            return None;
+        }
+        let secret = URL_SAFE_NO_PAD.decode(encoded_secret).ok()?;
+        (secret.len() == SECRET_LEN).then_some(secret)
+    }
+
+    fn is_trusted(&self, origin: &str, request_origin: &str) -> bool {
+        origin == request_origin
+            || self
+                .trusted_origins
+                .iter()
+                .any(|trusted| origin_matches(trusted, origin))
+    }
+}
+
+#[pymethods]
+impl CsrfGuard {
+    /// `secret_key` is `str`, `bytes` or a `Keyring`. `trusted_origins` lists
+    /// extra origins (`https://app.example.com`, or `https://*.example.com`
+    /// for any subdomain) allowed to submit unsafe requests.
+    #[new]
+    #[pyo3(signature = (secret_key, trusted_origins=Vec::new()))]
+    fn new(secret_key: &Bound<'_, PyAny>, trusted_origins: Vec<String>) -> PyResult<Self> {
+        let keys = match secret_key.downcast::<Keyring>() {
+            Ok(keyring) => keyring.get().secrets().map(<[u8]>::to_vec).collect(),
+            Err(_) => vec![secret_bytes(secret_key)?],
+        };
+        let trusted_origins = trusted_origins
+            .iter()
+            .map(|origin| {
+                normalize_origin(origin).ok_or_else(|| {
+                    PyValueError::new_err(format!("invalid trusted origin {origin:?}"))
+                })
+            })
+            .collect::<PyResult<_>>()?;
+        Ok(Self {
+            keys,
+            trusted_origins,
+        })
+    }
+
+    /// A signed cookie value holding a new random secret.
+    fn new_cookie(&self) -> String {
+        let encoded_secret = URL_SAFE_NO_PAD.encode(random_bytes(SECRET_LEN));
+        let signature = hmac_sha256(&self.keys[0], encoded_secret.as_bytes());
+        format!("{encoded_secret}.{}", URL_SAFE_NO_PAD.encode(signature))
+    }
+
+    /// Whether `cookie` came from `new_cookie` with one of our keys.
+    fn is_valid_cookie(&self, cookie: &str) -> bool {
+        self.cookie_secret(cookie).is_some()
+    }
+
+    /// A freshly masked token for the secret in `cookie`, or `None` if the
+    /// cookie is invalid.
+    fn mask(&self, cookie: &str) -> Option<String> {
+        let secret = self.cookie_secret(cookie)?;
+        let mut token = random_bytes(SECRET_LEN);
+        let masked: Vec<u8> = token.iter().zip(&secret).map(|(p, s)| p ^ s).collect();
+        token.extend_from_slice(&masked);
+        Some(URL_SAFE_NO_PAD.encode(token))
+    }
+
+    /// Whether `token` is a masked form of the secret in `cookie`.
+    fn verify(&self, cookie: &str, token: &str) -> bool {
+        let Some(secret) = self.cookie_secret(cookie) else {
+            return false;
+        };
+        let Ok(raw) = URL_SAFE_NO_PAD.decode(token) else {
+            return false;
+        };
+        if raw.len() != 2 * SECRET_LEN {
+            return false;
+        }
+        let (pad, masked) = raw.split_at(SECRET_LEN);
+        let unmasked: Vec<u8> = pad.iter().zip(masked).map(|(p, m)| p ^ m).collect();
+        unmasked.ct_eq(&secret).into()
+    }
+
+    /// Check where an unsafe request came from, returning why it must be
+    /// rejected or `None` if it may proceed.
+    ///
+    /// `Origin` is preferred, then `Referer`; either must match
+    /// `request_origin` (e.g. `https://example.com`) or a trusted origin.
+    /// Over HTTPS a request carrying neither header is rejected.
+    #[pyo3(signature = (origin, referer, request_origin))]
+    fn check_origin(
+        &self,
+        origin: Option<&str>,
+        referer: Option<&str>,
+        request_origin: &str,
+    ) -> Option<String> {
+        let Some(request_origin) = normalize_origin(request_origin) else {
+            return Some(format!("unusable request origin {request_origin:?}"));
+        };
+        let (header, value) = match (origin, referer) {
+            (Some(origin), _) => ("Origin", origin),
+            (None, Some(referer)) => ("Referer", referer),
+            (None, None) if request_origin.starts_with("https://") => {
+                return Some("missing Origin and Referer headers".to_owned());
+            }
+            (None, None) => return None,
+        };
+        match normalize_origin(value) {
+            Some(source) if self.is_trusted(&source, &request_origin) => None,
+            _ => Some(format!("{header} {value} is not trusted")),
+        }
+    }
+}
+
+/// `scheme://host[:port]` of an http(s) URL, lowercased and without a
+/// default port; `None` for anything else, including the `null` origin.
+fn normalize_origin(url: &str) -> Option<String> {
+    let (scheme, rest) = url.trim().split_once("://")?;
+    let scheme = scheme.to_ascii_lowercase();
+    let default_port = match scheme.as_str() {
+        "http" => ":80",
+        "https" => ":443",
+        _ => return None,
+    };
+    let authority = rest.split(['/', '?', '#']).next()?;
+    let host = authority.rsplit('@').next()?.to_ascii_lowercase();
+    let host = host.strip_suffix(default_port).unwrap_or(&host);
+    if host.is_empty() {
+        return None;
+    }
+    Some(format!("{scheme}://{host}"))
+}
+
+/// Whether `origin` matches a normalized trusted origin, where a leading
+/// `*.` in the host stands for any subdomain.
+fn origin_matches(trusted: &str, origin: &str) -> bool {
+    match trusted.split_once("://*.") {
+        Some((scheme, domain)) => origin
+            .strip_prefix(scheme)
+            .and_then(|rest| rest.strip_prefix("://"))
+            .and_then(|host| host.strip_suffix(domain))
+            .is_some_and(|sub| sub.len() > 1 && sub.ends_with('.')),
+        None => trusted == origin,
+    }
+}
@@ -0,0 +1,605 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use flate2::Crc;
+
+/// Record header: CRC-32 of the rest of the record, expiry as Unix time,
+/// then the lengths of the key, the encoded tags and the value.
+const HEADER: usize = 24;
+
+/// Value length marking a deletion.
+const TOMBSTONE: u32 = u32::MAX;
+
+/// A sealed segment is rewritten once less than this share of it is live.
+const LIVE_RATIO: f64 = 0.5;
+
+const SEGMENT_EXTENSION: &str = "seg";
+const MIN_SEGMENT_SIZE: u64 = 64 * 1024;
+const MAX_SEGMENT_SIZE: u64 = 64 * 1024 * 1024;
+
+pub(crate) fn now() -> f64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0.0, |elapsed| elapsed.as_secs_f64())
+}
+
+struct Record {
+    expires_at: f64,
+    key: String,
+    tags: Vec<String>,
+    /// `None` for a tombstone.
+    value: Option<Vec<u8>>,
+}
+
+fn encode(key: &str, tags: &[String], value: Option<&[u8]>, expires_at: f64) -> Vec<u8> {
+    let mut encoded_tags = Vec::new();
+    for tag in tags {
+        encoded_tags.extend_from_slice(&(tag.len() as u32).to_le_bytes());
+        encoded_tags.extend_from_slice(tag.as_bytes());
+    }
+    let value_len = value.map_or(TOMBSTONE, |value| value.len() as u32);
+    let mut record = vec![0; 4];
+    record.extend_from_slice(&expires_at.to_bits().to_le_bytes());
+    record.extend_from_slice(&(key.len() as u32).to_le_bytes());
+    record.extend_from_slice(&(encoded_tags.len() as u32).to_le_bytes());
+    record.extend_from_slice(&value_len.to_le_bytes());
+    record.extend_from_slice(key.as_bytes());
+    record.extend_from_slice(&encoded_tags);
+    record.extend_from_slice(value.unwrap_or_default());
+    let mut crc = Crc::new();
+    crc.update(&record[4..]);
+    record[..4].copy_from_slice(&crc.sum().to_le_bytes());
+    record
+}
+
+fn u32_at(buf: &[u8], at: usize) -> u32 {
+    u32::from_le_bytes(buf[at..at + 4].try_into().expect("4 bytes"))
+}
+
+/// The record at the start of `buf` and its length, or `None` if it is
+/// truncated or corrupt.
+fn decode(buf: &[u8]) -> Option<(usize, Record)> {
+    if buf.len() < HEADER {
+        return None;
+    }
+    let key_len = u32_at(buf, 12) as usize;
+    let tags_len = u32_at(buf, 16) as usize;
+    let value_len = match u32_at(buf, 20) {
+        TOMBSTONE => None,
+        len => Some(len as usize),
+    };
+    let len = HEADER + key_len + tags_len + value_len.unwrap_or(0);
+    let record = buf.get(..len)?;
+    let mut crc = Crc::new();
+    crc.update(&record[4..]);
+    if crc.sum() != u32_at(record, 0) {
+        return None;
+    }
+    let expires_at = f64::from_bits(u64::from_le_bytes(record[4..12].try_into().ok()?));
+    let key = std::str::from_utf8(&record[HEADER..HEADER + key_len]).ok()?;
+    let mut encoded_tags = &record[HEADER + key_len..HEADER + key_len + tags_len];
+    let mut tags = Vec::new();
+    while !encoded_tags.is_empty() {
+        let tag_len = u32_at(encoded_tags.get(..4)?, 0) as usize;
+        let tag = std::str::from_utf8(encoded_tags.get(4..4 + tag_len)?).ok()?;
+        tags.push(tag.to_owned());
+        encoded_tags = &encoded_tags[4 + tag_len..];
+    }
+    let value = value_len.map(|_| record[HEADER + key_len + tags_len..].to_vec());
+    Some((
+        len,
+        Record {
+            expires_at,
+            key: key.to_owned(),
+            tags,
+            value,
+        },
+    ))
+}
+
+/// A value read back from disk.
+pub(crate) struct Stored {
+    pub(crate) value: Vec<u8>,
+    pub(crate) expires_at: f64,
+    pub(crate) tags: Vec<String>,
+}
+
+/// Where the current record of a key lives.
+struct Location {
+    segment: u32,
+    offset: u64,
+    len: u64,
+    expires_at: f64,
+    tags: Vec<String>,
+}
+
+#[derive(Default)]
+struct SegmentStats {
+    bytes: u64,
+    /// Bytes of the records the index points to.
+    live: u64,
+}
+
+/// Options for the store's files: created readable by their owner only,
+/// and never through a symlink.
+fn private_options() -> OpenOptions {
+    #[allow(unused_mut)]
+    let mut options = OpenOptions::new();
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600).custom_flags(libc::O_NOFOLLOW);
+    }
+    options
+}
+
+/// Refuse a directory or file another user owns or can read or write:
+/// values are unpickled, so whoever can write them can run code here.
+#[cfg(unix)]
+fn check_private(metadata: &fs::Metadata, path: &Path) -> io::Result<()> {
+    use std::os::unix::fs::MetadataExt;
+    // SAFETY: geteuid has no preconditions and cannot fail.
+    let euid = unsafe { libc::geteuid() };
+    if metadata.uid() != euid || metadata.mode() & 0o077 != 0 {
+        return Err(io::Error::new(
+            io::ErrorKind::PermissionDenied,
+            format!(
+                "disk cache path '{}' is owned by another user or accessible to others; \
+                 restrict it to its owner (chmod 700 for directories, 600 for files) or \
+                 choose another path",
+                path.display()
+            ),
+        ));
+    }
+    Ok(())
+}
+
+#[cfg(not(unix))]
+fn check_private(_metadata: &fs::Metadata, _path: &Path) -> io::Result<()> {
+    Ok(())
+}
+
+/// Read a whole file after checking it is private.
+fn read_private(path: &Path) -> io::Result<Vec<u8>> {
+    let mut file = private_options().read(true).open(path)?;
+    check_private(&file.metadata()?, path)?;
+    let mut buf = Vec::new();
+    file.read_to_end(&mut buf)?;
+    Ok(buf)
+}
+
+/// Take an exclusive lock on the directory, so two processes never append
+/// to the same segments.
+#[cfg(unix)]
+fn lock(file: &File) -> io::Result<()> {
+    use std::os::unix::io::AsRawFd;
+    // SAFETY: flock only reads the descriptor, which `file` keeps open.
+    if unsafe { libc::flock(file.as_raw_fd(), libc::LOCK_EX | libc::LOCK_NB) } != 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(())
+}
+
+#[cfg(not(unix))]
+fn lock(_file: &File) -> io::Result<()> {
+    Ok(())
+}
+
+/// Persistent key-value store behind `HaskeCache`'s memory tier.
+///
+/// Records are appended to numbered segment files and never modified; an
+/// in-memory index, rebuilt by replaying the segments in order on open,
+/// maps each key to its latest record. Once a sealed segment is mostly
+/// garbage its live records are copied forward and the file is deleted,
+/// and the oldest segments are dropped whole to stay under `max_size`.
+pub(crate) struct DiskStore {
+    dir: PathBuf,
+    /// Holds the directory lock while open.
+    _lock: File,
+    max_size: u64,
+    segment_size: u64,
+    index: HashMap<String, Location>,
+    tags: HashMap<String, HashSet<String>>,
+    segments: BTreeMap<u32, SegmentStats>,
+    active: u32,
+    writer: File,
+    readers: HashMap<u32, File>,
+    size: u64,
+}
+
+impl DiskStore {
+    pub(crate) fn open(dir: &Path, max_size: u64) -> io::Result<Self> {
+        let mut builder = fs::DirBuilder::new();
+        builder.recursive(true);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::DirBuilderExt;
+            builder.mode(0o700);
+        }
+        builder.create(dir)?;
+        check_private(&fs::metadata(dir)?, dir)?;
+        let lock_path = dir.join("LOCK");
+        let lock_file = private_options()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(&lock_path)?;
+        check_private(&lock_file.metadata()?, &lock_path)?;
+        lock(&lock_file).map_err(|err| {
+            io::Error::new(
+                err.kind(),
+                format!(
+                    "disk cache directory '{}' is in use by another process: {err}",
+                    dir.display()
+                ),
+            )
+        })?;
+        let mut ids = Vec::new();
+        for entry in fs::read_dir(dir)? {
+            let path = entry?.path();
+            if path.extension().is_some_and(|ext| ext == SEGMENT_EXTENSION) {
+                if let Some(id) = path.file_stem().and_then(|s| s.to_str()?.parse().ok()) {
+                    ids.push(id);
+                }
+            }
+        }
+        ids.sort_unstable();
+        let active = ids.last().copied().unwrap_or(1);
+        let mut store = Self {
+            dir: dir.to_owned(),
+            _lock: lock_file,
+            max_size,
+            segment_size: (max_size / 8).clamp(MIN_SEGMENT_SIZE, MAX_SEGMENT_SIZE),
+            index: HashMap::new(),
+            tags: HashMap::new(),
+            segments: BTreeMap::new(),
+            active,
+            writer: Self::create(dir, active)?,
+            readers: HashMap::new(),
+            size: 0,
+        };
+        let now = now();
+        for id in ids {
+            store.replay(id, now)?;
+        }
+        store.segments.entry(active).or_default();
+        store.shrink()?;
+        Ok(store)
+    }
+
+    fn path(dir: &Path, id: u32) -> PathBuf {
+        dir.join(format!("{id:010}.{SEGMENT_EXTENSION}"))
+    }
+
+    fn create(dir: &Path, id: u32) -> io::Result<File> {
+        let path = Self::path(dir, id);
+        let file = private_options().create(true).append(true).open(&path)?;
+        check_private(&file.metadata()?, &path)?;
+        Ok(file)
+    }
+
+    /// Index the records of segment `id`, cutting off a torn or corrupt
+    /// tail left by a crash.
+    fn replay(&mut self, id: u32, now: f64) -> io::Result<()> {
+        let path = Self::path(&self.dir, id);
+        let buf = read_private(&path)?;
+        let mut offset = 0;
+        while offset < buf.len() {
+            let Some((len, record)) = decode(&buf[offset..]) else {
+                private_options()
+                    .write(true)
+                    .open(&path)?
+                    .set_len(offset as u64)?;
+                break;
+            };
+            self.segments.entry(id).or_default().bytes += len as u64;
+            self.size += len as u64;
+            self.forget(&record.key);
+            if record.value.is_some() && record.expires_at > now {
+                self.remember(record, id, offset as u64, len as u64);
+            }
+            offset += len;
+        }
+        self.segments.entry(id).or_default();
+        Ok(())
+    }
+
+    fn remember(&mut self, record: Record, segment: u32, offset: u64, len: u64) {
+        if let Some(stats) = self.segments.get_mut(&segment) {
+            stats.live += len;
+        }
+        for tag in &record.tags {
+            self.tags
+                .entry(tag.clone())
+                .or_default()
+                .insert(record.key.clone());
+        }
+        self.index.insert(
+            record.key,
+            Location {
+                segment,
+                offset,
+                len,
+                expires_at: record.expires_at,
+                tags: record.tags,
+            },
+        );
+    }
+
+    /// Drop `key` from the index, leaving the files alone.
+    fn forget(&mut self, key: &str) -> Option<Location> {
+        let location = self.index.remove(key)?;
+        if let Some(stats) = self.segments.get_mut(&location.segment) {
+            stats.live -= location.len;
+        }
+        for tag in &location.tags {
+            if let Some(keys) = self.tags.get_mut(tag) {
+                keys.remove(key);
+                if keys.is_empty() {
+                    self.tags.remove(tag);
+                }
+            }
+        }
+        Some(location)
+    }
+
+    /// Seal the active segment and start the next one.
+    fn roll(&mut self) -> io::Result<()> {
+        self.active += 1;
+        self.writer = Self::create(&self.dir, self.active)?;
+        self.segments.entry(self.active).or_default();
+        Ok(())
+    }
+
+    /// Append `record` to the active segment, returning where it went.
+    fn append(&mut self, record: &[u8]) -> io::Result<(u32, u64)> {
+        let len = record.len() as u64;
+        let active = self.segments.entry(self.active).or_default().bytes;
+        if active > 0 && active + len > self.segment_size {
+            self.roll()?;
+        }
+        self.writer.write_all(record)?;
+        let stats = self.segments.entry(self.active).or_default();
+        let offset = stats.bytes;
+        stats.bytes += len;
+        self.size += len;
+        Ok((self.active, offset))
+    }
+
+    /// Store `value` under `key`, returning false if it is larger than the
+    /// whole store.
+    pub(crate) fn put(
+        &mut self,
+        key: &str,
+        value: &[u8],
+        expires_at: f64,
+        tags: &[String],
+    ) -> io::Result<bool> {
+        let record = (value.len() < TOMBSTONE as usize)
+            .then(|| encode(key, tags, Some(value), expires_at))
+            .filter(|record| record.len() as u64 <= self.max_size);
+        let Some(record) = record else {
+            self.delete(key)?;
+            return Ok(false);
+        };
+        let (segment, offset) = self.append(&record)?;
+        self.forget(key);
+        let located = Record {
+            expires_at,
+            key: key.to_owned(),
+            tags: tags.to_vec(),
+            value: None,
+        };
+        self.remember(located, segment, offset, record.len() as u64);
+        self.shrink()?;
+        Ok(self.index.contains_key(key))
+    }
+
+    /// The value, expiry and tags stored under `key`, unless expired.
+    pub(crate) fn get(&mut self, key: &str) -> io::Result<Option<Stored>> {
+        let Some(location) = self.index.get(key) else {
+            return Ok(None);
+        };
+        if location.expires_at <= now() {
+            self.forget(key);
+            return Ok(None);
+        }
+        let (segment, offset, len) = (location.segment, location.offset, location.len);
+        let reader = match self.readers.entry(segment) {
+            std::collections::hash_map::Entry::Occupied(entry) => entry.into_mut(),
+            std::collections::hash_map::Entry::Vacant(entry) => {
+                entry.insert(File::open(Self::path(&self.dir, segment))?)
+            }
+        };
+        let mut buf = vec![0; len as usize];
+        reader.seek(SeekFrom::Start(offset))?;
+        reader.read_exact(&mut buf)?;
+        match decode(&buf) {
+            Some((_, record)) if record.key == key => Ok(record.value.map(|value| Stored {
+                value,
+                expires_at: record.expires_at,
+                tags: record.tags,
+            })),
+            _ => {
+                self.forget(key);
+                Ok(None)
+            }
+        }
+    }
+
+    pub(crate) fn contains(&self, key: &str) -> bool {
+        self.index
+            .get(key)
+            .is_some_and(|location| location.expires_at > now())
+    }
+
+    /// Delete `key`, returning whether it was stored.
+    pub(crate) fn delete(&mut self, key: &str) -> io::Result<bool> {
+        if self.forget(key).is_none() {
+            return Ok(false);
+        }
+        self.append(&encode(key, &[], None, 0.0))?;
+        self.shrink()?;
+        Ok(true)
+    }
+
+    /// Delete the keys tagged `tag` that start with `prefix`, returning them.
+    pub(crate) fn invalidate(&mut self, tag: &str, prefix: &str) -> io::Result<Vec<String>> {
+        let keys: Vec<String> = self
+            .tags
+            .get(tag)
+            .into_iter()
+            .flatten()
+            .filter(|key| key.starts_with(prefix))
+            .cloned()
+            .collect();
+        self.delete_all(keys)
+    }
+
+    /// Delete every key starting with `prefix`.
+    pub(crate) fn delete_prefix(&mut self, prefix: &str) -> io::Result<Vec<String>> {
+        let keys: Vec<String> = self
+            .index
+            .keys()
+            .filter(|key| key.starts_with(prefix))
+            .cloned()
+            .collect();
+        self.delete_all(keys)
+    }
+
+    fn delete_all(&mut self, keys: Vec<String>) -> io::Result<Vec<String>> {
+        for key in &keys {
+            self.delete(key)?;
+        }
+        Ok(keys)
+    }
+
+    pub(crate) fn clear(&mut self) -> io::Result<()> {
+        self.readers.clear();
+        for &id in self.segments.keys() {
+            match fs::remove_file(Self::path(&self.dir, id)) {
+                Err(err) if err.kind() != io::ErrorKind::NotFound => return Err(err),
+                _ => {}
+            }
+        }
+        self.index.clear();
+        self.tags.clear();
+        self.segments.clear();
+        self.size = 0;
+        self.roll()
+    }
+
+    /// Bytes of all segment files.
+    pub(crate) fn size(&self) -> u64 {
+        self.size
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.index.len()
+    }
+
+    /// Rewrite every segment holding garbage.
+    pub(crate) fn compact(&mut self) -> io::Result<()> {
+        if self
+            .segments
+            .get(&self.active)
+            .is_some_and(|stats| stats.live < stats.bytes)
+        {
+            self.roll()?;
+        }
+        let sealed: Vec<u32> = self
+            .segments
+            .iter()
+            .filter(|&(&id, stats)| id != self.active && stats.live < stats.bytes)
+            .map(|(&id, _)| id)
+            .collect();
+        for id in sealed {
+            self.compact_segment(id)?;
+        }
+        Ok(())
+    }
+
+    /// Get back under `max_size`: first rewrite mostly-garbage segments,
+    /// then drop the oldest ones.
+    fn shrink(&mut self) -> io::Result<()> {
+        if self.size <= self.max_size {
+            return Ok(());
+        }
+        let sparse: Vec<u32> = self
+            .segments
+            .iter()
+            .filter(|&(&id, stats)| {
+                id != self.active && (stats.live as f64) < stats.bytes as f64 * LIVE_RATIO
+            })
+            .map(|(&id, _)| id)
+            .collect();
+        for id in sparse {
+            self.compact_segment(id)?;
+        }
+        while self.size > self.max_size {
+            let Some(&oldest) = self.segments.keys().next() else {
+                break;
+            };
+            if oldest == self.active {
+                break;
+            }
+            let keys: Vec<String> = self
+                .index
+                .iter()
+                .filter(|(_, location)| location.segment == oldest)
+                .map(|(key, _)| key.clone())
+                .collect();
+            for key in keys {
+                self.forget(&key);
+            }
+            self.remove_segment(oldest)?;
+        }
+        Ok(())
+    }
+
+    /// Copy the live records of segment `id` forward and delete it.
+    ///
+    /// Tombstones are kept while older segments might still hold the
+    /// records they delete, so those cannot come back on the next open.
+    fn compact_segment(&mut self, id: u32) -> io::Result<()> {
+        let buf = read_private(&Self::path(&self.dir, id))?;
+        let oldest = self.segments.keys().next() == Some(&id);
+        let now = now();
+        let mut offset = 0;
+        while let Some((len, record)) = decode(&buf[offset..]) {
+            let raw = &buf[offset..offset + len];
+            let current = self
+                .index
+                .get(&record.key)
+                .is_some_and(|location| location.segment == id && location.offset == offset as u64);
+            if record.value.is_none() {
+                if !oldest && !self.index.contains_key(&record.key) {
+                    self.append(raw)?;
+                }
+            } else if current {
+                self.forget(&record.key);
+                if record.expires_at > now {
+                    let (segment, moved) = self.append(raw)?;
+                    self.remember(record, segment, moved, len as u64);
+                }
+            }
+            offset += len;
+        }
+        self.remove_segment(id)
+    }
+
+    fn remove_segment(&mut self, id: u32) -> io::Result<()> {
+        self.readers.remove(&id);
+        if let Some(stats) = self.segments.remove(&id) {
+            self.size -= stats.bytes;
+        }
+        match fs::remove_file(Self::path(&self.dir, id)) {
+            Err(err) if err.kind() != io::ErrorKind::NotFound => Err(err),
+            _ => Ok(()),
+        }
+    }
+}
@@ -0,0 +1,378 @@
+use std::collections::VecDeque;
+
+use pyo3::exceptions::PyStopIteration;
+use pyo3::intern;
+use pyo3::prelude::*;
+use pyo3::sync::PyOnceLock;
+use pyo3::types::{PyBytes, PyDict, PyList, PyString, PyTuple, PyType};
+
+use crate::json::py_to_value;
+
+static REQUEST_CLASS: PyOnceLock<Py<PyType>> = PyOnceLock::new();
+
+enum Stage {
+    /// Collecting `http.request` messages.
+    Receive,
+    /// Awaiting the route handler.
+    Handler,
+    /// Awaiting an ASGI response object returned by the handler.
+    Respond,
+    /// Sending queued response messages.
+    Send,
+    Done,
+}
+
+/// What the awaiting coroutine passed in on resumption.
+pub(crate) enum Resume<'py> {
+    Send(Bound<'py, PyAny>),
+    Throw(Bound<'py, PyAny>),
+}
+
+/// Awaitable returned by `HaskeApp.dispatch`.
+///
+/// A hand-written coroutine: each Python awaitable involved in the exchange
+/// (`receive()`, the handler, `send(...)`) is driven with `yield from`
+/// semantics. Resolves to `True` once the request was answered, or `False`
+/// when it should be passed on to the next application.
+#[pyclass(module = "haske")]
+pub struct Dispatch {
+    scope: Py<PyAny>,
+    receive: Py<PyAny>,
+    send: Py<PyAny>,
+    handler: Option<Py<PyAny>>,
+    params: Option<Py<PyDict>>,
+    default_headers: Vec<(String, String)>,
+    head: bool,
+    body: Vec<u8>,
+    outbox: VecDeque<Py<PyDict>>,
+    awaiting: Option<Py<PyAny>>,
+    stage: Stage,
+    handled: bool,
+    /// Whether response messages may already have reached the client, in
+    /// which case an error can no longer be turned into an error response.
+    #[pyo3(get)]
+    response_started: bool,
+}
+
+impl Dispatch {
+    /// A dispatch that resolves to `False` until `route` or `reply` gives it work.
+    pub(crate) fn new(
+        scope: Py<PyAny>,
+        receive: Py<PyAny>,
+        send: Py<PyAny>,
+        default_headers: Vec<(String, String)>,
+        head: bool,
+    ) -> Self {
+        Self {
+            scope,
+            receive,
+            send,
+            handler: None,
+            params: None,
+            default_headers,
+            head,
+            body: Vec::new(),
+            outbox: VecDeque::new(),
+            awaiting: None,
+            stage: Stage::Done,
+            handled: false,
+            response_started: false,
+        }
+    }
+
+    /// Read the body, call `handler` and send whatever it returns.
+    pub(crate) fn route(
+        &mut self,
+        py: Python<'_>,
+        handler: Py<PyAny>,
+        params: Py<PyDict>,
+    ) -> PyResult<()> {
+        self.handler = Some(handler);
+        self.params = Some(params);
+        self.handled = true;
+        self.stage = Stage::Receive;
+        if is_multipart(self.scope.bind(py))? {
+            // Leave the body on the channel so `Request.form()` can stream it.
+            return self.call_handler(py, None);
+        }
+        let receiving = self.receive.bind(py).call0()?;
+        self.await_(&receiving)
+    }
+
+    /// Send a fixed plain text response, e.g. a 405.
+    pub(crate) fn reply(
+        &mut self,
+        py: Python<'_>,
+        status: u16,
+        text: &str,
+        extra_headers: &[(&str, String)],
+    ) -> PyResult<()> {
+        self.handled = true;
+        self.queue_response(
+            py,
+            status,
+            Some("text/plain; charset=utf-8"),
+            text.as_bytes(),
+            extra_headers,
+        )
+    }
+
+    fn await_(&mut self, awaitable: &Bound<'_, PyAny>) -> PyResult<()> {
+        self.awaiting = Some(
+            awaitable
+                .call_method0(intern!(awaitable.py(), "__await__"))?
+                .unbind(),
+        );
+        Ok(())
+    }
+
+    fn resume<'py>(&mut self, py: Python<'py>, mut input: Resume<'py>) -> PyResult<Py<PyAny>> {
+        loop {
+            let Some(awaiting) = self.awaiting.as_ref().map(|a| a.bind(py).clone()) else {
+                return Err(match input {
+                    Resume::Throw(exc) => PyErr::from_value(exc),
+                    Resume::Send(_) => PyStopIteration::new_err((self.handled,)),
+                });
+            };
+            let result = match input {
+                Resume::Send(value) if value.is_none() => {
+                    awaiting.call_method0(intern!(py, "__next__"))
+                }
+                Resume::Send(value) => awaiting.call_method1(intern!(py, "send"), (value,)),
+                Resume::Throw(exc) => awaiting.call_method1(intern!(py, "throw"), (exc,)),
+            };
+            match result {
+                Ok(yielded) => return Ok(yielded.unbind()),
+                Err(err) if err.is_instance_of::<PyStopIteration>(py) => {
+                    self.awaiting = None;
+                    let value = err.value(py).getattr(intern!(py, "value"))?;
+                    if let Err(err) = self.advance(py, value) {
+                        self.stage = Stage::Done;
+                        self.awaiting = None;
+                        return Err(err);
+                    }
+                    input = Resume::Send(py.None().into_bound(py));
+                }
+                Err(err) => {
+                    self.stage = Stage::Done;
+                    self.awaiting = None;
+                    return Err(err);
+                }
+            }
+        }
+    }
+
+    /// Move to the next stage with the result of the awaitable that just finished.
+    fn advance<'py>(&mut self, py: Python<'py>, value: Bound<'py, PyAny>) -> PyResult<()> {
+        match self.stage {
+            Stage::Receive => {
+                let message = value.downcast::<PyDict>()?;
+                if let Some(kind) = message.get_item("type")? {
+                    if kind.downcast::<PyString>()?.to_cow()? == "http.disconnect" {
+                        self.stage = Stage::Done;
+                        return Ok(());
+                    }
+                }
+                if let Some(chunk) = message.get_item("body")? {
+                    self.body
+                        .extend_from_slice(chunk.downcast::<PyBytes>()?.as_bytes());
+                }
+                let more_body = match message.get_item("more_body")? {
+                    Some(more) => more.is_truthy()?,
+                    None => false,
+                };
+                if more_body {
+                    let receiving = self.receive.bind(py).call0()?;
+                    return self.await_(&receiving);
+                }
+
+                let body = std::mem::take(&mut self.body);
+                self.call_handler(py, Some(PyBytes::new(py, &body)))
+            }
+            Stage::Handler => self.respond(py, value),
+            Stage::Respond => {
+                self.stage = Stage::Done;
+                Ok(())
+            }
+            Stage::Send => self.send_next(py),
+            Stage::Done => Ok(()),
+        }
+    }
+
+    fn call_handler(&mut self, py: Python<'_>, body: Option<Bound<'_, PyBytes>>) -> PyResult<()> {
+        let request = REQUEST_CLASS
+            .import(py, "haske.request", "Request")?
+            .call1((
+                self.scope.bind(py),
+                self.receive.bind(py),
+                self.send.bind(py),
+                self.params.take(),
+                body,
+            ))?;
+        let handler = self.handler.take().expect("route dispatch without handler");
+        let result = handler.bind(py).call1((request,))?;
+        self.stage = Stage::Handler;
+        // Plain functions are accepted as well as coroutines.
+        if result.hasattr(intern!(py, "__await__"))? {
+            self.await_(&result)
+        } else {
+            self.respond(py, result)
+        }
+    }
+
+    /// Turn a handler return value into ASGI messages, mirroring
+    /// `Haske._convert_to_response`.
+    fn respond<'py>(&mut self, py: Python<'py>, result: Bound<'py, PyAny>) -> PyResult<()> {
+        if result.is_instance_of::<PyDict>()
+            || result.is_instance_of::<PyList>()
+            || result.is_instance_of::<PyTuple>()
+        {
+            let body = serde_json::to_vec(&py_to_value(&result)?)
+                .map_err(|e| pyo3::exceptions::PyValueError::new_err(e.to_string()))?;
+            self.queue_response(py, 200, Some("application/json"), &body, &[])
+        } else if let Ok(text) = result.downcast::<PyString>() {
+            let text = text.to_cow()?;
+            self.queue_response(
+                py,
+                200,
+                Some("text/html; charset=utf-8"),
+                text.as_bytes(),
+                &[],
+            )
+        } else if result.is_callable() {
+            // A Starlette/Haske response object: let it send itself.
+            let headers = result.getattr(intern!(py, "headers"))?;
+            for (name, value) in &self.default_headers {
+                headers.set_item(name, value)?;
+            }
+            let sending = result.call1((
+                self.scope.bind(py),
+                self.receive.bind(py),
+                self.send.bind(py),
+            ))?;
+            self.stage = Stage::Respond;
+            self.response_started = true;
+            self.await_(&sending)
+        } else {
+            let text = result.str()?;
+            self.queue_response(py, 200, None, text.to_cow()?.as_bytes(), &[])
+        }
+    }
+
+    fn queue_response(
+        &mut self,
+        py: Python<'_>,
+        status: u16,
+        content_type: Option<&str>,
+        body: &[u8],
+        extra_headers: &[(&str, String)],
+    ) -> PyResult<()> {
+        let headers = PyList::empty(py);
+        let push = |name: &str, value: &str| {
+            headers.append((
+                PyBytes::new(py, name.as_bytes()),
+                PyBytes::new(py, value.as_bytes()),
+            ))
+        };
+        push("content-length", &body.len().to_string())?;
+        if let Some(content_type) = content_type {
+            push("content-type", content_type)?;
+        }
+        for (name, value) in extra_headers {
+            push(name, value)?;
+        }
+        for (name, value) in &self.default_headers {
+            push(&name.to_ascii_lowercase(), value)?;
+        }
+
+        let start = PyDict::new(py);
+        start.set_item("type", "http.response.start")?;
+        start.set_item("status", status)?;
+        start.set_item("headers", headers)?;
+        let end = PyDict::new(py);
+        end.set_item("type", "http.response.body")?;
+        end.set_item("body", PyBytes::new(py, if self.head { b"" } else { body }))?;
+
+        self.outbox.push_back(start.unbind());
+        self.outbox.push_back(end.unbind());
+        self.stage = Stage::Send;
+        self.send_next(py)
+    }
+
+    fn send_next(&mut self, py: Python<'_>) -> PyResult<()> {
+        match self.outbox.pop_front() {
+            Some(message) => {
+                self.response_started = true;
+                let sending = self.send.bind(py).call1((message,))?;
+                self.await_(&sending)
+            }
+            None => {
+                self.stage = Stage::Done;
+                Ok(())
+            }
+        }
+    }
+}
+
+#[pymethods]
+impl Dispatch {
+    fn __await__(slf: PyRef<'_, Self>) -> PyRef<'_, Self> {
+        slf
+    }
+
+    fn __iter__(slf: PyRef<'_, Self>) -> PyRef<'_, Self> {
+        slf
+    }
+
+    fn __next__(&mut self, py: Python<'_>) -> PyResult<Py<PyAny>> {
+        self.resume(py, Resume::Send(py.None().into_bound(py)))
+    }
+
+    fn send<'py>(&mut self, py: Python<'py>, value: Bound<'py, PyAny>) -> PyResult<Py<PyAny>> {
+        self.resume(py, Resume::Send(value))
+    }
+
+    /// Accepts the legacy `(type, value, traceback)` form; the traceback is
+    /// already attached to the exception.
+    #[pyo3(signature = (typ, val=None, _tb=None))]
+    fn throw<'py>(
+        &mut self,
+        py: Python<'py>,
+        typ: Bound<'py, PyAny>,
+        val: Option<Bound<'py, PyAny>>,
+        _tb: Option<Bound<'py, PyAny>>,
+    ) -> PyResult<Py<PyAny>> {
+        let exc = match val {
+            Some(val) if !val.is_none() => val,
+            _ if typ.is_instance_of::<PyType>() => typ.call0()?,
+            _ => typ,
+        };
+        self.resume(py, Resume::Throw(exc))
+    }
+
+    fn close(&mut self, py: Python<'_>) -> PyResult<()> {
+        self.stage = Stage::Done;
+        match self.awaiting.take() {
+            Some(awaiting) if awaiting.bind(py).hasattr(intern!(py, "close"))? => {
+                awaiting.bind(py).call_method0(intern!(py, "close"))?;
+                Ok(())
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+fn is_multipart(scope: &Bound<'_, PyAny>) -> PyResult<bool> {
+    let Ok(headers) = scope.get_item("headers") else {
+        return Ok(false);
+    };
+    for header in headers.try_iter()? {
+        let (name, value): (Vec<u8>, Vec<u8>) = header?.extract()?;
+        if name.eq_ignore_ascii_case(b"content-type") {
+            return Ok(value
+                .get(..19)
+                .is_some_and(|v| v.eq_ignore_ascii_case(b"multipart/form-data")));
+        }
+    }
+    Ok(false)
+}
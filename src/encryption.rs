@@ -0,0 +1,170 @@
+use aes_gcm::aead::{Aead, KeyInit, Payload};
+use aes_gcm::{Aes256Gcm, Nonce};
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use chacha20poly1305::{XChaCha20Poly1305, XNonce};
+use hkdf::Hkdf;
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+use sha2::Sha256;
+
+use crate::crypto::{random_bytes, secret_bytes};
+use crate::keyring::Keyring;
+
+#[derive(Clone, Copy)]
+enum Cipher {
+    Aes256Gcm,
+    XChaCha20Poly1305,
+}
+
+impl Cipher {
+    fn from_name(name: &str) -> PyResult<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "aes-256-gcm" => Ok(Self::Aes256Gcm),
+            "xchacha20-poly1305" => Ok(Self::XChaCha20Poly1305),
+            _ => Err(PyValueError::new_err(format!(
+                "unsupported cipher {name:?}; expected 'aes-256-gcm' or 'xchacha20-poly1305'"
+            ))),
+        }
+    }
+
+    /// Leading byte of a token, identifying the cipher that produced it.
+    fn tag(self) -> u8 {
+        match self {
+            Self::Aes256Gcm => 1,
+            Self::XChaCha20Poly1305 => 2,
+        }
+    }
+
+    fn from_tag(tag: u8) -> Option<Self> {
+        match tag {
+            1 => Some(Self::Aes256Gcm),
+            2 => Some(Self::XChaCha20Poly1305),
+            _ => None,
+        }
+    }
+
+    fn nonce_len(self) -> usize {
+        match self {
+            Self::Aes256Gcm => 12,
+            Self::XChaCha20Poly1305 => 24,
+        }
+    }
+
+    /// Per-cipher key derived from the application secret with HKDF-SHA256.
+    fn derive_key(self, secret: &[u8]) -> [u8; 32] {
+        let info: &[u8] = match self {
+            Self::Aes256Gcm => b"haske cookie encryption aes-256-gcm",
+            Self::XChaCha20Poly1305 => b"haske cookie encryption xchacha20-poly1305",
+        };
+        let mut key = [0u8; 32];
+        Hkdf::<Sha256>::new(None, secret)
+            .expand(info, &mut key)
+            .expect("32 bytes is a valid HKDF-SHA256 output length");
+        key
+    }
+
+    fn seal(self, secret: &[u8], nonce: &[u8], payload: Payload<'_, '_>) -> Vec<u8> {
+        let key = self.derive_key(secret);
+        let sealed = match self {
+            Self::Aes256Gcm => {
+                Aes256Gcm::new(&key.into()).encrypt(Nonce::from_slice(nonce), payload)
+            }
+            Self::XChaCha20Poly1305 => {
+                XChaCha20Poly1305::new(&key.into()).encrypt(XNonce::from_slice(nonce), payload)
+            }
+        };
+        sealed.expect("cookie payloads are far below the AEAD length limit")
+    }
+
+    fn open(self, secret: &[u8], nonce: &[u8], payload: Payload<'_, '_>) -> Option<Vec<u8>> {
+        let key = self.derive_key(secret);
+        match self {
+            Self::Aes256Gcm => {
+                Aes256Gcm::new(&key.into()).decrypt(Nonce::from_slice(nonce), payload)
+            }
+            Self::XChaCha20Poly1305 => {
+                XChaCha20Poly1305::new(&key.into()).decrypt(XNonce::from_slice(nonce), payload)
+            }
+        }
+        .ok()
+    }
+}
+
+/// Encrypt `payload` for the cookie called `name`.
+///
+/// The token is `base64url(cipher || nonce || ciphertext || tag)` with a
+/// random nonce. The key is derived from `secret` (`str`, `bytes` or a
+/// `Keyring`, whose active key is used) with HKDF-SHA256, and the cookie name
+/// is bound as associated data, so a token cannot be replayed under another
+/// cookie name.
+#[pyfunction]
+#[pyo3(signature = (secret, name, payload, cipher="aes-256-gcm"))]
+pub fn encrypt_cookie(
+    secret: &Bound<'_, PyAny>,
+    name: &str,
+    payload: &str,
+    cipher: &str,
+) -> PyResult<String> {
+    let cipher = Cipher::from_name(cipher)?;
+    let secret = match secret.downcast::<Keyring>() {
+        Ok(keyring) => keyring.get().active_secret().to_vec(),
+        Err(_) => secret_bytes(secret)?,
+    };
+    let nonce = random_bytes(cipher.nonce_len());
+    let ciphertext = cipher.seal(
+        &secret,
+        &nonce,
+        Payload {
+            msg: payload.as_bytes(),
+            aad: name.as_bytes(),
+        },
+    );
+
+    let mut token = Vec::with_capacity(1 + nonce.len() + ciphertext.len());
+    token.push(cipher.tag());
+    token.extend_from_slice(&nonce);
+    token.extend_from_slice(&ciphertext);
+    Ok(URL_SAFE_NO_PAD.encode(token))
+}
+
+/// Decrypt a token from `encrypt_cookie`, returning `None` if it was altered,
+/// made for another cookie name or encrypted under an unknown key.
+///
+/// With a `Keyring` every key is tried, so cookies survive a rotation.
+#[pyfunction]
+pub fn decrypt_cookie(
+    secret: &Bound<'_, PyAny>,
+    name: &str,
+    token: &str,
+) -> PyResult<Option<String>> {
+    let secrets = match secret.downcast::<Keyring>() {
+        Ok(keyring) => keyring.get().secrets().map(<[u8]>::to_vec).collect(),
+        Err(_) => vec![secret_bytes(secret)?],
+    };
+    let Ok(raw) = URL_SAFE_NO_PAD.decode(token) else {
+        return Ok(None);
+    };
+    let Some((&tag, rest)) = raw.split_first() else {
+        return Ok(None);
+    };
+    let Some(cipher) = Cipher::from_tag(tag) else {
+        return Ok(None);
+    };
+    if rest.len() < cipher.nonce_len() {
+        return Ok(None);
+    }
+    let (nonce, ciphertext) = rest.split_at(cipher.nonce_len());
+
+    let plaintext = secrets.iter().find_map(|secret| {
+        cipher.open(
+            secret,
+            nonce,
+            Payload {
+                msg: ciphertext,
+                aad: name.as_bytes(),
+            },
+        )
+    });
+    Ok(plaintext.and_then(|bytes| String::from_utf8(bytes).ok()))
+}
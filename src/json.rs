@@ -0,0 +1,208 @@
+use std::io;
+
+use pyo3::exceptions::{PyTypeError, PyValueError};
+use pyo3::prelude::*;
+use pyo3::types::{PyBool, PyDict, PyFloat, PyInt, PyList, PyString, PyTuple};
+use serde_json::ser::Formatter;
+use serde_json::{Map, Number, Value};
+
+/// Parse JSON bytes into Python objects, returning `None` on invalid input.
+#[pyfunction]
+pub fn json_loads_bytes(py: Python<'_>, data: &[u8]) -> PyResult<Option<Py<PyAny>>> {
+    match serde_json::from_slice::<Value>(data) {
+        Ok(value) => Ok(Some(value_to_py(py, &value)?.unbind())),
+        Err(_) => Ok(None),
+    }
+}
+
+/// Serialize a Python object to a JSON string using the stdlib `json.dumps` layout.
+#[pyfunction]
+pub fn json_dumps_obj(obj: &Bound<'_, PyAny>) -> PyResult<String> {
+    let value = py_to_value(obj)?;
+    let mut out = Vec::with_capacity(128);
+    let mut ser = serde_json::Serializer::with_formatter(&mut out, PythonFormatter);
+    serde::Serialize::serialize(&value, &mut ser)
+        .map_err(|e| PyValueError::new_err(e.to_string()))?;
+    // serde_json only ever writes valid UTF-8.
+    Ok(String::from_utf8(out).expect("serde_json produced invalid UTF-8"))
+}
+
+#[pyfunction]
+pub fn json_is_valid(data: &[u8]) -> bool {
+    serde_json::from_slice::<serde::de::IgnoredAny>(data).is_ok()
+}
+
+/// Return the raw JSON text of a top-level field, or `None` if it is absent.
+#[pyfunction]
+pub fn json_extract_field(data: &[u8], field: &str) -> Option<String> {
+    let value: Value = serde_json::from_slice(data).ok()?;
+    value.get(field).map(Value::to_string)
+}
+
+pub(crate) fn value_to_py<'py>(py: Python<'py>, value: &Value) -> PyResult<Bound<'py, PyAny>> {
+    Ok(match value {
+        Value::Null => py.None().into_bound(py),
+        Value::Bool(b) => PyBool::new(py, *b).to_owned().into_any(),
+        Value::Number(n) => {
+            if let Some(i) = n.as_i64() {
+                i.into_pyobject(py)?.into_any()
+            } else if let Some(u) = n.as_u64() {
+                u.into_pyobject(py)?.into_any()
+            } else {
+                n.as_f64().unwrap_or(f64::NAN).into_pyobject(py)?.into_any()
+            }
+        }
+        Value::String(s) => PyString::new(py, s).into_any(),
+        Value::Array(items) => {
+            let list = PyList::empty(py);
+            for item in items {
+                list.append(value_to_py(py, item)?)?;
+            }
+            list.into_any()
+        }
+        Value::Object(map) => {
+            let dict = PyDict::new(py);
+            for (key, item) in map {
+                dict.set_item(key, value_to_py(py, item)?)?;
+            }
+            dict.into_any()
+        }
+    })
+}
+
+/// Converts a value `py_to_value` does not know into one it does, like the
+/// `default` argument of `json.dumps`; `None` means not serializable.
+pub(crate) type Fallback = for<'py> fn(&Bound<'py, PyAny>) -> PyResult<Option<Bound<'py, PyAny>>>;
+
+pub(crate) fn py_to_value(obj: &Bound<'_, PyAny>) -> PyResult<Value> {
+    py_to_value_with(obj, |_| Ok(None))
+}
+
+pub(crate) fn py_to_value_with(obj: &Bound<'_, PyAny>, fallback: Fallback) -> PyResult<Value> {
+    if obj.is_none() {
+        Ok(Value::Null)
+    } else if let Ok(b) = obj.downcast::<PyBool>() {
+        Ok(Value::Bool(b.is_true()))
+    } else if obj.is_instance_of::<PyInt>() {
+        if let Ok(i) = obj.extract::<i64>() {
+            Ok(Value::Number(i.into()))
+        } else if let Ok(u) = obj.extract::<u64>() {
+            Ok(Value::Number(u.into()))
+        } else {
+            Err(PyValueError::new_err("integer out of range for JSON"))
+        }
+    } else if let Ok(f) = obj.downcast::<PyFloat>() {
+        Number::from_f64(f.value())
+            .map(Value::Number)
+            .ok_or_else(|| {
+                PyValueError::new_err("Out of range float values are not JSON compliant")
+            })
+    } else if let Ok(s) = obj.downcast::<PyString>() {
+        Ok(Value::String(s.to_cow()?.into_owned()))
+    } else if let Ok(dict) = obj.downcast::<PyDict>() {
+        let mut map = Map::with_capacity(dict.len());
+        for (key, item) in dict.iter() {
+            map.insert(dict_key(&key)?, py_to_value_with(&item, fallback)?);
+        }
+        Ok(Value::Object(map))
+    } else if let Ok(list) = obj.downcast::<PyList>() {
+        list.iter()
+            .map(|item| py_to_value_with(&item, fallback))
+            .collect::<PyResult<_>>()
+            .map(Value::Array)
+    } else if let Ok(tuple) = obj.downcast::<PyTuple>() {
+        tuple
+            .iter()
+            .map(|item| py_to_value_with(&item, fallback))
+            .collect::<PyResult<_>>()
+            .map(Value::Array)
+    } else {
+        match fallback(obj)? {
+            Some(replacement) => py_to_value_with(&replacement, fallback),
+            None => Err(not_serializable(obj)),
+        }
+    }
+}
+
+fn dict_key(key: &Bound<'_, PyAny>) -> PyResult<String> {
+    if let Ok(s) = key.downcast::<PyString>() {
+        return Ok(s.to_cow()?.into_owned());
+    }
+    match py_to_value(key) {
+        Ok(Value::String(s)) => Ok(s),
+        Ok(Value::Null) => Ok("null".to_owned()),
+        Ok(value @ (Value::Bool(_) | Value::Number(_))) => Ok(value.to_string()),
+        _ => Err(PyTypeError::new_err(format!(
+            "keys must be str, int, float, bool or None, not {}",
+            type_name(key)
+        ))),
+    }
+}
+
+pub(crate) fn not_serializable(obj: &Bound<'_, PyAny>) -> PyErr {
+    PyTypeError::new_err(format!(
+        "Object of type {} is not JSON serializable",
+        type_name(obj)
+    ))
+}
+
+fn type_name(obj: &Bound<'_, PyAny>) -> String {
+    obj.get_type()
+        .name()
+        .map(|n| n.to_string())
+        .unwrap_or_else(|_| "object".to_owned())
+}
+
+/// Formatter matching the defaults of Python's `json.dumps`: its separators
+/// and `ensure_ascii`, which escapes DEL and non-ASCII text as `\uXXXX`.
+struct PythonFormatter;
+
+impl Formatter for PythonFormatter {
+    fn begin_array_value<W: ?Sized + io::Write>(
+        &mut self,
+        writer: &mut W,
+        first: bool,
+    ) -> io::Result<()> {
+        if first {
+            Ok(())
+        } else {
+            writer.write_all(b", ")
+        }
+    }
+
+    fn begin_object_key<W: ?Sized + io::Write>(
+        &mut self,
+        writer: &mut W,
+        first: bool,
+    ) -> io::Result<()> {
+        if first {
+            Ok(())
+        } else {
+            writer.write_all(b", ")
+        }
+    }
+
+    fn begin_object_value<W: ?Sized + io::Write>(&mut self, writer: &mut W) -> io::Result<()> {
+        writer.write_all(b": ")
+    }
+
+    fn write_string_fragment<W: ?Sized + io::Write>(
+        &mut self,
+        writer: &mut W,
+        fragment: &str,
+    ) -> io::Result<()> {
+        let mut start = 0;
+        for (index, c) in fragment.char_indices() {
+            if c.is_ascii() && c != '\x7f' {
+                continue;
+            }
+            writer.write_all(&fragment.as_bytes()[start..index])?;
+            // Characters outside the BMP become a surrogate pair.
+            for unit in c.encode_utf16(&mut [0; 2]) {
+                write!(writer, "\\u{unit:04x}")?;
+            }
+            start = index + c.len_utf8();
+        }
+        writer.write_all(&fragment.as_bytes()[start..])
+    }
+}
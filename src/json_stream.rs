@@ -0,0 +1,356 @@
+use pyo3::exceptions::{PyStopAsyncIteration, PyStopIteration, PyTypeError, PyValueError};
+use pyo3::intern;
+use pyo3::prelude::*;
+use pyo3::sync::PyOnceLock;
+use pyo3::types::{PyBytes, PyDict, PyIterator, PyString, PyType};
+
+use crate::dispatch::Resume;
+use crate::json::py_to_value_with;
+
+/// Default size a chunk grows to before it is handed to the server.
+const DEFAULT_CHUNK_SIZE: usize = 64 * 1024;
+
+static DATE: PyOnceLock<Py<PyType>> = PyOnceLock::new();
+static TIME: PyOnceLock<Py<PyType>> = PyOnceLock::new();
+static DECIMAL: PyOnceLock<Py<PyType>> = PyOnceLock::new();
+static UUID: PyOnceLock<Py<PyType>> = PyOnceLock::new();
+static ENUM: PyOnceLock<Py<PyType>> = PyOnceLock::new();
+static DATACLASS_FIELDS: PyOnceLock<Py<PyAny>> = PyOnceLock::new();
+
+/// Standard library types written natively by the stream encoder.
+///
+/// `date`, `datetime` and `time` become ISO 8601 strings, `Decimal` and
+/// `UUID` their string form (so no precision is lost), an `Enum` its value
+/// and a dataclass instance an object of its fields.
+pub(crate) fn stdlib_fallback<'py>(obj: &Bound<'py, PyAny>) -> PyResult<Option<Bound<'py, PyAny>>> {
+    let py = obj.py();
+    if obj.is_instance(DATE.import(py, "datetime", "date")?)?
+        || obj.is_instance(TIME.import(py, "datetime", "time")?)?
+    {
+        return obj.call_method0(intern!(py, "isoformat")).map(Some);
+    }
+    if obj.is_instance(ENUM.import(py, "enum", "Enum")?)? {
+        return obj.getattr(intern!(py, "value")).map(Some);
+    }
+    if obj.is_instance(DECIMAL.import(py, "decimal", "Decimal")?)? {
+        if !obj.call_method0(intern!(py, "is_finite"))?.is_truthy()? {
+            return Err(PyValueError::new_err(
+                "Out of range Decimal values are not JSON compliant",
+            ));
+        }
+        return obj.str().map(|s| Some(s.into_any()));
+    }
+    if obj.is_instance(UUID.import(py, "uuid", "UUID")?)? {
+        return obj.str().map(|s| Some(s.into_any()));
+    }
+    if !obj.is_instance_of::<PyType>()
+        && obj
+            .get_type()
+            .hasattr(intern!(py, "__dataclass_fields__"))?
+    {
+        let fields = DATACLASS_FIELDS
+            .import(py, "dataclasses", "fields")?
+            .call1((obj,))?;
+        let dict = PyDict::new(py);
+        for field in fields.try_iter()? {
+            let name = field?.getattr(intern!(py, "name"))?;
+            dict.set_item(&name, obj.getattr(name.downcast::<PyString>()?)?)?;
+        }
+        return Ok(Some(dict.into_any()));
+    }
+    Ok(None)
+}
+
+/// Accumulates encoded rows into chunks.
+struct Encoder {
+    ndjson: bool,
+    chunk_size: usize,
+    buffer: Vec<u8>,
+    rows: usize,
+}
+
+impl Encoder {
+    fn push(&mut self, row: &Bound<'_, PyAny>) -> PyResult<()> {
+        let value = py_to_value_with(row, stdlib_fallback)?;
+        if !self.ndjson {
+            self.buffer.push(if self.rows == 0 { b'[' } else { b',' });
+        }
+        serde_json::to_writer(&mut self.buffer, &value)
+            .map_err(|e| PyValueError::new_err(e.to_string()))?;
+        if self.ndjson {
+            self.buffer.push(b'\n');
+        }
+        self.rows += 1;
+        Ok(())
+    }
+
+    fn full(&self) -> bool {
+        self.buffer.len() >= self.chunk_size
+    }
+
+    fn take(&mut self) -> Vec<u8> {
+        std::mem::take(&mut self.buffer)
+    }
+
+    /// Close the array and return what is left; empty for an empty NDJSON stream.
+    fn finish(&mut self) -> Vec<u8> {
+        if !self.ndjson {
+            self.buffer
+                .extend_from_slice(if self.rows == 0 { b"[]" } else { b"]" });
+        }
+        self.take()
+    }
+}
+
+enum Source {
+    Sync(Py<PyIterator>),
+    Async(Py<PyAny>),
+}
+
+/// Chunked JSON encoder over an iterable or async iterable of rows.
+///
+/// Iterate it (`for` or `async for`) to get `bytes` chunks of roughly
+/// `chunk_size` bytes, suitable as `StreamingResponse` content. With
+/// `format="array"` the chunks form one JSON array; with `format="ndjson"`
+/// every row is a line of its own. Synchronous sources can be consumed with
+/// `async for` as well, so servers never need a thread pool for them.
+#[pyclass(module = "haske")]
+pub struct JsonStream {
+    source: Source,
+    encoder: Encoder,
+    finished: bool,
+}
+
+impl JsonStream {
+    /// Encode synchronous rows until a chunk fills up or the source ends.
+    fn next_sync_chunk(&mut self, py: Python<'_>) -> PyResult<Option<Vec<u8>>> {
+        let Source::Sync(iterator) = &self.source else {
+            return Err(PyTypeError::new_err(
+                "JsonStream over an async iterable must be consumed with 'async for'",
+            ));
+        };
+        let iterator = iterator.bind(py).clone();
+        while !self.finished {
+            match iterator.clone().next() {
+                Some(row) => {
+                    self.encoder.push(&row?)?;
+                    if self.encoder.full() {
+                        return Ok(Some(self.encoder.take()));
+                    }
+                }
+                None => return Ok(self.finish()),
+            }
+        }
+        Ok(None)
+    }
+
+    fn finish(&mut self) -> Option<Vec<u8>> {
+        self.finished = true;
+        Some(self.encoder.finish()).filter(|chunk| !chunk.is_empty())
+    }
+}
+
+#[pymethods]
+impl JsonStream {
+    #[new]
+    #[pyo3(signature = (rows, format="array", chunk_size=DEFAULT_CHUNK_SIZE))]
+    fn new(rows: &Bound<'_, PyAny>, format: &str, chunk_size: usize) -> PyResult<Self> {
+        let ndjson = match format {
+            "array" => false,
+            "ndjson" => true,
+            _ => {
+                return Err(PyValueError::new_err(format!(
+                    "unknown JSON stream format '{format}' (expected 'array' or 'ndjson')"
+                )))
+            }
+        };
+        let py = rows.py();
+        let source = if rows.hasattr(intern!(py, "__aiter__"))? {
+            Source::Async(rows.call_method0(intern!(py, "__aiter__"))?.unbind())
+        } else {
+            Source::Sync(rows.try_iter()?.unbind())
+        };
+        Ok(Self {
+            source,
+            encoder: Encoder {
+                ndjson,
+                chunk_size: chunk_size.max(1),
+                buffer: Vec::new(),
+                rows: 0,
+            },
+            finished: false,
+        })
+    }
+
+    /// `application/x-ndjson` or `application/json`, matching the format.
+    #[getter]
+    fn media_type(&self) -> &'static str {
+        if self.encoder.ndjson {
+            "application/x-ndjson"
+        } else {
+            "application/json"
+        }
+    }
+
+    fn __iter__(slf: PyRef<'_, Self>) -> PyRef<'_, Self> {
+        slf
+    }
+
+    fn __next__<'py>(&mut self, py: Python<'py>) -> PyResult<Option<Bound<'py, PyBytes>>> {
+        Ok(self
+            .next_sync_chunk(py)?
+            .map(|chunk| PyBytes::new(py, &chunk)))
+    }
+
+    fn __aiter__(slf: PyRef<'_, Self>) -> PyRef<'_, Self> {
+        slf
+    }
+
+    fn __anext__(slf: Bound<'_, Self>) -> PyResult<JsonStreamNext> {
+        let py = slf.py();
+        let sync = matches!(slf.borrow().source, Source::Sync(_));
+        let ready = if sync {
+            Some(slf.borrow_mut().next_sync_chunk(py)?)
+        } else {
+            None
+        };
+        Ok(JsonStreamNext {
+            stream: slf.unbind(),
+            awaiting: None,
+            ready,
+        })
+    }
+}
+
+/// Awaitable returned by `JsonStream.__anext__`.
+///
+/// Drives the source's `__anext__()` with `yield from` semantics until a chunk
+/// is full, then resolves to it.
+#[pyclass(module = "haske")]
+pub struct JsonStreamNext {
+    stream: Py<JsonStream>,
+    awaiting: Option<Py<PyAny>>,
+    /// Result computed up front for synchronous sources.
+    ready: Option<Option<Vec<u8>>>,
+}
+
+impl JsonStreamNext {
+    fn resume<'py>(&mut self, py: Python<'py>, mut input: Resume<'py>) -> PyResult<Py<PyAny>> {
+        if let Some(ready) = self.ready.take() {
+            return Err(chunk_result(py, ready));
+        }
+        let stream = self.stream.bind(py);
+        loop {
+            let awaiting = match self.awaiting.as_ref() {
+                Some(awaiting) => awaiting.bind(py).clone(),
+                None => {
+                    if let Resume::Throw(exc) = input {
+                        return Err(PyErr::from_value(exc));
+                    }
+                    let source = match &stream.borrow().source {
+                        Source::Async(source) if !stream.borrow().finished => source.clone_ref(py),
+                        _ => return Err(PyStopAsyncIteration::new_err(())),
+                    };
+                    let next = source.bind(py).call_method0(intern!(py, "__anext__"))?;
+                    let awaiting = next.call_method0(intern!(py, "__await__"))?;
+                    self.awaiting = Some(awaiting.clone().unbind());
+                    awaiting
+                }
+            };
+            let result = match input {
+                Resume::Send(value) if value.is_none() => {
+                    awaiting.call_method0(intern!(py, "__next__"))
+                }
+                Resume::Send(value) => awaiting.call_method1(intern!(py, "send"), (value,)),
+                Resume::Throw(exc) => awaiting.call_method1(intern!(py, "throw"), (exc,)),
+            };
+            match result {
+                Ok(yielded) => return Ok(yielded.unbind()),
+                Err(err) if err.is_instance_of::<PyStopIteration>(py) => {
+                    self.awaiting = None;
+                    let row = err.value(py).getattr(intern!(py, "value"))?;
+                    let mut stream = stream.borrow_mut();
+                    stream.encoder.push(&row)?;
+                    if stream.encoder.full() {
+                        return Err(chunk_result(py, Some(stream.encoder.take())));
+                    }
+                    input = Resume::Send(py.None().into_bound(py));
+                }
+                Err(err) if err.is_instance_of::<PyStopAsyncIteration>(py) => {
+                    self.awaiting = None;
+                    let tail = stream.borrow_mut().finish();
+                    return Err(chunk_result(py, tail));
+                }
+                Err(err) => {
+                    self.awaiting = None;
+                    return Err(err);
+                }
+            }
+        }
+    }
+}
+
+/// Resolve the awaitable with `chunk`, or end the `async for` loop.
+fn chunk_result(py: Python<'_>, chunk: Option<Vec<u8>>) -> PyErr {
+    match chunk {
+        Some(chunk) => PyStopIteration::new_err((PyBytes::new(py, &chunk).unbind(),)),
+        None => PyStopAsyncIteration::new_err(()),
+    }
+}
+
+#[pymethods]
+impl JsonStreamNext {
+    fn __await__(slf: PyRef<'_, Self>) -> PyRef<'_, Self> {
+        slf
+    }
+
+    fn __iter__(slf: PyRef<'_, Self>) -> PyRef<'_, Self> {
+        slf
+    }
+
+    fn __next__(&mut self, py: Python<'_>) -> PyResult<Py<PyAny>> {
+        self.resume(py, Resume::Send(py.None().into_bound(py)))
+    }
+
+    fn send<'py>(&mut self, py: Python<'py>, value: Bound<'py, PyAny>) -> PyResult<Py<PyAny>> {
+        self.resume(py, Resume::Send(value))
+    }
+
+    #[pyo3(signature = (typ, val=None, _tb=None))]
+    fn throw<'py>(
+        &mut self,
+        py: Python<'py>,
+        typ: Bound<'py, PyAny>,
+        val: Option<Bound<'py, PyAny>>,
+        _tb: Option<Bound<'py, PyAny>>,
+    ) -> PyResult<Py<PyAny>> {
+        let exc = match val {
+            Some(val) if !val.is_none() => val,
+            _ if typ.is_instance_of::<PyType>() => typ.call0()?,
+            _ => typ,
+        };
+        self.resume(py, Resume::Throw(exc))
+    }
+
+    fn close(&mut self, py: Python<'_>) -> PyResult<()> {
+        self.ready = None;
+        match self.awaiting.take() {
+            Some(awaiting) if awaiting.bind(py).hasattr(intern!(py, "close"))? => {
+                awaiting.bind(py).call_method0(intern!(py, "close"))?;
+                Ok(())
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Shorthand for `JsonStream(rows, format, chunk_size)`.
+#[pyfunction]
+#[pyo3(signature = (rows, format="array", chunk_size=DEFAULT_CHUNK_SIZE))]
+pub fn stream_json(
+    rows: &Bound<'_, PyAny>,
+    format: &str,
+    chunk_size: usize,
+) -> PyResult<JsonStream> {
+    JsonStream::new(rows, format, chunk_size)
+}
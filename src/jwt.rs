@@ -0,0 +1,900 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use hmac::digest::KeyInit;
+use hmac::{Hmac, Mac};
+use pyo3::create_exception;
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+use pyo3::types::{PyBytes, PyDict, PyList, PyString};
+use rsa::pkcs1::{DecodeRsaPrivateKey, DecodeRsaPublicKey};
+use rsa::pkcs8::{AssociatedOid, DecodePrivateKey, DecodePublicKey};
+use rsa::signature::{RandomizedSigner, SignatureEncoding, Signer, Verifier};
+use rsa::traits::PublicKeyParts;
+use rsa::{BigUint, RsaPrivateKey, RsaPublicKey};
+use serde_json::{Map, Value};
+use sha2::digest::FixedOutputReset;
+use sha2::{Digest, Sha256, Sha384, Sha512};
+
+use crate::crypto::hmac_sign;
+use crate::json::{py_to_value, value_to_py};
+
+create_exception!(
+    haske,
+    JwtError,
+    PyValueError,
+    "Raised when a JWT or key is malformed, or a token fails verification."
+);
+
+/// Signature algorithms accepted in the JWS `alg` header.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Algorithm {
+    Hs256,
+    Hs384,
+    Hs512,
+    Rs256,
+    Rs384,
+    Rs512,
+    Ps256,
+    Ps384,
+    Ps512,
+    Es256,
+    Es384,
+    EdDsa,
+}
+
+impl Algorithm {
+    fn from_name(name: &str) -> Option<Self> {
+        Some(match name {
+            "HS256" => Self::Hs256,
+            "HS384" => Self::Hs384,
+            "HS512" => Self::Hs512,
+            "RS256" => Self::Rs256,
+            "RS384" => Self::Rs384,
+            "RS512" => Self::Rs512,
+            "PS256" => Self::Ps256,
+            "PS384" => Self::Ps384,
+            "PS512" => Self::Ps512,
+            "ES256" => Self::Es256,
+            "ES384" => Self::Es384,
+            "EdDSA" => Self::EdDsa,
+            _ => return None,
+        })
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            Self::Hs256 => "HS256",
+            Self::Hs384 => "HS384",
+            Self::Hs512 => "HS512",
+            Self::Rs256 => "RS256",
+            Self::Rs384 => "RS384",
+            Self::Rs512 => "RS512",
+            Self::Ps256 => "PS256",
+            Self::Ps384 => "PS384",
+            Self::Ps512 => "PS512",
+            Self::Es256 => "ES256",
+            Self::Es384 => "ES384",
+            Self::EdDsa => "EdDSA",
+        }
+    }
+}
+
+/// Key material; asymmetric keys always carry the public half, and the
+/// private half when loaded from a private key.
+enum Material {
+    Hmac(Vec<u8>),
+    Rsa {
+        public: RsaPublicKey,
+        private: Option<RsaPrivateKey>,
+    },
+    P256 {
+        public: p256::ecdsa::VerifyingKey,
+        private: Option<p256::ecdsa::SigningKey>,
+    },
+    P384 {
+        public: p384::ecdsa::VerifyingKey,
+        private: Option<p384::ecdsa::SigningKey>,
+    },
+    Ed25519 {
+        public: ed25519_dalek::VerifyingKey,
+        private: Option<ed25519_dalek::SigningKey>,
+    },
+}
+
+impl Material {
+    fn default_algorithm(&self) -> Algorithm {
+        match self {
+            Self::Hmac(_) => Algorithm::Hs256,
+            Self::Rsa { .. } => Algorithm::Rs256,
+            Self::P256 { .. } => Algorithm::Es256,
+            Self::P384 { .. } => Algorithm::Es384,
+            Self::Ed25519 { .. } => Algorithm::EdDsa,
+        }
+    }
+
+    fn supports(&self, algorithm: Algorithm) -> bool {
+        use Algorithm::*;
+        match self {
+            Self::Hmac(_) => matches!(algorithm, Hs256 | Hs384 | Hs512),
+            Self::Rsa { .. } => matches!(algorithm, Rs256 | Rs384 | Rs512 | Ps256 | Ps384 | Ps512),
+            Self::P256 { .. } => algorithm == Es256,
+            Self::P384 { .. } => algorithm == Es384,
+            Self::Ed25519 { .. } => algorithm == EdDsa,
+        }
+    }
+
+    fn can_sign(&self) -> bool {
+        match self {
+            Self::Hmac(_) => true,
+            Self::Rsa { private, .. } => private.is_some(),
+            Self::P256 { private, .. } => private.is_some(),
+            Self::P384 { private, .. } => private.is_some(),
+            Self::Ed25519 { private, .. } => private.is_some(),
+        }
+    }
+
+    /// Sign `message`; `None` when only the public key is known.
+    fn sign(&self, algorithm: Algorithm, message: &[u8]) -> Option<Vec<u8>> {
+        use Algorithm::*;
+        Some(match (self, algorithm) {
+            (Self::Hmac(secret), Hs256) => hmac_sign::<Hmac<Sha256>>(secret, message),
+            (Self::Hmac(secret), Hs384) => hmac_sign::<Hmac<Sha384>>(secret, message),
+            (Self::Hmac(secret), Hs512) => hmac_sign::<Hmac<Sha512>>(secret, message),
+            (Self::Rsa { private, .. }, _) => {
+                let private = private.as_ref()?;
+                match algorithm {
+                    Rs256 => rsa_sign::<Sha256>(private, message, false),
+                    Rs384 => rsa_sign::<Sha384>(private, message, false),
+                    Rs512 => rsa_sign::<Sha512>(private, message, false),
+                    Ps256 => rsa_sign::<Sha256>(private, message, true),
+                    Ps384 => rsa_sign::<Sha384>(private, message, true),
+                    _ => rsa_sign::<Sha512>(private, message, true),
+                }
+            }
+            (Self::P256 { private, .. }, _) => {
+                let signature: p256::ecdsa::Signature = private.as_ref()?.sign(message);
+                signature.to_bytes().to_vec()
+            }
+            (Self::P384 { private, .. }, _) => {
+                let signature: p384::ecdsa::Signature = private.as_ref()?.sign(message);
+                signature.to_bytes().to_vec()
+            }
+            (Self::Ed25519 { private, .. }, _) => {
+                private.as_ref()?.sign(message).to_bytes().to_vec()
+            }
+            _ => return None,
+        })
+    }
+
+    fn verify(&self, algorithm: Algorithm, message: &[u8], signature: &[u8]) -> bool {
+        use Algorithm::*;
+        match (self, algorithm) {
+            (Self::Hmac(secret), Hs256) => hmac_verify::<Hmac<Sha256>>(secret, message, signature),
+            (Self::Hmac(secret), Hs384) => hmac_verify::<Hmac<Sha384>>(secret, message, signature),
+            (Self::Hmac(secret), Hs512) => hmac_verify::<Hmac<Sha512>>(secret, message, signature),
+            (Self::Rsa { public, .. }, Rs256) => {
+                rsa_verify::<Sha256>(public, message, signature, false)
+            }
+            (Self::Rsa { public, .. }, Rs384) => {
+                rsa_verify::<Sha384>(public, message, signature, false)
+            }
+            (Self::Rsa { public, .. }, Rs512) => {
+                rsa_verify::<Sha512>(public, message, signature, false)
+            }
+            (Self::Rsa { public, .. }, Ps256) => {
+                rsa_verify::<Sha256>(public, message, signature, true)
+            }
+            (Self::Rsa { public, .. }, Ps384) => {
+                rsa_verify::<Sha384>(public, message, signature, true)
+            }
+            (Self::Rsa { public, .. }, Ps512) => {
+                rsa_verify::<Sha512>(public, message, signature, true)
+            }
+            (Self::P256 { public, .. }, Es256) => p256::ecdsa::Signature::from_slice(signature)
+                .is_ok_and(|signature| public.verify(message, &signature).is_ok()),
+            (Self::P384 { public, .. }, Es384) => p384::ecdsa::Signature::from_slice(signature)
+                .is_ok_and(|signature| public.verify(message, &signature).is_ok()),
+            (Self::Ed25519 { public, .. }, EdDsa) => {
+                ed25519_dalek::Signature::from_slice(signature)
+                    .is_ok_and(|signature| public.verify_strict(message, &signature).is_ok())
+            }
+            _ => false,
+        }
+    }
+
+    fn from_pem(pem: &str) -> Result<Self, String> {
+        if let Ok(private) =
+            RsaPrivateKey::from_pkcs8_pem(pem).or_else(|_| RsaPrivateKey::from_pkcs1_pem(pem))
+        {
+            return Ok(Self::Rsa {
+                public: private.to_public_key(),
+                private: Some(private),
+            });
+        }
+        if let Ok(public) =
+            RsaPublicKey::from_public_key_pem(pem).or_else(|_| RsaPublicKey::from_pkcs1_pem(pem))
+        {
+            return Ok(Self::Rsa {
+                public,
+                private: None,
+            });
+        }
+        if let Ok(secret) =
+            p256::SecretKey::from_pkcs8_pem(pem).or_else(|_| p256::SecretKey::from_sec1_pem(pem))
+        {
+            let private = p256::ecdsa::SigningKey::from(secret);
+            return Ok(Self::P256 {
+                public: *private.verifying_key(),
+                private: Some(private),
+            });
+        }
+        if let Ok(public) = p256::PublicKey::from_public_key_pem(pem) {
+            return Ok(Self::P256 {
+                public: public.into(),
+                private: None,
+            });
+        }
+        if let Ok(secret) =
+            p384::SecretKey::from_pkcs8_pem(pem).or_else(|_| p384::SecretKey::from_sec1_pem(pem))
+        {
+            let private = p384::ecdsa::SigningKey::from(secret);
+            return Ok(Self::P384 {
+                public: *private.verifying_key(),
+                private: Some(private),
+            });
+        }
+        if let Ok(public) = p384::PublicKey::from_public_key_pem(pem) {
+            return Ok(Self::P384 {
+                public: public.into(),
+                private: None,
+            });
+        }
+        if let Ok(private) = ed25519_dalek::SigningKey::from_pkcs8_pem(pem) {
+            return Ok(Self::Ed25519 {
+                public: private.verifying_key(),
+                private: Some(private),
+            });
+        }
+        if let Ok(public) = ed25519_dalek::VerifyingKey::from_public_key_pem(pem) {
+            return Ok(Self::Ed25519 {
+                public,
+                private: None,
+            });
+        }
+        Err(
+            "unsupported or malformed PEM key (expected an RSA, P-256, P-384 or Ed25519 key)"
+                .into(),
+        )
+    }
+
+    fn from_jwk(jwk: &Map<String, Value>) -> Result<Self, String> {
+        let field = |name: &str| -> Result<Option<Vec<u8>>, String> {
+            match jwk.get(name) {
+                None => Ok(None),
+                Some(Value::String(text)) => URL_SAFE_NO_PAD
+                    .decode(text.trim_end_matches('='))
+                    .map(Some)
+                    .map_err(|_| format!("JWK member '{name}' is not base64url")),
+                Some(_) => Err(format!("JWK member '{name}' must be a string")),
+            }
+        };
+        let required = |name: &str| field(name)?.ok_or_else(|| format!("JWK is missing '{name}'"));
+        let kty = jwk.get("kty").and_then(Value::as_str).unwrap_or_default();
+        let crv = jwk.get("crv").and_then(Value::as_str).unwrap_or_default();
+        let invalid = |e: &dyn std::fmt::Display| format!("invalid {kty} JWK: {e}");
+
+        match (kty, crv) {
+            ("oct", _) => Ok(Self::Hmac(required("k")?)),
+            ("RSA", _) => {
+                let n = BigUint::from_bytes_be(&required("n")?);
+                let e = BigUint::from_bytes_be(&required("e")?);
+                let private = match field("d")? {
+                    Some(d) => {
+                        let primes = match (field("p")?, field("q")?) {
+                            (Some(p), Some(q)) => {
+                                vec![BigUint::from_bytes_be(&p), BigUint::from_bytes_be(&q)]
+                            }
+                            _ => Vec::new(),
+                        };
+                        let d = BigUint::from_bytes_be(&d);
+                        Some(
+                            RsaPrivateKey::from_components(n.clone(), e.clone(), d, primes)
+                                .map_err(|e| invalid(&e))?,
+                        )
+                    }
+                    None => None,
+                };
+                Ok(Self::Rsa {
+                    public: RsaPublicKey::new(n, e).map_err(|e| invalid(&e))?,
+                    private,
+                })
+            }
+            ("EC", "P-256") => {
+                let point = uncompressed_point(&required("x")?, &required("y")?);
+                let public =
+                    p256::ecdsa::VerifyingKey::from_sec1_bytes(&point).map_err(|e| invalid(&e))?;
+                let private = field("d")?
+                    .map(|d| p256::ecdsa::SigningKey::from_slice(&d))
+                    .transpose()
+                    .map_err(|e| invalid(&e))?;
+                Ok(Self::P256 { public, private })
+            }
+            ("EC", "P-384") => {
+                let point = uncompressed_point(&required("x")?, &required("y")?);
+                let public =
+                    p384::ecdsa::VerifyingKey::from_sec1_bytes(&point).map_err(|e| invalid(&e))?;
+                let private = field("d")?
+                    .map(|d| p384::ecdsa::SigningKey::from_slice(&d))
+                    .transpose()
+                    .map_err(|e| invalid(&e))?;
+                Ok(Self::P384 { public, private })
+            }
+            ("OKP", "Ed25519") => {
+                let x: [u8; 32] = required("x")?
+                    .try_into()
+                    .map_err(|_| invalid(&"'x' must be 32 bytes"))?;
+                let public =
+                    ed25519_dalek::VerifyingKey::from_bytes(&x).map_err(|e| invalid(&e))?;
+                let private = match field("d")? {
+                    Some(d) => {
+                        let d: [u8; 32] =
+                            d.try_into().map_err(|_| invalid(&"'d' must be 32 bytes"))?;
+                        Some(ed25519_dalek::SigningKey::from_bytes(&d))
+                    }
+                    None => None,
+                };
+                Ok(Self::Ed25519 { public, private })
+            }
+            _ if crv.is_empty() => Err(format!("unsupported JWK key type '{kty}'")),
+            _ => Err(format!(
+                "unsupported JWK curve '{crv}' for key type '{kty}'"
+            )),
+        }
+    }
+
+    /// Public JWK members for this key, without `kid`/`alg`/`use`.
+    fn public_jwk(&self) -> Option<Vec<(&'static str, String)>> {
+        let b64 = |bytes: &[u8]| URL_SAFE_NO_PAD.encode(bytes);
+        Some(match self {
+            Self::Hmac(_) => return None,
+            Self::Rsa { public, .. } => vec![
+                ("kty", "RSA".into()),
+                ("n", b64(&public.n().to_bytes_be())),
+                ("e", b64(&public.e().to_bytes_be())),
+            ],
+            Self::P256 { public, .. } => {
+                let point = public.to_encoded_point(false);
+                vec![
+                    ("kty", "EC".into()),
+                    ("crv", "P-256".into()),
+                    ("x", b64(point.x()?)),
+                    ("y", b64(point.y()?)),
+                ]
+            }
+            Self::P384 { public, .. } => {
+                let point = public.to_encoded_point(false);
+                vec![
+                    ("kty", "EC".into()),
+                    ("crv", "P-384".into()),
+                    ("x", b64(point.x()?)),
+                    ("y", b64(point.y()?)),
+                ]
+            }
+            Self::Ed25519 { public, .. } => vec![
+                ("kty", "OKP".into()),
+                ("crv", "Ed25519".into()),
+                ("x", b64(public.as_bytes())),
+            ],
+        })
+    }
+}
+
+fn uncompressed_point(x: &[u8], y: &[u8]) -> Vec<u8> {
+    let mut point = Vec::with_capacity(1 + x.len() + y.len());
+    point.push(0x04);
+    point.extend_from_slice(x);
+    point.extend_from_slice(y);
+    point
+}
+
+fn hmac_verify<M: Mac + KeyInit>(secret: &[u8], message: &[u8], signature: &[u8]) -> bool {
+    let mut mac = <M as KeyInit>::new_from_slice(secret).expect("HMAC accepts keys of any length");
+    mac.update(message);
+    mac.verify_slice(signature).is_ok()
+}
+
+fn rsa_sign<D>(private: &RsaPrivateKey, message: &[u8], pss: bool) -> Vec<u8>
+where
+    D: Digest + AssociatedOid + FixedOutputReset,
+{
+    if pss {
+        rsa::pss::BlindedSigningKey::<D>::new(private.clone())
+            .sign_with_rng(&mut rand::thread_rng(), message)
+            .to_vec()
+    } else {
+        rsa::pkcs1v15::SigningKey::<D>::new(private.clone())
+            .sign(message)
+            .to_vec()
+    }
+}
+
+fn rsa_verify<D>(public: &RsaPublicKey, message: &[u8], signature: &[u8], pss: bool) -> bool
+where
+    D: Digest + AssociatedOid + FixedOutputReset,
+{
+    if pss {
+        rsa::pss::Signature::try_from(signature).is_ok_and(|signature| {
+            rsa::pss::VerifyingKey::<D>::new(public.clone())
+                .verify(message, &signature)
+                .is_ok()
+        })
+    } else {
+        rsa::pkcs1v15::Signature::try_from(signature).is_ok_and(|signature| {
+            rsa::pkcs1v15::VerifyingKey::<D>::new(public.clone())
+                .verify(message, &signature)
+                .is_ok()
+        })
+    }
+}
+
+/// A signing or verification key for `jwt_encode`/`jwt_decode`.
+///
+/// Build one with `from_secret` (HMAC), `from_pem`/`from_pem_file` (RSA,
+/// P-256, P-384 or Ed25519; a private key can also verify) or
+/// `from_jwk`/`from_jwk_file`. The optional `kid` is written into the header
+/// of tokens it signs and used to pick the key when decoding.
+#[pyclass(frozen, module = "haske")]
+pub struct JwtKey {
+    kid: Option<String>,
+    algorithm: Algorithm,
+    material: Material,
+}
+
+impl JwtKey {
+    fn build(material: Material, algorithm: Option<&str>, kid: Option<String>) -> PyResult<Self> {
+        let algorithm = match algorithm {
+            Some(name) => Algorithm::from_name(name)
+                .ok_or_else(|| JwtError::new_err(format!("unsupported algorithm '{name}'")))?,
+            None => material.default_algorithm(),
+        };
+        if !material.supports(algorithm) {
+            return Err(JwtError::new_err(format!(
+                "algorithm {} does not match the key type",
+                algorithm.name()
+            )));
+        }
+        Ok(Self {
+            kid,
+            algorithm,
+            material,
+        })
+    }
+}
+
+#[pymethods]
+impl JwtKey {
+    /// HMAC key from a shared secret (`str` or `bytes`).
+    #[staticmethod]
+    #[pyo3(signature = (secret, algorithm="HS256", kid=None))]
+    fn from_secret(
+        secret: &Bound<'_, PyAny>,
+        algorithm: &str,
+        kid: Option<String>,
+    ) -> PyResult<Self> {
+        Self::build(Material::Hmac(text_or_bytes(secret)?), Some(algorithm), kid)
+    }
+
+    /// Key from a PEM document: PKCS#8, PKCS#1 or SEC1 private keys, or
+    /// SubjectPublicKeyInfo/PKCS#1 public keys. The algorithm defaults to
+    /// RS256, ES256, ES384 or EdDSA depending on the key.
+    #[staticmethod]
+    #[pyo3(signature = (pem, algorithm=None, kid=None))]
+    fn from_pem(
+        pem: &Bound<'_, PyAny>,
+        algorithm: Option<&str>,
+        kid: Option<String>,
+    ) -> PyResult<Self> {
+        let pem = String::from_utf8(text_or_bytes(pem)?)
+            .map_err(|_| JwtError::new_err("PEM data is not valid UTF-8"))?;
+        let material = Material::from_pem(pem.trim()).map_err(JwtError::new_err)?;
+        Self::build(material, algorithm, kid)
+    }
+
+    #[staticmethod]
+    #[pyo3(signature = (path, algorithm=None, kid=None))]
+    fn from_pem_file(
+        py: Python<'_>,
+        path: &str,
+        algorithm: Option<&str>,
+        kid: Option<String>,
+    ) -> PyResult<Self> {
+        let pem = std::fs::read_to_string(path)?;
+        Self::from_pem(PyString::new(py, &pem).as_any(), algorithm, kid)
+    }
+
+    /// Key from a JWK given as a dict or JSON text. `kid` and `alg` are taken
+    /// from the JWK unless passed explicitly.
+    #[staticmethod]
+    #[pyo3(signature = (jwk, algorithm=None, kid=None))]
+    fn from_jwk(
+        jwk: &Bound<'_, PyAny>,
+        algorithm: Option<&str>,
+        kid: Option<String>,
+    ) -> PyResult<Self> {
+        let jwk = json_object(jwk, "JWK")?;
+        key_from_jwk(&jwk, algorithm, kid)
+    }
+
+    #[staticmethod]
+    #[pyo3(signature = (path, algorithm=None, kid=None))]
+    fn from_jwk_file(
+        py: Python<'_>,
+        path: &str,
+        algorithm: Option<&str>,
+        kid: Option<String>,
+    ) -> PyResult<Self> {
+        let text = std::fs::read_to_string(path)?;
+        Self::from_jwk(PyString::new(py, &text).as_any(), algorithm, kid)
+    }
+
+    #[getter]
+    pub(crate) fn kid(&self) -> Option<&str> {
+        self.kid.as_deref()
+    }
+
+    #[getter]
+    fn algorithm(&self) -> &'static str {
+        self.algorithm.name()
+    }
+
+    /// Whether the key holds private (or shared) material and can sign.
+    #[getter]
+    fn can_sign(&self) -> bool {
+        self.material.can_sign()
+    }
+
+    /// The public half as a JWK dict, for publishing in a JWKS document.
+    fn public_jwk<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyDict>> {
+        let members = self
+            .material
+            .public_jwk()
+            .ok_or_else(|| JwtError::new_err("HMAC keys have no public form"))?;
+        let jwk = PyDict::new(py);
+        for (name, value) in members {
+            jwk.set_item(name, value)?;
+        }
+        jwk.set_item("use", "sig")?;
+        jwk.set_item("alg", self.algorithm.name())?;
+        if let Some(kid) = &self.kid {
+            jwk.set_item("kid", kid)?;
+        }
+        Ok(jwk)
+    }
+
+    fn __repr__(&self) -> String {
+        match &self.kid {
+            Some(kid) => format!("JwtKey(algorithm='{}', kid='{kid}')", self.algorithm.name()),
+            None => format!("JwtKey(algorithm='{}')", self.algorithm.name()),
+        }
+    }
+}
+
+fn key_from_jwk(
+    jwk: &Map<String, Value>,
+    algorithm: Option<&str>,
+    kid: Option<String>,
+) -> PyResult<JwtKey> {
+    let material = Material::from_jwk(jwk).map_err(JwtError::new_err)?;
+    let algorithm = algorithm.or_else(|| jwk.get("alg").and_then(Value::as_str));
+    let kid = kid.or_else(|| jwk.get("kid").and_then(Value::as_str).map(str::to_owned));
+    JwtKey::build(material, algorithm, kid)
+}
+
+/// Load the signature keys of a JWKS document (dict or JSON text).
+///
+/// Keys marked `"use": "enc"` and key types this module cannot verify with
+/// are skipped, so documents listing other keys still load.
+#[pyfunction]
+pub fn load_jwks(jwks: &Bound<'_, PyAny>) -> PyResult<Vec<JwtKey>> {
+    let jwks = json_object(jwks, "JWKS")?;
+    let Some(Value::Array(entries)) = jwks.get("keys") else {
+        return Err(JwtError::new_err("JWKS document has no 'keys' array"));
+    };
+    let mut keys = Vec::with_capacity(entries.len());
+    for entry in entries {
+        let Value::Object(jwk) = entry else {
+            return Err(JwtError::new_err("JWKS 'keys' entries must be objects"));
+        };
+        if jwk.get("use").and_then(Value::as_str) == Some("enc") {
+            continue;
+        }
+        match Material::from_jwk(jwk) {
+            Ok(_) => keys.push(key_from_jwk(jwk, None, None)?),
+            Err(e) if e.starts_with("unsupported") => continue,
+            Err(e) => return Err(JwtError::new_err(e)),
+        }
+    }
+    Ok(keys)
+}
+
+#[pyfunction]
+pub fn load_jwks_file(py: Python<'_>, path: &str) -> PyResult<Vec<JwtKey>> {
+    let text = std::fs::read_to_string(path)?;
+    load_jwks(PyString::new(py, &text).as_any())
+}
+
+/// Encode `claims` as a compact JWS signed with `key`.
+///
+/// `alg`, `typ` and the key's `kid` are set in the header; `headers` adds or
+/// overrides other members. Time claims are written as given.
+#[pyfunction]
+#[pyo3(signature = (claims, key, headers=None))]
+pub fn jwt_encode(
+    claims: &Bound<'_, PyDict>,
+    key: &JwtKey,
+    headers: Option<&Bound<'_, PyDict>>,
+) -> PyResult<String> {
+    let mut header = Map::new();
+    header.insert("alg".into(), key.algorithm.name().into());
+    header.insert("typ".into(), "JWT".into());
+    if let Some(kid) = &key.kid {
+        header.insert("kid".into(), kid.as_str().into());
+    }
+    if let Some(headers) = headers {
+        if let Value::Object(extra) = py_to_value(headers)? {
+            for (name, value) in extra {
+                if name != "alg" {
+                    header.insert(name, value);
+                }
+            }
+        }
+    }
+    let mut token = URL_SAFE_NO_PAD.encode(Value::Object(header).to_string());
+    token.push('.');
+    token.push_str(&URL_SAFE_NO_PAD.encode(py_to_value(claims)?.to_string()));
+    let signature = key
+        .material
+        .sign(key.algorithm, token.as_bytes())
+        .ok_or_else(|| JwtError::new_err("key cannot sign: it has no private part"))?;
+    token.push('.');
+    token.push_str(&URL_SAFE_NO_PAD.encode(signature));
+    Ok(token)
+}
+
+/// Verify `token` against `keys` and validate its registered claims,
+/// returning the claims dict.
+///
+/// `keys` is a `JwtKey` or a list of them. When the header names a `kid`
+/// the key with that id is used (keys without an id are tried if none
+/// matches); the header `alg` must equal the key's algorithm and, if given,
+/// be listed in `algorithms`. `exp`, `nbf` and `iat` are checked against the
+/// current time with `leeway` seconds of tolerance. When `audience` (str or
+/// list) is given the `aud` claim must contain one of them, and a token
+/// carrying `aud` is rejected when no audience is expected; `issuer` (str or
+/// list) must match `iss`. Claims listed in `require` must be present.
+#[pyfunction]
+#[pyo3(signature = (token, keys, *, algorithms=None, audience=None, issuer=None, leeway=0.0, require=Vec::new()))]
+pub fn jwt_decode<'py>(
+    token: &str,
+    keys: &Bound<'py, PyAny>,
+    algorithms: Option<Vec<String>>,
+    audience: Option<&Bound<'py, PyAny>>,
+    issuer: Option<&Bound<'py, PyAny>>,
+    leeway: f64,
+    require: Vec<String>,
+) -> PyResult<Bound<'py, PyAny>> {
+    let (_, claims) = verify_token(token, keys, algorithms.as_deref())?;
+    let audience = audience.map(string_or_list).transpose()?;
+    let issuer = issuer.map(string_or_list).transpose()?;
+    validate_claims(
+        &claims,
+        &Expectations {
+            audience: audience.as_deref(),
+            issuer: issuer.as_deref(),
+            leeway,
+            require: &require,
+        },
+    )?;
+    value_to_py(keys.py(), &Value::Object(claims))
+}
+
+/// Decode the header of `token` without verifying anything, e.g. to read
+/// its `kid` before fetching keys.
+#[pyfunction]
+pub fn jwt_decode_header<'py>(py: Python<'py>, token: &str) -> PyResult<Bound<'py, PyAny>> {
+    let (header, _, _) = split_token(token)?;
+    value_to_py(py, &Value::Object(header))
+}
+
+/// Claim checks applied by `jwt_decode`.
+pub(crate) struct Expectations<'a> {
+    pub(crate) audience: Option<&'a [String]>,
+    pub(crate) issuer: Option<&'a [String]>,
+    pub(crate) leeway: f64,
+    pub(crate) require: &'a [String],
+}
+
+pub(crate) type Parts<'a> = (Map<String, Value>, &'a str, Vec<u8>);
+
+/// Split a compact JWS into its decoded header, signing input and signature.
+pub(crate) fn split_token(token: &str) -> PyResult<Parts<'_>> {
+    let malformed = || JwtError::new_err("malformed token");
+    let (signing_input, signature) = token.rsplit_once('.').ok_or_else(malformed)?;
+    let (header, _) = signing_input.split_once('.').ok_or_else(malformed)?;
+    let header: Value = URL_SAFE_NO_PAD
+        .decode(header)
+        .ok()
+        .and_then(|bytes| serde_json::from_slice(&bytes).ok())
+        .ok_or_else(malformed)?;
+    let Value::Object(header) = header else {
+        return Err(malformed());
+    };
+    let signature = URL_SAFE_NO_PAD.decode(signature).map_err(|_| malformed())?;
+    Ok((header, signing_input, signature))
+}
+
+/// Check the signature of `token` with the matching key and return its
+/// header and claims.
+pub(crate) fn verify_token(
+    token: &str,
+    keys: &Bound<'_, PyAny>,
+    algorithms: Option<&[String]>,
+) -> PyResult<(Map<String, Value>, Map<String, Value>)> {
+    let (header, signing_input, signature) = split_token(token)?;
+    let name = header
+        .get("alg")
+        .and_then(Value::as_str)
+        .ok_or_else(|| JwtError::new_err("token header has no 'alg'"))?;
+    let algorithm = Algorithm::from_name(name)
+        .ok_or_else(|| JwtError::new_err(format!("unsupported algorithm '{name}'")))?;
+    if algorithms.is_some_and(|allowed| !allowed.iter().any(|a| a == name)) {
+        return Err(JwtError::new_err(format!(
+            "algorithm {name} is not allowed"
+        )));
+    }
+
+    let keys: Vec<PyRef<'_, JwtKey>> = match keys.extract::<PyRef<'_, JwtKey>>() {
+        Ok(key) => vec![key],
+        Err(_) => keys.extract()?,
+    };
+    let kid = header.get("kid").and_then(Value::as_str);
+    let mut candidates: Vec<&JwtKey> = keys
+        .iter()
+        .map(|key| &**key)
+        .filter(|key| kid.is_some() && key.kid.as_deref() == kid)
+        .collect();
+    if candidates.is_empty() {
+        candidates = keys
+            .iter()
+            .map(|key| &**key)
+            .filter(|key| key.kid.is_none() || kid.is_none())
+            .collect();
+    }
+    candidates.retain(|key| key.algorithm == algorithm);
+    if candidates.is_empty() {
+        return Err(JwtError::new_err(match kid {
+            Some(kid) => format!("no {name} key found for kid '{kid}'"),
+            None => format!("no {name} key found"),
+        }));
+    }
+    if !candidates.iter().any(|key| {
+        key.material
+            .verify(algorithm, signing_input.as_bytes(), &signature)
+    }) {
+        return Err(JwtError::new_err("signature verification failed"));
+    }
+
+    let (_, payload) = signing_input
+        .split_once('.')
+        .expect("checked by split_token");
+    let claims: Value = URL_SAFE_NO_PAD
+        .decode(payload)
+        .ok()
+        .and_then(|bytes| serde_json::from_slice(&bytes).ok())
+        .ok_or_else(|| JwtError::new_err("token payload is not a JSON object"))?;
+    match claims {
+        Value::Object(claims) => Ok((header, claims)),
+        _ => Err(JwtError::new_err("token payload is not a JSON object")),
+    }
+}
+
+pub(crate) fn validate_claims(
+    claims: &Map<String, Value>,
+    expect: &Expectations<'_>,
+) -> PyResult<()> {
+    for name in expect.require {
+        if !claims.contains_key(name) {
+            return Err(JwtError::new_err(format!(
+                "token is missing the '{name}' claim"
+            )));
+        }
+    }
+
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs_f64())
+        .unwrap_or_default();
+    let time_claim = |name: &str| -> PyResult<Option<f64>> {
+        match claims.get(name) {
+            None => Ok(None),
+            Some(value) => value
+                .as_f64()
+                .map(Some)
+                .ok_or_else(|| JwtError::new_err(format!("'{name}' claim must be a number"))),
+        }
+    };
+    if time_claim("exp")?.is_some_and(|exp| now >= exp + expect.leeway) {
+        return Err(JwtError::new_err("token has expired"));
+    }
+    if time_claim("nbf")?.is_some_and(|nbf| now + expect.leeway < nbf) {
+        return Err(JwtError::new_err("token is not yet valid"));
+    }
+    if time_claim("iat")?.is_some_and(|iat| now + expect.leeway < iat) {
+        return Err(JwtError::new_err("token was issued in the future"));
+    }
+
+    match (claims.get("aud"), expect.audience) {
+        (None, None) => {}
+        (None, Some(_)) => return Err(JwtError::new_err("token is missing the 'aud' claim")),
+        (Some(_), None) => {
+            return Err(JwtError::new_err(
+                "token has an audience but none was expected",
+            ))
+        }
+        (Some(aud), Some(expected)) => {
+            let matches = match aud {
+                Value::String(aud) => expected.contains(aud),
+                Value::Array(auds) => auds
+                    .iter()
+                    .filter_map(Value::as_str)
+                    .any(|aud| expected.iter().any(|e| e == aud)),
+                _ => false,
+            };
+            if !matches {
+                return Err(JwtError::new_err("token audience does not match"));
+            }
+        }
+    }
+
+    if let Some(expected) = expect.issuer {
+        match claims.get("iss").and_then(Value::as_str) {
+            Some(iss) if expected.iter().any(|e| e == iss) => {}
+            Some(_) => return Err(JwtError::new_err("token issuer does not match")),
+            None => return Err(JwtError::new_err("token is missing the 'iss' claim")),
+        }
+    }
+    Ok(())
+}
+
+fn text_or_bytes(value: &Bound<'_, PyAny>) -> PyResult<Vec<u8>> {
+    match value.downcast::<PyBytes>() {
+        Ok(bytes) => Ok(bytes.as_bytes().to_vec()),
+        Err(_) => Ok(value.downcast::<PyString>()?.to_cow()?.as_bytes().to_vec()),
+    }
+}
+
+pub(crate) fn string_or_list(value: &Bound<'_, PyAny>) -> PyResult<Vec<String>> {
+    if let Ok(text) = value.downcast::<PyString>() {
+        return Ok(vec![text.to_cow()?.into_owned()]);
+    }
+    if let Ok(list) = value.downcast::<PyList>() {
+        return list.extract();
+    }
+    value.extract()
+}
+
+/// A JSON object given as a dict or as JSON text.
+fn json_object(value: &Bound<'_, PyAny>, what: &str) -> PyResult<Map<String, Value>> {
+    let value = if value.is_instance_of::<PyString>() || value.is_instance_of::<PyBytes>() {
+        serde_json::from_slice(&text_or_bytes(value)?)
+            .map_err(|e| JwtError::new_err(format!("invalid {what} JSON: {e}")))?
+    } else {
+        py_to_value(value)?
+    };
+    match value {
+        Value::Object(map) => Ok(map),
+        _ => Err(JwtError::new_err(format!("{what} must be a JSON object"))),
+    }
+}
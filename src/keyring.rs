@@ -0,0 +1,154 @@
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+use pyo3::types::PyTuple;
+
+use crate::crypto::{hmac_sha256, hmac_sha256_verify, secret_bytes};
+
+/// Length of the key ids derived for secrets given without one.
+const DERIVED_KID_LEN: usize = 8;
+
+struct Key {
+    kid: String,
+    secret: Vec<u8>,
+}
+
+impl Key {
+    fn from_py(obj: &Bound<'_, PyAny>) -> PyResult<Self> {
+        let Ok(pair) = obj.downcast::<PyTuple>() else {
+            let secret = secret_bytes(obj)?;
+            return Ok(Self {
+                kid: derive_kid(&secret),
+                secret,
+            });
+        };
+        let (kid, secret): (String, Bound<'_, PyAny>) = pair.extract()?;
+        if kid.is_empty() || kid.contains('.') {
+            return Err(PyValueError::new_err(format!(
+                "invalid key id {kid:?}: must be non-empty and contain no '.'"
+            )));
+        }
+        Ok(Self {
+            kid,
+            secret: secret_bytes(&secret)?,
+        })
+    }
+}
+
+/// Signing keys for cookies and session tokens, with rotation.
+///
+/// The first key signs; retired keys only verify, so tokens issued before a
+/// rotation stay valid until they expire. Tokens look like
+/// `kid.base64url(payload).base64url(signature)`, the signature covering the
+/// key id and the encoded payload. Two-part tokens from `sign_cookie` are
+/// still accepted and checked against every key.
+///
+/// Keys are secrets (`str` or `bytes`) or `(kid, secret)` tuples; a secret
+/// without a kid gets one derived from it.
+#[pyclass(frozen, module = "haske")]
+pub struct Keyring {
+    keys: Vec<Key>,
+}
+
+impl Keyring {
+    fn find(&self, kid: &str) -> Option<&Key> {
+        self.keys.iter().find(|key| key.kid == kid)
+    }
+
+    pub(crate) fn active_secret(&self) -> &[u8] {
+        &self.keys[0].secret
+    }
+
+    /// Every secret, signing key first.
+    pub(crate) fn secrets(&self) -> impl Iterator<Item = &[u8]> {
+        self.keys.iter().map(|key| key.secret.as_slice())
+    }
+}
+
+#[pymethods]
+impl Keyring {
+    #[new]
+    #[pyo3(signature = (active, retired=Vec::new()))]
+    fn new(active: &Bound<'_, PyAny>, retired: Vec<Bound<'_, PyAny>>) -> PyResult<Self> {
+        let mut keys = vec![Key::from_py(active)?];
+        for obj in &retired {
+            let key = Key::from_py(obj)?;
+            if keys.iter().any(|k| k.kid == key.kid) {
+                return Err(PyValueError::new_err(format!(
+                    "duplicate key id {:?}",
+                    key.kid
+                )));
+            }
+            keys.push(key);
+        }
+        Ok(Self { keys })
+    }
+
+    /// Key id of the signing key.
+    #[getter]
+    fn active_kid(&self) -> &str {
+        &self.keys[0].kid
+    }
+
+    /// Every key id, signing key first.
+    #[getter]
+    fn kids(&self) -> Vec<String> {
+        self.keys.iter().map(|key| key.kid.clone()).collect()
+    }
+
+    /// Sign `payload` with the active key.
+    fn sign(&self, payload: &str) -> String {
+        let key = &self.keys[0];
+        let signing_input = format!("{}.{}", key.kid, URL_SAFE_NO_PAD.encode(payload));
+        let signature = hmac_sha256(&key.secret, signing_input.as_bytes());
+        format!("{signing_input}.{}", URL_SAFE_NO_PAD.encode(signature))
+    }
+
+    /// Verify `token`, returning `(payload, kid)` with the id of the key that
+    /// matched, or `None` if no key does.
+    ///
+    /// A kid other than `active_kid` means the token should be re-issued.
+    fn verify(&self, token: &str) -> Option<(String, String)> {
+        let (signing_input, encoded_signature) = token.rsplit_once('.')?;
+        let signature = URL_SAFE_NO_PAD.decode(encoded_signature).ok()?;
+        match signing_input.split_once('.') {
+            Some((kid, encoded_payload)) => {
+                let key = self.find(kid)?;
+                if !hmac_sha256_verify(&key.secret, signing_input.as_bytes(), &signature) {
+                    return None;
+                }
+                let payload = URL_SAFE_NO_PAD.decode(encoded_payload).ok()?;
+                Some((String::from_utf8(payload).ok()?, key.kid.clone()))
+            }
+            None => {
+                let payload = URL_SAFE_NO_PAD.decode(signing_input).ok()?;
+                let key = self
+                    .keys
+                    .iter()
+                    .find(|key| hmac_sha256_verify(&key.secret, &payload, &signature))?;
+                Some((String::from_utf8(payload).ok()?, key.kid.clone()))
+            }
+        }
+    }
+
+    fn __len__(&self) -> usize {
+        self.keys.len()
+    }
+
+    fn __repr__(&self) -> String {
+        let kids: Vec<String> = self
+            .keys
+            .iter()
+            .map(|key| format!("'{}'", key.kid))
+            .collect();
+        format!("Keyring(kids=[{}])", kids.join(", "))
+    }
+}
+
+/// Stable, non-reversible id for a secret: a truncated HMAC under the secret.
+fn derive_kid(secret: &[u8]) -> String {
+    let mut kid = URL_SAFE_NO_PAD.encode(hmac_sha256(secret, b"haske keyring kid"));
+    kid.truncate(DERIVED_KID_LEN);
+    kid
+}
@@ -1,38 +1,294 @@
+use pyo3::exceptions::PyImportError;
 use pyo3::prelude::*;
 use pyo3::types::PyModule;
 
-#[pymodule]
-// #[pyo3(name = "_haske_core")]
-fn haske(_py: Python, m: &Bound<PyModule>) -> PyResult<()> {
-    // Import the core module
-    let core = PyModule::import(py, "_haske_core")?;
-
-    // Re-export all classes
-    m.add("HaskeApp", core.getattr("HaskeApp")?)?;
-    m.add("HaskeCache", core.getattr("HaskeCache")?)?;
-    m.add("WebSocketFrame", core.getattr("WebSocketFrame")?)?;
-
-    // Re-export all functions with proper names
-    let functions = [
-        "compile_path", "match_path",
-        "json_loads_bytes", "json_dumps_obj", "json_is_valid", "json_extract_field",
-        "render_template", "precompile_template",
-        "sign_cookie", "verify_cookie", "hash_password", "verify_password", "generate_random_bytes",
-        "prepare_query", "prepare_queries",
-        "create_cache",
-        "gzip_compress", "gzip_decompress", "zstd_compress", "zstd_decompress", 
-        "brotli_compress", "brotli_decompress",
-        "websocket_accept_key"
-    ];
-
-    for func_name in functions.iter() {
-        if let Ok(func) = core.getattr(*func_name) {
-            m.add(*func_name, func)?;
+mod api_keys;
+mod cache;
+mod compress;
+mod converters;
+mod crypto;
+mod csrf;
+mod disk_cache;
+mod dispatch;
+mod encryption;
+mod json;
+mod json_stream;
+mod jwt;
+mod keyring;
+mod multidict;
+mod multipart;
+mod oidc;
+mod orm;
+mod otp;
+mod password;
+mod path;
+mod policy;
+mod router;
+mod schema;
+mod session_store;
+mod shared_cache;
+mod templates;
+mod ws;
+
+/// Every symbol the `haske` Python package imports from the extension.
+const EXPORTS: &[&str] = &[
+    "HaskeApp",
+    "HaskeCache",
+    "SharedCache",
+    "WebSocketFrame",
+    "WebSocketManager",
+    "WebSocketReceiver",
+    "MultipartParser",
+    "UploadFile",
+    "MultiDict",
+    "JsonStream",
+    "JsonSchema",
+    "JwtKey",
+    "Jwks",
+    "IdTokenVerifier",
+    "PasswordPolicy",
+    "Keyring",
+    "CsrfGuard",
+    "ApiKeys",
+    "ApiKey",
+    "Totp",
+    "Hotp",
+    "Policy",
+    "Decision",
+    "SessionStore",
+    "Session",
+    "MultipartError",
+    "MultipartLimitError",
+    "SchemaError",
+    "JwtError",
+    "SessionStoreError",
+    "PolicyError",
+    "compile_path",
+    "match_path",
+    "register_converter",
+    "parse_query_string",
+    "parse_cookie_header",
+    "json_loads_bytes",
+    "json_dumps_obj",
+    "json_is_valid",
+    "json_extract_field",
+    "stream_json",
+    "compile_schema",
+    "render_template",
+    "precompile_template",
+    "sign_cookie",
+    "verify_cookie",
+    "encrypt_cookie",
+    "decrypt_cookie",
+    "jwt_encode",
+    "jwt_decode",
+    "jwt_decode_header",
+    "load_jwks",
+    "load_jwks_file",
+    "generate_pkce_verifier",
+    "pkce_challenge",
+    "verify_pkce",
+    "hash_password",
+    "verify_password",
+    "needs_rehash",
+    "generate_otp_secret",
+    "generate_recovery_codes",
+    "hash_recovery_code",
+    "verify_recovery_code",
+    "generate_random_bytes",
+    "prepare_query",
+    "prepare_queries",
+    "build_select_query",
+    "build_update_query",
+    "build_delete_query",
+    "batch_insert",
+    "process_result_set",
+    "optimize_type_conversion",
+    "validate_query_syntax",
+    "cache_prepared_statement",
+    "get_cached_statement",
+    "clear_statement_cache",
+    "get_connection_from_pool",
+    "return_connection_to_pool",
+    "create_cache",
+    "gzip_compress",
+    "gzip_decompress",
+    "zstd_compress",
+    "zstd_decompress",
+    "brotli_compress",
+    "brotli_decompress",
+    "websocket_accept_key",
+    "validate_websocket_frame",
+    "get_frame_type",
+    "get_payload_length",
+    "is_final_frame",
+    "is_masked_frame",
+];
+
+/// Fail module import with every missing name instead of letting Python
+/// code hit a bare `ImportError` on the first one it happens to use.
+fn check_exports(m: &Bound<PyModule>) -> PyResult<()> {
+    let mut missing = Vec::new();
+    for name in EXPORTS {
+        if !m.hasattr(*name)? {
+            missing.push(*name);
         }
     }
+    if missing.is_empty() {
+        Ok(())
+    } else {
+        Err(PyImportError::new_err(format!(
+            "haske native extension is incomplete; missing symbols: {}",
+            missing.join(", ")
+        )))
+    }
+}
+
+#[pymodule]
+fn haske(m: &Bound<PyModule>) -> PyResult<()> {
+    // Classes
+    m.add_class::<router::HaskeApp>()?;
+    m.add_class::<router::RouteMatch>()?;
+    m.add_class::<dispatch::Dispatch>()?;
+    m.add_class::<cache::HaskeCache>()?;
+    m.add_class::<cache::CacheLoad>()?;
+    m.add_class::<cache::CacheNamespace>()?;
+    m.add_class::<shared_cache::SharedCache>()?;
+    m.add_class::<ws::WebSocketFrame>()?;
+    m.add_class::<ws::WebSocketManager>()?;
+    m.add_class::<ws::WebSocketReceiver>()?;
+    m.add_class::<multipart::MultipartParser>()?;
+    m.add_class::<multipart::UploadFile>()?;
+    m.add_class::<multidict::MultiDict>()?;
+    m.add_class::<json_stream::JsonStream>()?;
+    m.add_class::<json_stream::JsonStreamNext>()?;
+    m.add_class::<schema::JsonSchema>()?;
+    m.add_class::<jwt::JwtKey>()?;
+    m.add_class::<oidc::Jwks>()?;
+    m.add_class::<oidc::IdTokenVerifier>()?;
+    m.add_class::<password::PasswordPolicy>()?;
+    m.add_class::<keyring::Keyring>()?;
+    m.add_class::<csrf::CsrfGuard>()?;
+    m.add_class::<api_keys::ApiKeys>()?;
+    m.add_class::<api_keys::ApiKey>()?;
+    m.add_class::<otp::Totp>()?;
+    m.add_class::<otp::Hotp>()?;
+    m.add_class::<policy::Policy>()?;
+    m.add_class::<policy::Decision>()?;
+    m.add_class::<session_store::SessionStore>()?;
+    m.add_class::<session_store::Session>()?;
+
+    // Exceptions
+    m.add(
+        "MultipartError",
+        m.py().get_type::<multipart::MultipartError>(),
+    )?;
+    m.add(
+        "MultipartLimitError",
+        m.py().get_type::<multipart::MultipartLimitError>(),
+    )?;
+    m.add("SchemaError", m.py().get_type::<schema::SchemaError>())?;
+    m.add("JwtError", m.py().get_type::<jwt::JwtError>())?;
+    m.add(
+        "SessionStoreError",
+        m.py().get_type::<session_store::SessionStoreError>(),
+    )?;
+    m.add("PolicyError", m.py().get_type::<policy::PolicyError>())?;
+
+    // Routing
+    m.add_function(wrap_pyfunction!(path::compile_path, m)?)?;
+    m.add_function(wrap_pyfunction!(path::match_path, m)?)?;
+    m.add_function(wrap_pyfunction!(converters::register_converter, m)?)?;
+
+    // Query strings and cookies
+    m.add_function(wrap_pyfunction!(multidict::parse_query_string, m)?)?;
+    m.add_function(wrap_pyfunction!(multidict::parse_cookie_header, m)?)?;
+
+    // JSON
+    m.add_function(wrap_pyfunction!(json::json_loads_bytes, m)?)?;
+    m.add_function(wrap_pyfunction!(json::json_dumps_obj, m)?)?;
+    m.add_function(wrap_pyfunction!(json::json_is_valid, m)?)?;
+    m.add_function(wrap_pyfunction!(json::json_extract_field, m)?)?;
+    m.add_function(wrap_pyfunction!(json_stream::stream_json, m)?)?;
+
+    // JSON Schema
+    m.add_function(wrap_pyfunction!(schema::compile_schema, m)?)?;
+
+    // Templates
+    m.add_function(wrap_pyfunction!(templates::render_template, m)?)?;
+    m.add_function(wrap_pyfunction!(templates::precompile_template, m)?)?;
+
+    // Crypto
+    m.add_function(wrap_pyfunction!(crypto::sign_cookie, m)?)?;
+    m.add_function(wrap_pyfunction!(crypto::verify_cookie, m)?)?;
+    m.add_function(wrap_pyfunction!(encryption::encrypt_cookie, m)?)?;
+    m.add_function(wrap_pyfunction!(encryption::decrypt_cookie, m)?)?;
+    m.add_function(wrap_pyfunction!(crypto::generate_random_bytes, m)?)?;
+
+    // Passwords
+    m.add_function(wrap_pyfunction!(password::hash_password, m)?)?;
+    m.add_function(wrap_pyfunction!(password::verify_password, m)?)?;
+    m.add_function(wrap_pyfunction!(password::needs_rehash, m)?)?;
+
+    // One-time passwords
+    m.add_function(wrap_pyfunction!(otp::generate_otp_secret, m)?)?;
+    m.add_function(wrap_pyfunction!(otp::generate_recovery_codes, m)?)?;
+    m.add_function(wrap_pyfunction!(otp::hash_recovery_code, m)?)?;
+    m.add_function(wrap_pyfunction!(otp::verify_recovery_code, m)?)?;
+
+    // JWT
+    m.add_function(wrap_pyfunction!(jwt::jwt_encode, m)?)?;
+    m.add_function(wrap_pyfunction!(jwt::jwt_decode, m)?)?;
+    m.add_function(wrap_pyfunction!(jwt::jwt_decode_header, m)?)?;
+    m.add_function(wrap_pyfunction!(jwt::load_jwks, m)?)?;
+    m.add_function(wrap_pyfunction!(jwt::load_jwks_file, m)?)?;
+
+    // OpenID Connect
+    m.add_function(wrap_pyfunction!(oidc::generate_pkce_verifier, m)?)?;
+    m.add_function(wrap_pyfunction!(oidc::pkce_challenge, m)?)?;
+    m.add_function(wrap_pyfunction!(oidc::verify_pkce, m)?)?;
+
+    // Query preparation
+    m.add_function(wrap_pyfunction!(orm::prepare_query, m)?)?;
+    m.add_function(wrap_pyfunction!(orm::prepare_queries, m)?)?;
+
+    // ORM
+    m.add_function(wrap_pyfunction!(orm::build_select_query, m)?)?;
+    m.add_function(wrap_pyfunction!(orm::build_update_query, m)?)?;
+    m.add_function(wrap_pyfunction!(orm::build_delete_query, m)?)?;
+    m.add_function(wrap_pyfunction!(orm::batch_insert, m)?)?;
+    m.add_function(wrap_pyfunction!(orm::process_result_set, m)?)?;
+    m.add_function(wrap_pyfunction!(orm::optimize_type_conversion, m)?)?;
+    m.add_function(wrap_pyfunction!(orm::validate_query_syntax, m)?)?;
+    m.add_function(wrap_pyfunction!(orm::cache_prepared_statement, m)?)?;
+    m.add_function(wrap_pyfunction!(orm::get_cached_statement, m)?)?;
+    m.add_function(wrap_pyfunction!(orm::clear_statement_cache, m)?)?;
+    m.add_function(wrap_pyfunction!(orm::get_connection_from_pool, m)?)?;
+    m.add_function(wrap_pyfunction!(orm::return_connection_to_pool, m)?)?;
+
+    // Cache
+    m.add_function(wrap_pyfunction!(cache::create_cache, m)?)?;
+
+    // Compression
+    m.add_function(wrap_pyfunction!(compress::gzip_compress, m)?)?;
+    m.add_function(wrap_pyfunction!(compress::gzip_decompress, m)?)?;
+    m.add_function(wrap_pyfunction!(compress::zstd_compress, m)?)?;
+    m.add_function(wrap_pyfunction!(compress::zstd_decompress, m)?)?;
+    m.add_function(wrap_pyfunction!(compress::brotli_compress, m)?)?;
+    m.add_function(wrap_pyfunction!(compress::brotli_decompress, m)?)?;
+
+    // WebSocket
+    m.add_function(wrap_pyfunction!(ws::websocket_accept_key, m)?)?;
+    m.add_function(wrap_pyfunction!(ws::validate_websocket_frame, m)?)?;
+    m.add_function(wrap_pyfunction!(ws::get_frame_type, m)?)?;
+    m.add_function(wrap_pyfunction!(ws::get_payload_length, m)?)?;
+    m.add_function(wrap_pyfunction!(ws::is_final_frame, m)?)?;
+    m.add_function(wrap_pyfunction!(ws::is_masked_frame, m)?)?;
+
+    check_exports(m)?;
 
     // Add build information
     m.add("HAS_RUST_EXTENSION", true)?;
-    
+
     Ok(())
-}
\ No newline at end of file
+}
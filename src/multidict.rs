@@ -0,0 +1,237 @@
+use std::collections::hash_map::{Entry, HashMap};
+
+use pyo3::exceptions::PyKeyError;
+use pyo3::prelude::*;
+use pyo3::types::{PyBool, PyBytes, PyList, PyString};
+
+/// Values accepted as true or false by `MultiDict.get_bool`.
+const TRUE_VALUES: &[&str] = &["1", "true", "yes", "on"];
+const FALSE_VALUES: &[&str] = &["0", "false", "no", "off"];
+
+/// Immutable multi-valued mapping returned by `parse_query_string` and
+/// `parse_cookie_header`.
+///
+/// Pairs keep their original order. Mapping access (`[]`, `get`) returns the
+/// first value for a key; `getlist` returns all of them.
+#[pyclass(frozen, module = "haske")]
+pub struct MultiDict {
+    items: Vec<(String, String)>,
+    /// Position in `items` of each key's first pair.
+    firsts: HashMap<String, usize>,
+    /// Positions of those first pairs, in key order.
+    order: Vec<usize>,
+}
+
+impl MultiDict {
+    fn from_items(items: Vec<(String, String)>) -> Self {
+        let mut firsts = HashMap::with_capacity(items.len());
+        let mut order = Vec::new();
+        for (index, (key, _)) in items.iter().enumerate() {
+            if let Entry::Vacant(entry) = firsts.entry(key.clone()) {
+                entry.insert(index);
+                order.push(index);
+            }
+        }
+        Self {
+            items,
+            firsts,
+            order,
+        }
+    }
+
+    fn first(&self, key: &str) -> Option<&str> {
+        self.firsts
+            .get(key)
+            .map(|&index| self.items[index].1.as_str())
+    }
+
+    /// `(key, first value)` for each key, in order.
+    fn first_items(&self) -> impl Iterator<Item = &(String, String)> {
+        self.order.iter().map(|&index| &self.items[index])
+    }
+}
+
+#[pymethods]
+impl MultiDict {
+    /// Build from `(key, value)` pairs, e.g. `MultiDict([("a", "1")])`.
+    #[new]
+    #[pyo3(signature = (items=Vec::new()))]
+    fn new(items: Vec<(String, String)>) -> Self {
+        Self::from_items(items)
+    }
+
+    #[pyo3(signature = (key, default=None))]
+    fn get(&self, py: Python<'_>, key: &str, default: Option<Py<PyAny>>) -> Py<PyAny> {
+        match self.first(key) {
+            Some(value) => PyString::new(py, value).into_any().unbind(),
+            None => default.unwrap_or_else(|| py.None()),
+        }
+    }
+
+    fn getlist(&self, key: &str) -> Vec<String> {
+        self.items
+            .iter()
+            .filter(|(k, _)| k == key)
+            .map(|(_, v)| v.clone())
+            .collect()
+    }
+
+    /// First value parsed as an integer, or `default` if missing or invalid.
+    #[pyo3(signature = (key, default=None))]
+    fn get_int(
+        &self,
+        py: Python<'_>,
+        key: &str,
+        default: Option<Py<PyAny>>,
+    ) -> PyResult<Py<PyAny>> {
+        match self.first(key).and_then(|v| v.trim().parse::<i64>().ok()) {
+            Some(value) => Ok(value.into_pyobject(py)?.into_any().unbind()),
+            None => Ok(default.unwrap_or_else(|| py.None())),
+        }
+    }
+
+    /// First value read as a boolean (`1/true/yes/on`, `0/false/no/off`,
+    /// case-insensitive), or `default` if missing or unrecognised.
+    #[pyo3(signature = (key, default=None))]
+    fn get_bool(&self, py: Python<'_>, key: &str, default: Option<Py<PyAny>>) -> Py<PyAny> {
+        let parsed = self.first(key).and_then(|v| {
+            let v = v.trim().to_ascii_lowercase();
+            if TRUE_VALUES.contains(&v.as_str()) {
+                Some(true)
+            } else if FALSE_VALUES.contains(&v.as_str()) {
+                Some(false)
+            } else {
+                None
+            }
+        });
+        match parsed {
+            Some(value) => PyBool::new(py, value).to_owned().into_any().unbind(),
+            None => default.unwrap_or_else(|| py.None()),
+        }
+    }
+
+    fn keys(&self) -> Vec<String> {
+        self.first_items().map(|(k, _)| k.clone()).collect()
+    }
+
+    /// First value of each key.
+    fn values(&self) -> Vec<String> {
+        self.first_items().map(|(_, v)| v.clone()).collect()
+    }
+
+    /// `(key, first value)` for each key.
+    fn items(&self) -> Vec<(String, String)> {
+        self.first_items().cloned().collect()
+    }
+
+    /// Every pair, duplicates included.
+    fn multi_items(&self) -> Vec<(String, String)> {
+        self.items.clone()
+    }
+
+    fn __getitem__(&self, key: &str) -> PyResult<String> {
+        self.first(key)
+            .map(str::to_owned)
+            .ok_or_else(|| PyKeyError::new_err(key.to_owned()))
+    }
+
+    fn __contains__(&self, key: &str) -> bool {
+        self.first(key).is_some()
+    }
+
+    fn __len__(&self) -> usize {
+        self.order.len()
+    }
+
+    fn __iter__<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyAny>> {
+        Ok(PyList::new(py, self.keys())?.try_iter()?.into_any())
+    }
+
+    fn __bool__(&self) -> bool {
+        !self.items.is_empty()
+    }
+
+    fn __repr__(&self, py: Python<'_>) -> PyResult<String> {
+        Ok(format!(
+            "MultiDict({})",
+            PyList::new(py, &self.items)?.repr()?
+        ))
+    }
+}
+
+/// Parse an `application/x-www-form-urlencoded` query string.
+///
+/// Accepts `str` or the raw `bytes` from the ASGI scope. `+` decodes to a
+/// space, invalid UTF-8 is replaced, and keys without `=` get an empty value.
+#[pyfunction]
+pub fn parse_query_string(query: &Bound<'_, PyAny>) -> PyResult<MultiDict> {
+    let raw = match query.downcast::<PyBytes>() {
+        Ok(bytes) => String::from_utf8_lossy(bytes.as_bytes()).into_owned(),
+        Err(_) => query.extract::<String>()?,
+    };
+    let items = raw
+        .split('&')
+        .filter(|pair| !pair.is_empty())
+        .map(|pair| {
+            let (key, value) = pair.split_once('=').unwrap_or((pair, ""));
+            (form_decode(key), form_decode(value))
+        })
+        .collect();
+    Ok(MultiDict::from_items(items))
+}
+
+/// Parse a `Cookie` request header following RFC 6265 section 5.4.
+///
+/// Pairs are split on `;`, whitespace around names and values is dropped and
+/// a value wrapped in double quotes is unquoted. Pairs without `=` or with an
+/// empty name are ignored. Duplicate names are kept in header order, which
+/// puts the most specific cookie first.
+#[pyfunction]
+pub fn parse_cookie_header(header: &str) -> MultiDict {
+    let items = header
+        .split(';')
+        .filter_map(|pair| {
+            let (name, value) = pair.split_once('=')?;
+            let name = name.trim();
+            if name.is_empty() {
+                return None;
+            }
+            let value = value.trim();
+            let value = value
+                .strip_prefix('"')
+                .and_then(|v| v.strip_suffix('"'))
+                .unwrap_or(value);
+            Some((name.to_owned(), value.to_owned()))
+        })
+        .collect();
+    MultiDict::from_items(items)
+}
+
+/// Percent-decode a form component, treating `+` as a space.
+fn form_decode(text: &str) -> String {
+    let bytes = text.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'+' => out.push(b' '),
+            b'%' => match (
+                bytes.get(i + 1).and_then(|b| hex(*b)),
+                bytes.get(i + 2).and_then(|b| hex(*b)),
+            ) {
+                (Some(high), Some(low)) => {
+                    out.push(high << 4 | low);
+                    i += 2;
+                }
+                _ => out.push(b'%'),
+            },
+            byte => out.push(byte),
+        }
+        i += 1;
+    }
+    String::from_utf8_lossy(&out).into_owned()
+}
+
+fn hex(byte: u8) -> Option<u8> {
+    (byte as char).to_digit(16).map(|d| d as u8)
+}
@@ -0,0 +1,540 @@
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Cursor, Read, Seek, SeekFrom, Write};
+use std::path::PathBuf;
+
+use memchr::memmem;
+use pyo3::create_exception;
+use pyo3::exceptions::{PyIOError, PyValueError};
+use pyo3::prelude::*;
+use pyo3::types::{PyBytes, PyDict, PyList, PyString};
+
+use crate::crypto::random_bytes;
+
+create_exception!(
+    haske,
+    MultipartError,
+    PyValueError,
+    "Malformed multipart/form-data body."
+);
+create_exception!(
+    haske,
+    MultipartLimitError,
+    MultipartError,
+    "A multipart part or body exceeded its configured size limit."
+);
+
+/// Upper bound on one part's header block.
+const MAX_HEADER_SIZE: usize = 16 * 1024;
+const DEFAULT_SPOOL_SIZE: usize = 1024 * 1024;
+/// Plain fields are always held in memory, so they get a limit of their own.
+const DEFAULT_MAX_FIELD_SIZE: usize = 1024 * 1024;
+const DEFAULT_MAX_PARTS: usize = 1000;
+
+enum State {
+    Preamble,
+    /// Just after a delimiter: expecting `\r\n` (next part) or `--` (end).
+    Boundary,
+    Headers,
+    Body,
+    Done,
+}
+
+/// Upload contents, in memory until they outgrow the spool size.
+enum Storage {
+    Memory(Cursor<Vec<u8>>),
+    Disk { file: File, path: PathBuf },
+}
+
+impl Storage {
+    fn write_all(&mut self, data: &[u8]) -> io::Result<()> {
+        match self {
+            Self::Memory(cursor) => cursor.write_all(data),
+            Self::Disk { file, .. } => file.write_all(data),
+        }
+    }
+
+    /// Move in-memory contents to a fresh temporary file.
+    fn spill(&mut self) -> io::Result<()> {
+        if let Self::Memory(cursor) = self {
+            let path = std::env::temp_dir().join(format!(
+                "haske-upload-{}",
+                random_bytes(12)
+                    .iter()
+                    .map(|b| format!("{b:02x}"))
+                    .collect::<String>()
+            ));
+            let mut options = OpenOptions::new();
+            options.read(true).write(true).create_new(true);
+            #[cfg(unix)]
+            {
+                use std::os::unix::fs::OpenOptionsExt;
+                options.mode(0o600).custom_flags(libc::O_NOFOLLOW);
+            }
+            let mut file = options.open(&path)?;
+            file.write_all(cursor.get_ref())?;
+            *self = Self::Disk { file, path };
+        }
+        Ok(())
+    }
+}
+
+impl Read for Storage {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        match self {
+            Self::Memory(cursor) => cursor.read(buf),
+            Self::Disk { file, .. } => file.read(buf),
+        }
+    }
+}
+
+impl Seek for Storage {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        match self {
+            Self::Memory(cursor) => cursor.seek(pos),
+            Self::Disk { file, .. } => file.seek(pos),
+        }
+    }
+}
+
+impl Drop for Storage {
+    fn drop(&mut self) {
+        if let Self::Disk { path, .. } = self {
+            let _ = fs::remove_file(path);
+        }
+    }
+}
+
+/// A file uploaded through `multipart/form-data`.
+///
+/// Small uploads stay in memory; larger ones live in a temporary file that
+/// is deleted on `close()` or when the object is garbage collected.
+#[pyclass(module = "haske")]
+pub struct UploadFile {
+    #[pyo3(get)]
+    filename: String,
+    #[pyo3(get)]
+    content_type: Option<String>,
+    #[pyo3(get)]
+    size: usize,
+    headers: Vec<(String, String)>,
+    storage: Option<Storage>,
+}
+
+impl UploadFile {
+    fn storage(&mut self) -> PyResult<&mut Storage> {
+        self.storage
+            .as_mut()
+            .ok_or_else(|| PyValueError::new_err("I/O operation on closed file"))
+    }
+}
+
+#[pymethods]
+impl UploadFile {
+    /// Part headers, lowercased names.
+    #[getter]
+    fn headers<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyDict>> {
+        let headers = PyDict::new(py);
+        for (name, value) in &self.headers {
+            headers.set_item(name, value)?;
+        }
+        Ok(headers)
+    }
+
+    /// Whether the contents were spilled to a temporary file.
+    #[getter]
+    fn on_disk(&self) -> bool {
+        matches!(self.storage, Some(Storage::Disk { .. }))
+    }
+
+    /// Read up to `size` bytes, or everything left when `size` is negative.
+    #[pyo3(signature = (size=-1))]
+    fn read<'py>(&mut self, py: Python<'py>, size: isize) -> PyResult<Bound<'py, PyBytes>> {
+        let storage = self.storage()?;
+        let mut out = Vec::new();
+        let result = match usize::try_from(size) {
+            Ok(limit) => storage.take(limit as u64).read_to_end(&mut out),
+            Err(_) => storage.read_to_end(&mut out),
+        };
+        result.map_err(|e| PyIOError::new_err(e.to_string()))?;
+        Ok(PyBytes::new(py, &out))
+    }
+
+    #[pyo3(signature = (offset, whence=0))]
+    fn seek(&mut self, offset: i64, whence: u8) -> PyResult<u64> {
+        let pos = match whence {
+            0 => SeekFrom::Start(
+                u64::try_from(offset)
+                    .map_err(|_| PyValueError::new_err("negative seek position"))?,
+            ),
+            1 => SeekFrom::Current(offset),
+            2 => SeekFrom::End(offset),
+            _ => return Err(PyValueError::new_err(format!("invalid whence ({whence})"))),
+        };
+        self.storage()?
+            .seek(pos)
+            .map_err(|e| PyIOError::new_err(e.to_string()))
+    }
+
+    fn tell(&mut self) -> PyResult<u64> {
+        self.storage()?
+            .stream_position()
+            .map_err(|e| PyIOError::new_err(e.to_string()))
+    }
+
+    /// Release the contents, deleting the temporary file if there is one.
+    fn close(&mut self) {
+        self.storage = None;
+    }
+
+    #[getter]
+    fn closed(&self) -> bool {
+        self.storage.is_none()
+    }
+
+    fn __repr__(&self) -> String {
+        format!(
+            "UploadFile(filename={:?}, size={}, content_type={})",
+            self.filename,
+            self.size,
+            self.content_type
+                .as_deref()
+                .map_or_else(|| "None".to_owned(), |c| format!("{c:?}"))
+        )
+    }
+}
+
+/// The part currently being read.
+struct Part {
+    name: String,
+    filename: Option<String>,
+    content_type: Option<String>,
+    headers: Vec<(String, String)>,
+    size: usize,
+    data: Storage,
+}
+
+/// Incremental `multipart/form-data` parser.
+///
+/// Feed body chunks as they arrive from the ASGI `receive` channel, then call
+/// `finish()` for the `(name, value)` pairs in body order. Plain fields are
+/// `str`, file parts are `UploadFile`.
+#[pyclass(module = "haske")]
+pub struct MultipartParser {
+    delimiter: Vec<u8>,
+    max_part_size: Option<usize>,
+    max_total_size: Option<usize>,
+    spool_size: usize,
+    max_field_size: usize,
+    max_parts: usize,
+    parts: usize,
+    state: State,
+    buffer: Vec<u8>,
+    total: usize,
+    part: Option<Part>,
+    fields: Vec<(String, Py<PyAny>)>,
+}
+
+#[pymethods]
+impl MultipartParser {
+    /// `content_type` is the request's `Content-Type` header, which carries
+    /// the boundary. Sizes are in bytes; `None` disables a limit. Plain
+    /// (non-file) fields are capped at `max_field_size` and the body at
+    /// `max_parts` parts.
+    #[new]
+    #[pyo3(signature = (content_type, max_part_size=None, max_total_size=None, spool_size=DEFAULT_SPOOL_SIZE, max_field_size=DEFAULT_MAX_FIELD_SIZE, max_parts=DEFAULT_MAX_PARTS))]
+    fn new(
+        content_type: &str,
+        max_part_size: Option<usize>,
+        max_total_size: Option<usize>,
+        spool_size: usize,
+        max_field_size: usize,
+        max_parts: usize,
+    ) -> PyResult<Self> {
+        let boundary = boundary(content_type)
+            .ok_or_else(|| MultipartError::new_err("missing multipart boundary"))?;
+        let mut delimiter = b"\r\n--".to_vec();
+        delimiter.extend_from_slice(boundary.as_bytes());
+        Ok(Self {
+            delimiter,
+            max_part_size,
+            max_total_size,
+            spool_size,
+            max_field_size,
+            max_parts,
+            parts: 0,
+            state: State::Preamble,
+            // The first delimiter has no preceding line break; supply one.
+            buffer: b"\r\n".to_vec(),
+            total: 0,
+            part: None,
+            fields: Vec::new(),
+        })
+    }
+
+    /// Parse another chunk of the body.
+    fn feed(&mut self, py: Python<'_>, chunk: &[u8]) -> PyResult<()> {
+        self.total += chunk.len();
+        if let Some(limit) = self.max_total_size {
+            if self.total > limit {
+                return Err(MultipartLimitError::new_err(format!(
+                    "multipart body exceeds {limit} bytes"
+                )));
+            }
+        }
+        if matches!(self.state, State::Done) {
+            return Ok(());
+        }
+        self.buffer.extend_from_slice(chunk);
+        self.process(py)
+    }
+
+    /// The first plain field called `name` parsed so far, so a caller can
+    /// stop reading once the field it needs has arrived.
+    fn field(&self, py: Python<'_>, name: &str) -> Option<String> {
+        self.fields
+            .iter()
+            .filter(|(field, _)| field == name)
+            .find_map(|(_, value)| value.bind(py).extract::<String>().ok())
+    }
+
+    /// Return the parsed fields; fails if the closing delimiter never arrived.
+    fn finish<'py>(&mut self, py: Python<'py>) -> PyResult<Bound<'py, PyList>> {
+        if !matches!(self.state, State::Done) {
+            return Err(MultipartError::new_err("multipart body ended unexpectedly"));
+        }
+        PyList::new(py, std::mem::take(&mut self.fields))
+    }
+}
+
+impl MultipartParser {
+    fn process(&mut self, py: Python<'_>) -> PyResult<()> {
+        loop {
+            match self.state {
+                State::Preamble => match memmem::find(&self.buffer, &self.delimiter) {
+                    Some(at) => {
+                        self.buffer.drain(..at + self.delimiter.len());
+                        self.state = State::Boundary;
+                    }
+                    None => {
+                        let keep = self.delimiter.len() - 1;
+                        if self.buffer.len() > keep {
+                            self.buffer.drain(..self.buffer.len() - keep);
+                        }
+                        return Ok(());
+                    }
+                },
+                State::Boundary => {
+                    if self.buffer.len() < 2 {
+                        return Ok(());
+                    }
+                    match &self.buffer[..2] {
+                        b"--" => {
+                            self.buffer.clear();
+                            self.state = State::Done;
+                            return Ok(());
+                        }
+                        b"\r\n" => {
+                            self.buffer.drain(..2);
+                            self.state = State::Headers;
+                        }
+                        _ => return Err(MultipartError::new_err("malformed multipart delimiter")),
+                    }
+                }
+                State::Headers => {
+                    let Some(end) = memmem::find(&self.buffer, b"\r\n\r\n") else {
+                        if self.buffer.len() > MAX_HEADER_SIZE {
+                            return Err(MultipartLimitError::new_err(
+                                "multipart part headers are too large",
+                            ));
+                        }
+                        return Ok(());
+                    };
+                    self.parts += 1;
+                    if self.parts > self.max_parts {
+                        return Err(MultipartLimitError::new_err(format!(
+                            "multipart body has more than {} parts",
+                            self.max_parts
+                        )));
+                    }
+                    let part = parse_part_headers(&self.buffer[..end])?;
+                    self.buffer.drain(..end + 4);
+                    self.part = Some(part);
+                    self.state = State::Body;
+                }
+                State::Body => match memmem::find(&self.buffer, &self.delimiter) {
+                    Some(at) => {
+                        let data: Vec<u8> =
+                            self.buffer.drain(..at + self.delimiter.len()).collect();
+                        self.write(&data[..at])?;
+                        self.finish_part(py)?;
+                        self.state = State::Boundary;
+                    }
+                    None => {
+                        // Hold back what could be the start of a split delimiter.
+                        let safe = self.buffer.len().saturating_sub(self.delimiter.len() - 1);
+                        if safe > 0 {
+                            let data: Vec<u8> = self.buffer.drain(..safe).collect();
+                            self.write(&data)?;
+                        }
+                        return Ok(());
+                    }
+                },
+                State::Done => return Ok(()),
+            }
+        }
+    }
+
+    fn write(&mut self, data: &[u8]) -> PyResult<()> {
+        let part = self.part.as_mut().expect("body without a part");
+        part.size += data.len();
+        if let Some(limit) = self.max_part_size {
+            if part.size > limit {
+                return Err(MultipartLimitError::new_err(format!(
+                    "multipart part '{}' exceeds {limit} bytes",
+                    part.name
+                )));
+            }
+        }
+        if part.filename.is_none() && part.size > self.max_field_size {
+            return Err(MultipartLimitError::new_err(format!(
+                "multipart field '{}' exceeds {} bytes",
+                part.name, self.max_field_size
+            )));
+        }
+        if part.filename.is_some() && part.size > self.spool_size {
+            part.data
+                .spill()
+                .map_err(|e| PyIOError::new_err(e.to_string()))?;
+        }
+        part.data
+            .write_all(data)
+            .map_err(|e| PyIOError::new_err(e.to_string()))
+    }
+
+    fn finish_part(&mut self, py: Python<'_>) -> PyResult<()> {
+        let Part {
+            name,
+            filename,
+            content_type,
+            headers,
+            size,
+            mut data,
+        } = self
+            .part
+            .take()
+            .expect("finished a part that never started");
+        let value = match filename {
+            Some(filename) => {
+                data.rewind()
+                    .map_err(|e| PyIOError::new_err(e.to_string()))?;
+                Py::new(
+                    py,
+                    UploadFile {
+                        filename,
+                        content_type,
+                        size,
+                        headers,
+                        storage: Some(data),
+                    },
+                )?
+                .into_any()
+            }
+            None => {
+                let Storage::Memory(cursor) = &data else {
+                    unreachable!("plain fields are never spilled")
+                };
+                PyString::new(py, &String::from_utf8_lossy(cursor.get_ref()))
+                    .into_any()
+                    .unbind()
+            }
+        };
+        self.fields.push((name, value));
+        Ok(())
+    }
+}
+
+fn parse_part_headers(block: &[u8]) -> PyResult<Part> {
+    let block = String::from_utf8_lossy(block);
+    let mut headers = Vec::new();
+    for line in block.split("\r\n") {
+        let (name, value) = line
+            .split_once(':')
+            .ok_or_else(|| MultipartError::new_err(format!("malformed part header '{line}'")))?;
+        headers.push((name.trim().to_ascii_lowercase(), value.trim().to_owned()));
+    }
+
+    let header = |wanted: &str| {
+        headers
+            .iter()
+            .find(|(name, _)| name == wanted)
+            .map(|(_, value)| value.as_str())
+    };
+    let disposition = header("content-disposition")
+        .ok_or_else(|| MultipartError::new_err("part without Content-Disposition"))?;
+    let name = header_param(disposition, "name")
+        .ok_or_else(|| MultipartError::new_err("part without a name"))?;
+    let filename = header_param(disposition, "filename");
+    let content_type = header("content-type").map(str::to_owned);
+
+    Ok(Part {
+        name,
+        filename,
+        content_type,
+        headers,
+        size: 0,
+        data: Storage::Memory(Cursor::new(Vec::new())),
+    })
+}
+
+/// Extract the boundary parameter from a `multipart/form-data` content type.
+fn boundary(content_type: &str) -> Option<String> {
+    let (media_type, _) = content_type.split_once(';')?;
+    if !media_type
+        .trim()
+        .eq_ignore_ascii_case("multipart/form-data")
+    {
+        return None;
+    }
+    header_param(content_type, "boundary").filter(|b| !b.is_empty() && b.len() <= 70)
+}
+
+/// Value of a `; key=value` or `; key="value"` header parameter.
+fn header_param(header: &str, key: &str) -> Option<String> {
+    let mut rest = header.split_once(';')?.1;
+    loop {
+        rest = rest.trim_start();
+        let eq = rest.find('=')?;
+        let name = rest[..eq].trim();
+        let after = &rest[eq + 1..];
+        let (value, tail) = match after.strip_prefix('"') {
+            Some(quoted) => {
+                let mut value = String::new();
+                let mut chars = quoted.char_indices();
+                let mut end = quoted.len();
+                while let Some((i, c)) = chars.next() {
+                    match c {
+                        '\\' => {
+                            if let Some((_, escaped)) = chars.next() {
+                                value.push(escaped);
+                            }
+                        }
+                        '"' => {
+                            end = i + 1;
+                            break;
+                        }
+                        _ => value.push(c),
+                    }
+                }
+                (value, &quoted[end..])
+            }
+            None => {
+                let end = after.find(';').unwrap_or(after.len());
+                (after[..end].trim().to_owned(), &after[end..])
+            }
+        };
+        if name.eq_ignore_ascii_case(key) {
+            return Some(value);
+        }
+        rest = tail.split_once(';')?.1;
+    }
+}
@@ -0,0 +1,409 @@
+use std::collections::HashMap;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use parking_lot::{Mutex, RwLock};
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+use pyo3::types::{PyList, PyString};
+use serde_json::Value;
+use sha2::{Digest, Sha256, Sha384, Sha512};
+use subtle::ConstantTimeEq;
+
+use crate::crypto::random_bytes;
+use crate::json::value_to_py;
+use crate::jwt::{
+    load_jwks, split_token, validate_claims, verify_token, Expectations, JwtError, JwtKey,
+};
+
+/// Algorithms `IdTokenVerifier` accepts unless told otherwise: every
+/// asymmetric one, since a JWKS publishes public keys.
+const DEFAULT_ALGORITHMS: &[&str] = &[
+    "RS256", "RS384", "RS512", "PS256", "PS384", "PS512", "ES256", "ES384", "EdDSA",
+];
+
+/// Claims every ID token must carry (OpenID Connect Core 1.0, section 2).
+const REQUIRED_CLAIMS: &[&str] = &["iss", "sub", "aud", "exp", "iat"];
+
+fn now() -> f64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0.0, |elapsed| elapsed.as_secs_f64())
+}
+
+#[derive(Default)]
+struct KeySet {
+    by_kid: HashMap<String, Py<JwtKey>>,
+    /// Keys published without a `kid`.
+    unkeyed: Vec<Py<JwtKey>>,
+}
+
+impl KeySet {
+    fn load(document: &Bound<'_, PyAny>) -> PyResult<Self> {
+        let py = document.py();
+        let mut set = Self::default();
+        for key in load_jwks(document)? {
+            let key = Py::new(py, key)?;
+            match key.get().kid() {
+                Some(kid) => {
+                    set.by_kid.insert(kid.to_owned(), key);
+                }
+                None => set.unkeyed.push(key),
+            }
+        }
+        Ok(set)
+    }
+
+    fn all<'py>(&self, py: Python<'py>) -> Vec<Bound<'py, JwtKey>> {
+        self.by_kid
+            .values()
+            .chain(&self.unkeyed)
+            .map(|key| key.bind(py).clone())
+            .collect()
+    }
+}
+
+/// Signature keys of an identity provider, cached by `kid`.
+///
+/// Built from a JWKS document (dict or JSON text) or file. With a `loader`,
+/// a callable returning a fresh JWKS document, a token signed with an
+/// unknown `kid` triggers a reload so provider key rotations are picked up;
+/// reloads happen at most once every `min_refresh_interval` seconds.
+#[pyclass(frozen, module = "haske")]
+pub struct Jwks {
+    keys: RwLock<KeySet>,
+    loader: Option<Py<PyAny>>,
+    min_refresh_interval: f64,
+    /// When the loader last ran.
+    refreshed_at: Mutex<f64>,
+}
+
+impl Jwks {
+    /// Call the loader unless it ran within `min_refresh_interval`.
+    fn refresh_if_due(&self, py: Python<'_>) -> PyResult<bool> {
+        let Some(loader) = &self.loader else {
+            return Ok(false);
+        };
+        {
+            let mut refreshed_at = self.refreshed_at.lock();
+            if now() - *refreshed_at < self.min_refresh_interval {
+                return Ok(false);
+            }
+            *refreshed_at = now();
+        }
+        let keys = KeySet::load(&loader.bind(py).call0()?)?;
+        *self.keys.write() = keys;
+        Ok(true)
+    }
+
+    /// Keys that may have signed a token with header `kid`.
+    pub(crate) fn candidates<'py>(
+        &self,
+        py: Python<'py>,
+        kid: Option<&str>,
+    ) -> PyResult<Bound<'py, PyList>> {
+        let Some(kid) = kid else {
+            return PyList::new(py, self.keys.read().all(py));
+        };
+        if let Some(key) = self.get(py, kid)? {
+            return PyList::new(py, [key]);
+        }
+        PyList::new(py, self.keys.read().unkeyed.iter().map(|key| key.bind(py)))
+    }
+}
+
+#[pymethods]
+impl Jwks {
+    #[new]
+    #[pyo3(signature = (document=None, *, loader=None, min_refresh_interval=60.0))]
+    fn new(
+        py: Python<'_>,
+        document: Option<&Bound<'_, PyAny>>,
+        loader: Option<Py<PyAny>>,
+        min_refresh_interval: f64,
+    ) -> PyResult<Self> {
+        let (keys, refreshed_at) = match (document, &loader) {
+            (Some(document), _) => (KeySet::load(document)?, 0.0),
+            (None, Some(loader)) => (KeySet::load(&loader.bind(py).call0()?)?, now()),
+            (None, None) => {
+                return Err(PyValueError::new_err(
+                    "Jwks needs a document, a loader or both",
+                ))
+            }
+        };
+        Ok(Self {
+            keys: RwLock::new(keys),
+            loader,
+            min_refresh_interval,
+            refreshed_at: Mutex::new(refreshed_at),
+        })
+    }
+
+    #[staticmethod]
+    #[pyo3(signature = (path, *, loader=None, min_refresh_interval=60.0))]
+    fn from_file(
+        py: Python<'_>,
+        path: &str,
+        loader: Option<Py<PyAny>>,
+        min_refresh_interval: f64,
+    ) -> PyResult<Self> {
+        let text = std::fs::read_to_string(path)?;
+        Self::new(
+            py,
+            Some(PyString::new(py, &text).as_any()),
+            loader,
+            min_refresh_interval,
+        )
+    }
+
+    /// The key with id `kid`, reloading the document once if it is unknown.
+    fn get(&self, py: Python<'_>, kid: &str) -> PyResult<Option<Py<JwtKey>>> {
+        if let Some(key) = self.keys.read().by_kid.get(kid) {
+            return Ok(Some(key.clone_ref(py)));
+        }
+        if !self.refresh_if_due(py)? {
+            return Ok(None);
+        }
+        Ok(self
+            .keys
+            .read()
+            .by_kid
+            .get(kid)
+            .map(|key| key.clone_ref(py)))
+    }
+
+    /// Replace the keys with those of `document`.
+    fn update(&self, document: &Bound<'_, PyAny>) -> PyResult<()> {
+        *self.keys.write() = KeySet::load(document)?;
+        Ok(())
+    }
+
+    /// Reload the keys from the loader now, returning whether it ran.
+    fn refresh(&self, py: Python<'_>) -> PyResult<bool> {
+        *self.refreshed_at.lock() = f64::NEG_INFINITY;
+        self.refresh_if_due(py)
+    }
+
+    /// Ids of the cached keys.
+    #[getter]
+    fn kids(&self) -> Vec<String> {
+        let mut kids: Vec<String> = self.keys.read().by_kid.keys().cloned().collect();
+        kids.sort();
+        kids
+    }
+
+    fn __len__(&self) -> usize {
+        let keys = self.keys.read();
+        keys.by_kid.len() + keys.unkeyed.len()
+    }
+
+    fn __repr__(&self) -> String {
+        let kids: Vec<String> = self.kids().iter().map(|kid| format!("'{kid}'")).collect();
+        format!("Jwks(kids=[{}])", kids.join(", "))
+    }
+}
+
+/// `at_hash`/`c_hash` of `value`: the left half of its digest under the
+/// hash of the token's algorithm, base64url-encoded.
+fn left_half_hash(algorithm: &str, value: &str) -> Option<String> {
+    let digest = match algorithm {
+        "HS256" | "RS256" | "PS256" | "ES256" => Sha256::digest(value).to_vec(),
+        "HS384" | "RS384" | "PS384" | "ES384" => Sha384::digest(value).to_vec(),
+        // Ed25519 hashes with SHA-512.
+        "HS512" | "RS512" | "PS512" | "EdDSA" => Sha512::digest(value).to_vec(),
+        _ => return None,
+    };
+    Some(URL_SAFE_NO_PAD.encode(&digest[..digest.len() / 2]))
+}
+
+/// Validates OpenID Connect ID tokens from one provider for one client.
+///
+/// `verify` checks the signature against the provider's `Jwks`, then `iss`,
+/// `aud` (which must contain `client_id`, with `azp` naming the client when
+/// there are several audiences), `exp` and `iat`, and optionally the
+/// `nonce`, `at_hash` and `auth_time` claims.
+#[pyclass(frozen, module = "haske")]
+pub struct IdTokenVerifier {
+    jwks: Py<Jwks>,
+    issuer: String,
+    client_id: String,
+    algorithms: Vec<String>,
+    leeway: f64,
+}
+
+#[pymethods]
+impl IdTokenVerifier {
+    #[new]
+    #[pyo3(signature = (jwks, issuer, client_id, *, algorithms=None, leeway=0.0))]
+    fn new(
+        jwks: Py<Jwks>,
+        issuer: String,
+        client_id: String,
+        algorithms: Option<Vec<String>>,
+        leeway: f64,
+    ) -> Self {
+        Self {
+            jwks,
+            issuer,
+            client_id,
+            algorithms: algorithms
+                .unwrap_or_else(|| DEFAULT_ALGORITHMS.iter().map(|&a| a.to_owned()).collect()),
+            leeway,
+        }
+    }
+
+    #[getter]
+    fn issuer(&self) -> &str {
+        &self.issuer
+    }
+
+    #[getter]
+    fn client_id(&self) -> &str {
+        &self.client_id
+    }
+
+    /// Verify `id_token` and return its claims, raising `JwtError` if it is
+    /// invalid.
+    ///
+    /// `nonce` must equal the token's `nonce` claim when given. With
+    /// `access_token`, a token carrying `at_hash` must match it. With
+    /// `max_age` (seconds), `auth_time` must be present and recent enough.
+    #[pyo3(signature = (id_token, *, nonce=None, access_token=None, max_age=None))]
+    fn verify<'py>(
+        &self,
+        py: Python<'py>,
+        id_token: &str,
+        nonce: Option<&str>,
+        access_token: Option<&str>,
+        max_age: Option<f64>,
+    ) -> PyResult<Bound<'py, PyAny>> {
+        let (header, _, _) = split_token(id_token)?;
+        let kid = header.get("kid").and_then(Value::as_str);
+        let keys = self.jwks.get().candidates(py, kid)?;
+        let (header, claims) =
+            verify_token(id_token, keys.as_any(), Some(self.algorithms.as_slice()))?;
+
+        let issuer = [self.issuer.clone()];
+        let audience = [self.client_id.clone()];
+        let require: Vec<String> = REQUIRED_CLAIMS.iter().map(|&c| c.to_owned()).collect();
+        validate_claims(
+            &claims,
+            &Expectations {
+                audience: Some(&audience),
+                issuer: Some(&issuer),
+                leeway: self.leeway,
+                require: &require,
+            },
+        )?;
+
+        let multiple_audiences = claims
+            .get("aud")
+            .and_then(Value::as_array)
+            .is_some_and(|auds| auds.len() > 1);
+        match claims.get("azp").and_then(Value::as_str) {
+            Some(azp) if azp != self.client_id => {
+                return Err(JwtError::new_err("token 'azp' does not name this client"));
+            }
+            None if multiple_audiences => {
+                return Err(JwtError::new_err(
+                    "token has several audiences but no 'azp' claim",
+                ));
+            }
+            _ => {}
+        }
+
+        if let Some(nonce) = nonce {
+            match claims.get("nonce").and_then(Value::as_str) {
+                Some(claimed) if claimed == nonce => {}
+                Some(_) => return Err(JwtError::new_err("token nonce does not match")),
+                None => return Err(JwtError::new_err("token is missing the 'nonce' claim")),
+            }
+        }
+
+        if let (Some(access_token), Some(at_hash)) = (access_token, claims.get("at_hash")) {
+            let algorithm = header.get("alg").and_then(Value::as_str).unwrap_or("");
+            let expected = left_half_hash(algorithm, access_token);
+            if expected.is_none() || at_hash.as_str() != expected.as_deref() {
+                return Err(JwtError::new_err(
+                    "token 'at_hash' does not match the access token",
+                ));
+            }
+        }
+
+        if let Some(max_age) = max_age {
+            let auth_time = claims
+                .get("auth_time")
+                .and_then(Value::as_f64)
+                .ok_or_else(|| JwtError::new_err("token is missing the 'auth_time' claim"))?;
+            if now() > auth_time + max_age + self.leeway {
+                return Err(JwtError::new_err("authentication is older than max_age"));
+            }
+        }
+
+        value_to_py(py, &Value::Object(claims))
+    }
+
+    fn __repr__(&self) -> String {
+        format!(
+            "IdTokenVerifier(issuer='{}', client_id='{}')",
+            self.issuer, self.client_id
+        )
+    }
+}
+
+/// Whether `verifier` is a valid PKCE code verifier: 43 to 128 characters
+/// from `A-Z a-z 0-9 - . _ ~` (RFC 7636, section 4.1).
+fn is_pkce_verifier(verifier: &str) -> bool {
+    (43..=128).contains(&verifier.len())
+        && verifier
+            .bytes()
+            .all(|b| b.is_ascii_alphanumeric() || matches!(b, b'-' | b'.' | b'_' | b'~'))
+}
+
+fn challenge_for(verifier: &str, method: &str) -> PyResult<String> {
+    match method {
+        "S256" => Ok(URL_SAFE_NO_PAD.encode(Sha256::digest(verifier))),
+        "plain" => Ok(verifier.to_owned()),
+        _ => Err(PyValueError::new_err(format!(
+            "unsupported PKCE method '{method}'; expected 'S256' or 'plain'"
+        ))),
+    }
+}
+
+/// A random PKCE code verifier of `length` characters (43 to 128).
+#[pyfunction]
+#[pyo3(signature = (length=64))]
+pub fn generate_pkce_verifier(length: usize) -> PyResult<String> {
+    if !(43..=128).contains(&length) {
+        return Err(PyValueError::new_err(
+            "PKCE verifiers must be 43 to 128 characters long",
+        ));
+    }
+    let mut verifier = URL_SAFE_NO_PAD.encode(random_bytes(length.div_ceil(4) * 3));
+    verifier.truncate(length);
+    Ok(verifier)
+}
+
+/// The code challenge to send with the authorization request for
+/// `verifier`; `method` is `S256` (recommended) or `plain`.
+#[pyfunction]
+#[pyo3(signature = (verifier, method="S256"))]
+pub fn pkce_challenge(verifier: &str, method: &str) -> PyResult<String> {
+    if !is_pkce_verifier(verifier) {
+        return Err(PyValueError::new_err("invalid PKCE code verifier"));
+    }
+    challenge_for(verifier, method)
+}
+
+/// Whether `verifier` matches `challenge`, for servers checking the token
+/// request of an authorization-code flow. Compared in constant time.
+#[pyfunction]
+#[pyo3(signature = (verifier, challenge, method="S256"))]
+pub fn verify_pkce(verifier: &str, challenge: &str, method: &str) -> PyResult<bool> {
+    if !is_pkce_verifier(verifier) {
+        return Ok(false);
+    }
+    let expected = challenge_for(verifier, method)?;
+    Ok(expected.as_bytes().ct_eq(challenge.as_bytes()).into())
+}
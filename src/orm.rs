@@ -0,0 +1,341 @@
+use std::collections::HashMap;
+use std::sync::LazyLock;
+
+use parking_lot::Mutex;
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+use pyo3::types::{PyBool, PyDict, PyFloat, PyInt, PyList, PyString};
+
+/// Keywords that make a statement unsafe for `validate_query_syntax`.
+const WRITE_KEYWORDS: &[&str] = &[
+    "ALTER", "CREATE", "DELETE", "DROP", "EXEC", "EXECUTE", "GRANT", "INSERT", "MERGE", "REVOKE",
+    "TRUNCATE", "UPDATE",
+];
+
+/// Prepared statements keyed by the SQL (or name) they were cached under.
+static STATEMENT_CACHE: LazyLock<Mutex<HashMap<String, String>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+
+/// Idle connection objects handed back by `return_connection_to_pool`.
+static CONNECTION_POOL: LazyLock<Mutex<Vec<Py<PyAny>>>> = LazyLock::new(|| Mutex::new(Vec::new()));
+
+/// Rewrite `:name` placeholders to positional `$n` markers.
+///
+/// Returns the rewritten SQL and the parameter values in placeholder order;
+/// names missing from `params` bind as `None`.
+#[pyfunction]
+pub fn prepare_query<'py>(
+    sql: &str,
+    params: &Bound<'py, PyDict>,
+) -> PyResult<(String, Bound<'py, PyList>)> {
+    let py = params.py();
+    let positional = PyList::empty(py);
+    let mut out = String::with_capacity(sql.len());
+
+    for token in tokenize(sql) {
+        match token {
+            Token::Text(text) => out.push_str(text),
+            Token::Param(name) => {
+                positional.append(
+                    params
+                        .get_item(name)?
+                        .unwrap_or_else(|| py.None().into_bound(py)),
+                )?;
+                out.push('$');
+                out.push_str(&positional.len().to_string());
+            }
+        }
+    }
+    Ok((out, positional))
+}
+
+/// Batch form of `prepare_query`; queries and parameter dicts are paired up in order.
+#[pyfunction]
+pub fn prepare_queries<'py>(
+    queries: Vec<String>,
+    params: Vec<Bound<'py, PyDict>>,
+) -> PyResult<Vec<(String, Bound<'py, PyList>)>> {
+    queries
+        .iter()
+        .zip(params.iter())
+        .map(|(sql, p)| prepare_query(sql, p))
+        .collect()
+}
+
+#[pyfunction]
+#[pyo3(signature = (table, columns, where_clauses, order_by=None, limit=None, offset=None))]
+pub fn build_select_query(
+    table: &str,
+    columns: Vec<String>,
+    where_clauses: Vec<String>,
+    order_by: Option<&str>,
+    limit: Option<u64>,
+    offset: Option<u64>,
+) -> String {
+    let columns = if columns.is_empty() {
+        "*".to_owned()
+    } else {
+        columns.join(", ")
+    };
+    let mut sql = format!("SELECT {columns} FROM {table}");
+    push_where(&mut sql, &where_clauses);
+    if let Some(order_by) = order_by.filter(|o| !o.is_empty()) {
+        sql.push_str(" ORDER BY ");
+        sql.push_str(order_by);
+    }
+    if let Some(limit) = limit {
+        sql.push_str(&format!(" LIMIT {limit}"));
+    }
+    if let Some(offset) = offset {
+        sql.push_str(&format!(" OFFSET {offset}"));
+    }
+    sql
+}
+
+#[pyfunction]
+pub fn build_update_query(
+    table: &str,
+    set_clauses: Vec<String>,
+    where_clauses: Vec<String>,
+) -> PyResult<String> {
+    if set_clauses.is_empty() {
+        return Err(PyValueError::new_err("No set clauses provided"));
+    }
+    let mut sql = format!("UPDATE {table} SET {}", set_clauses.join(", "));
+    push_where(&mut sql, &where_clauses);
+    Ok(sql)
+}
+
+#[pyfunction]
+pub fn build_delete_query(table: &str, where_clauses: Vec<String>) -> String {
+    let mut sql = format!("DELETE FROM {table}");
+    push_where(&mut sql, &where_clauses);
+    sql
+}
+
+/// Build a multi-row `INSERT` with `$n` placeholders; values are bound separately.
+#[pyfunction]
+pub fn batch_insert(
+    table: &str,
+    columns: Vec<String>,
+    values: Vec<Vec<Bound<'_, PyAny>>>,
+) -> PyResult<String> {
+    if columns.is_empty() {
+        return Err(PyValueError::new_err("No columns provided"));
+    }
+    if values.is_empty() {
+        return Err(PyValueError::new_err("No values provided"));
+    }
+
+    let mut rows = Vec::with_capacity(values.len());
+    let mut n = 0;
+    for (i, row) in values.iter().enumerate() {
+        if row.len() != columns.len() {
+            return Err(PyValueError::new_err(format!(
+                "Row {} has {} values, expected {}",
+                i + 1,
+                row.len(),
+                columns.len()
+            )));
+        }
+        let placeholders: Vec<String> = (0..row.len())
+            .map(|_| {
+                n += 1;
+                format!("${n}")
+            })
+            .collect();
+        rows.push(format!("({})", placeholders.join(", ")));
+    }
+    Ok(format!(
+        "INSERT INTO {table} ({}) VALUES {}",
+        columns.join(", "),
+        rows.join(", ")
+    ))
+}
+
+/// Zip each row with `column_names` into a dict; extra values are dropped.
+#[pyfunction]
+pub fn process_result_set<'py>(
+    py: Python<'py>,
+    results: Vec<Vec<Bound<'py, PyAny>>>,
+    column_names: Vec<String>,
+) -> PyResult<Vec<Bound<'py, PyDict>>> {
+    results
+        .into_iter()
+        .map(|row| {
+            let dict = PyDict::new(py);
+            for (name, value) in column_names.iter().zip(row) {
+                dict.set_item(name, value)?;
+            }
+            Ok(dict)
+        })
+        .collect()
+}
+
+/// Convert numeric and boolean strings to `int`, `float` or `bool`.
+///
+/// Non-string values and strings that do not parse are returned unchanged.
+#[pyfunction]
+pub fn optimize_type_conversion<'py>(
+    py: Python<'py>,
+    values: Vec<Bound<'py, PyAny>>,
+) -> PyResult<Vec<Bound<'py, PyAny>>> {
+    values
+        .into_iter()
+        .map(|value| {
+            let Ok(s) = value.downcast::<PyString>() else {
+                return Ok(value);
+            };
+            let text = s.to_cow()?;
+            Ok(if text.eq_ignore_ascii_case("true") {
+                PyBool::new(py, true).to_owned().into_any()
+            } else if text.eq_ignore_ascii_case("false") {
+                PyBool::new(py, false).to_owned().into_any()
+            } else if let Ok(i) = text.parse::<i64>() {
+                PyInt::new(py, i).into_any()
+            } else if let Some(f) = text.parse::<f64>().ok().filter(|f| f.is_finite()) {
+                PyFloat::new(py, f).into_any()
+            } else {
+                value
+            })
+        })
+        .collect()
+}
+
+/// Check that `sql` is a single read-only `SELECT`/`WITH` statement.
+///
+/// Rejects statement separators, comments and any data-modifying keyword
+/// outside of quoted literals.
+#[pyfunction]
+pub fn validate_query_syntax(sql: &str) -> bool {
+    let sql = sql.trim();
+    let Some(first) = words(sql).next() else {
+        return false;
+    };
+    if !first.eq_ignore_ascii_case("SELECT") && !first.eq_ignore_ascii_case("WITH") {
+        return false;
+    }
+    let unquoted = strip_literals(sql);
+    if [";", "--", "/*", "*/"].iter().any(|t| unquoted.contains(t)) {
+        return false;
+    }
+    let writes = words(&unquoted).any(|w| WRITE_KEYWORDS.iter().any(|k| w.eq_ignore_ascii_case(k)));
+    !writes
+}
+
+/// Remember `statement` under the key `sql`.
+#[pyfunction]
+pub fn cache_prepared_statement(statement: String, sql: String) {
+    STATEMENT_CACHE.lock().insert(sql, statement);
+}
+
+#[pyfunction]
+pub fn get_cached_statement(sql: &str) -> Option<String> {
+    STATEMENT_CACHE.lock().get(sql).cloned()
+}
+
+/// Drop every cached statement, returning how many were removed.
+#[pyfunction]
+pub fn clear_statement_cache() -> usize {
+    let mut cache = STATEMENT_CACHE.lock();
+    let count = cache.len();
+    cache.clear();
+    count
+}
+
+/// Take an idle connection previously returned to the pool, if any.
+#[pyfunction]
+pub fn get_connection_from_pool() -> Option<Py<PyAny>> {
+    CONNECTION_POOL.lock().pop()
+}
+
+#[pyfunction]
+pub fn return_connection_to_pool(conn: Option<Py<PyAny>>) {
+    if let Some(conn) = conn {
+        CONNECTION_POOL.lock().push(conn);
+    }
+}
+
+fn push_where(sql: &mut String, where_clauses: &[String]) {
+    if !where_clauses.is_empty() {
+        sql.push_str(" WHERE ");
+        sql.push_str(&where_clauses.join(" AND "));
+    }
+}
+
+fn words(sql: &str) -> impl Iterator<Item = &str> {
+    sql.split(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+        .filter(|w| !w.is_empty())
+}
+
+/// Blank out the contents of quoted literals and identifiers.
+fn strip_literals(sql: &str) -> String {
+    let mut out = String::with_capacity(sql.len());
+    let mut quote = None;
+    for c in sql.chars() {
+        match quote {
+            Some(q) if c == q => {
+                quote = None;
+                out.push(c);
+            }
+            Some(_) => out.push(' '),
+            None => {
+                if c == '\'' || c == '"' || c == '`' {
+                    quote = Some(c);
+                }
+                out.push(c);
+            }
+        }
+    }
+    out
+}
+
+enum Token<'a> {
+    Text(&'a str),
+    Param(&'a str),
+}
+
+/// Split SQL into literal text and `:name` placeholders, leaving quoted
+/// strings, identifiers and `::` casts untouched.
+fn tokenize(sql: &str) -> Vec<Token<'_>> {
+    let bytes = sql.as_bytes();
+    let mut tokens = Vec::new();
+    let mut start = 0;
+    let mut i = 0;
+
+    while i < bytes.len() {
+        match bytes[i] {
+            quote @ (b'\'' | b'"') => {
+                i += 1;
+                while i < bytes.len() && bytes[i] != quote {
+                    i += 1;
+                }
+                i += 1;
+            }
+            b':' if bytes.get(i + 1) == Some(&b':') => i += 2,
+            b':' if bytes
+                .get(i + 1)
+                .is_some_and(|b| b.is_ascii_alphabetic() || *b == b'_') =>
+            {
+                let name_start = i + 1;
+                let mut end = name_start;
+                while end < bytes.len()
+                    && (bytes[end].is_ascii_alphanumeric() || bytes[end] == b'_')
+                {
+                    end += 1;
+                }
+                if start < i {
+                    tokens.push(Token::Text(&sql[start..i]));
+                }
+                tokens.push(Token::Param(&sql[name_start..end]));
+                start = end;
+                i = end;
+            }
+            _ => i += 1,
+        }
+    }
+    if start < sql.len() {
+        tokens.push(Token::Text(&sql[start..]));
+    }
+    tokens
+}
@@ -0,0 +1,388 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use data_encoding::BASE32_NOPAD;
+use hmac::Hmac;
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+use pyo3::types::PyBytes;
+use sha1::Sha1;
+use sha2::{Sha256, Sha512};
+use subtle::ConstantTimeEq;
+
+use crate::crypto::{hmac_sign, random_bytes};
+use crate::password::{hash_bytes, verify_bytes, PasswordPolicy};
+
+/// Characters in a recovery code, shown as two groups of five.
+const RECOVERY_CODE_LEN: usize = 10;
+
+#[derive(Clone, Copy)]
+enum OtpAlgorithm {
+    Sha1,
+    Sha256,
+    Sha512,
+}
+
+impl OtpAlgorithm {
+    fn from_name(name: &str) -> PyResult<Self> {
+        match name.to_ascii_uppercase().as_str() {
+            "SHA1" => Ok(Self::Sha1),
+            "SHA256" => Ok(Self::Sha256),
+            "SHA512" => Ok(Self::Sha512),
+            _ => Err(PyValueError::new_err(format!(
+                "unsupported OTP algorithm {name:?}; expected SHA1, SHA256 or SHA512"
+            ))),
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            Self::Sha1 => "SHA1",
+            Self::Sha256 => "SHA256",
+            Self::Sha512 => "SHA512",
+        }
+    }
+}
+
+/// Secret, digit count and HMAC algorithm shared by TOTP and HOTP.
+struct Generator {
+    secret: Vec<u8>,
+    digits: u32,
+    algorithm: OtpAlgorithm,
+}
+
+impl Generator {
+    fn new(secret: &Bound<'_, PyAny>, digits: u32, algorithm: &str) -> PyResult<Self> {
+        if !(6..=10).contains(&digits) {
+            return Err(PyValueError::new_err("digits must be between 6 and 10"));
+        }
+        let secret = match secret.downcast::<PyBytes>() {
+            Ok(bytes) => bytes.as_bytes().to_vec(),
+            Err(_) => decode_secret(&secret.extract::<String>()?)?,
+        };
+        if secret.is_empty() {
+            return Err(PyValueError::new_err("OTP secret must not be empty"));
+        }
+        Ok(Self {
+            secret,
+            digits,
+            algorithm: OtpAlgorithm::from_name(algorithm)?,
+        })
+    }
+
+    /// RFC 4226 section 5.3: dynamic truncation of the HMAC of `counter`.
+    fn code(&self, counter: u64) -> String {
+        let message = counter.to_be_bytes();
+        let mac = match self.algorithm {
+            OtpAlgorithm::Sha1 => hmac_sign::<Hmac<Sha1>>(&self.secret, &message),
+            OtpAlgorithm::Sha256 => hmac_sign::<Hmac<Sha256>>(&self.secret, &message),
+            OtpAlgorithm::Sha512 => hmac_sign::<Hmac<Sha512>>(&self.secret, &message),
+        };
+        let offset = (mac[mac.len() - 1] & 0x0f) as usize;
+        let binary = u32::from_be_bytes([
+            mac[offset] & 0x7f,
+            mac[offset + 1],
+            mac[offset + 2],
+            mac[offset + 3],
+        ]);
+        let code = u64::from(binary) % 10u64.pow(self.digits);
+        format!("{code:0width$}", width = self.digits as usize)
+    }
+
+    fn matches(&self, counter: u64, code: &str) -> bool {
+        self.code(counter).as_bytes().ct_eq(code.as_bytes()).into()
+    }
+
+    /// `otpauth://` URI for authenticator apps (Key Uri Format).
+    fn uri(&self, kind: &str, account: &str, issuer: Option<&str>, extra: &str) -> String {
+        let label = match issuer {
+            Some(issuer) => format!("{}:{}", uri_encode(issuer), uri_encode(account)),
+            None => uri_encode(account),
+        };
+        let mut uri = format!(
+            "otpauth://{kind}/{label}?secret={}&algorithm={}&digits={}{extra}",
+            BASE32_NOPAD.encode(&self.secret),
+            self.algorithm.name(),
+            self.digits
+        );
+        if let Some(issuer) = issuer {
+            uri.push_str("&issuer=");
+            uri.push_str(&uri_encode(issuer));
+        }
+        uri
+    }
+}
+
+/// Time-based one-time passwords (RFC 6238), as used by authenticator apps.
+///
+/// `secret` is base32 text (spaces and case ignored) or raw `bytes`.
+/// Verification accepts codes up to `drift` periods before or after the
+/// current one to absorb clock skew.
+#[pyclass(frozen, module = "haske")]
+pub struct Totp {
+    generator: Generator,
+    period: u64,
+    drift: u64,
+}
+
+impl Totp {
+    fn step_at(&self, timestamp: Option<f64>) -> PyResult<u64> {
+        let timestamp = match timestamp {
+            Some(timestamp) => timestamp,
+            None => SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map_or(0.0, |elapsed| elapsed.as_secs_f64()),
+        };
+        if !timestamp.is_finite() || timestamp < 0.0 {
+            return Err(PyValueError::new_err(
+                "timestamp must be a non-negative number",
+            ));
+        }
+        Ok(timestamp as u64 / self.period)
+    }
+}
+
+#[pymethods]
+impl Totp {
+    #[new]
+    #[pyo3(signature = (secret, *, digits=6, period=30, algorithm="SHA1", drift=1))]
+    fn new(
+        secret: &Bound<'_, PyAny>,
+        digits: u32,
+        period: u64,
+        algorithm: &str,
+        drift: u64,
+    ) -> PyResult<Self> {
+        if period == 0 {
+            return Err(PyValueError::new_err("period must be positive"));
+        }
+        Ok(Self {
+            generator: Generator::new(secret, digits, algorithm)?,
+            period,
+            drift,
+        })
+    }
+
+    /// Base32 form of the secret.
+    #[getter]
+    fn secret(&self) -> String {
+        BASE32_NOPAD.encode(&self.generator.secret)
+    }
+
+    #[getter]
+    fn digits(&self) -> u32 {
+        self.generator.digits
+    }
+
+    #[getter]
+    fn period(&self) -> u64 {
+        self.period
+    }
+
+    /// Time step (counter) for `timestamp`, or for now.
+    #[pyo3(signature = (timestamp=None))]
+    fn step(&self, timestamp: Option<f64>) -> PyResult<u64> {
+        self.step_at(timestamp)
+    }
+
+    /// The code for the current period.
+    fn now(&self) -> PyResult<String> {
+        Ok(self.generator.code(self.step_at(None)?))
+    }
+
+    /// The code for the period containing `timestamp` (Unix seconds).
+    fn at(&self, timestamp: f64) -> PyResult<String> {
+        Ok(self.generator.code(self.step_at(Some(timestamp))?))
+    }
+
+    /// Check `code`, returning the time step it belongs to or `None`.
+    ///
+    /// Store the returned step and pass it as `after_step` next time so a
+    /// code cannot be used twice.
+    #[pyo3(signature = (code, *, timestamp=None, after_step=None))]
+    fn verify(
+        &self,
+        code: &str,
+        timestamp: Option<f64>,
+        after_step: Option<u64>,
+    ) -> PyResult<Option<u64>> {
+        let code = code.trim();
+        let current = self.step_at(timestamp)?;
+        let first = current.saturating_sub(self.drift);
+        let first = after_step.map_or(first, |used| first.max(used.saturating_add(1)));
+        Ok((first..=current.saturating_add(self.drift))
+            .find(|&step| self.generator.matches(step, code)))
+    }
+
+    /// `otpauth://totp/...` URI to show as a QR code when enrolling a user.
+    #[pyo3(signature = (account, issuer=None))]
+    fn provisioning_uri(&self, account: &str, issuer: Option<&str>) -> String {
+        let period = format!("&period={}", self.period);
+        self.generator.uri("totp", account, issuer, &period)
+    }
+
+    fn __repr__(&self) -> String {
+        format!(
+            "Totp(digits={}, period={}, algorithm='{}')",
+            self.generator.digits,
+            self.period,
+            self.generator.algorithm.name()
+        )
+    }
+}
+
+/// Counter-based one-time passwords (RFC 4226), as used by hardware tokens.
+///
+/// Verification looks up to `look_ahead` counters past the expected one, for
+/// codes the user generated without submitting.
+#[pyclass(frozen, module = "haske")]
+pub struct Hotp {
+    generator: Generator,
+    look_ahead: u64,
+}
+
+#[pymethods]
+impl Hotp {
+    #[new]
+    #[pyo3(signature = (secret, *, digits=6, algorithm="SHA1", look_ahead=0))]
+    fn new(
+        secret: &Bound<'_, PyAny>,
+        digits: u32,
+        algorithm: &str,
+        look_ahead: u64,
+    ) -> PyResult<Self> {
+        Ok(Self {
+            generator: Generator::new(secret, digits, algorithm)?,
+            look_ahead,
+        })
+    }
+
+    /// Base32 form of the secret.
+    #[getter]
+    fn secret(&self) -> String {
+        BASE32_NOPAD.encode(&self.generator.secret)
+    }
+
+    #[getter]
+    fn digits(&self) -> u32 {
+        self.generator.digits
+    }
+
+    /// The code for `counter`.
+    fn at(&self, counter: u64) -> String {
+        self.generator.code(counter)
+    }
+
+    /// Check `code` against `counter` and the following `look_ahead`
+    /// counters, returning the one that matched or `None`. The next
+    /// expected counter is the returned value plus one.
+    fn verify(&self, code: &str, counter: u64) -> Option<u64> {
+        let code = code.trim();
+        (counter..=counter.saturating_add(self.look_ahead))
+            .find(|&candidate| self.generator.matches(candidate, code))
+    }
+
+    /// `otpauth://hotp/...` URI to show as a QR code when enrolling a user.
+    #[pyo3(signature = (account, issuer=None, counter=0))]
+    fn provisioning_uri(&self, account: &str, issuer: Option<&str>, counter: u64) -> String {
+        let counter = format!("&counter={counter}");
+        self.generator.uri("hotp", account, issuer, &counter)
+    }
+
+    fn __repr__(&self) -> String {
+        format!(
+            "Hotp(digits={}, algorithm='{}')",
+            self.generator.digits,
+            self.generator.algorithm.name()
+        )
+    }
+}
+
+/// A random base32 secret of `length` bytes for a new `Totp`/`Hotp`.
+///
+/// 20 bytes matches the SHA1 block recommendation of RFC 4226.
+#[pyfunction]
+#[pyo3(signature = (length=20))]
+pub fn generate_otp_secret(length: usize) -> PyResult<String> {
+    if length < 16 {
+        return Err(PyValueError::new_err(
+            "OTP secrets must be at least 16 bytes",
+        ));
+    }
+    Ok(BASE32_NOPAD.encode(&random_bytes(length)))
+}
+
+/// `count` single-use recovery codes such as `k3jd9-x8f2q`.
+///
+/// Show them to the user once and store only `hash_recovery_code` of each.
+#[pyfunction]
+#[pyo3(signature = (count=10))]
+pub fn generate_recovery_codes(count: usize) -> Vec<String> {
+    (0..count)
+        .map(|_| {
+            let mut code = BASE32_NOPAD
+                .encode(&random_bytes(RECOVERY_CODE_LEN))
+                .to_ascii_lowercase();
+            code.truncate(RECOVERY_CODE_LEN);
+            code.insert(RECOVERY_CODE_LEN / 2, '-');
+            code
+        })
+        .collect()
+}
+
+/// Hash a recovery code for storage (Argon2id, as for passwords).
+#[pyfunction]
+pub fn hash_recovery_code(py: Python<'_>, code: &str) -> PyResult<String> {
+    let normalized = normalize_recovery_code(code);
+    py.detach(|| hash_bytes(normalized.as_bytes(), PasswordPolicy::default()))
+        .map_err(PyValueError::new_err)
+}
+
+/// Find `code` among stored recovery code hashes, returning the index of the
+/// match so the caller can delete it, or `None`.
+///
+/// Dashes, spaces and case are ignored.
+#[pyfunction]
+pub fn verify_recovery_code(
+    py: Python<'_>,
+    code: &str,
+    hashes: Vec<String>,
+) -> PyResult<Option<usize>> {
+    let normalized = normalize_recovery_code(code);
+    for (index, hash) in hashes.iter().enumerate() {
+        if verify_bytes(py, normalized.as_bytes(), hash)? {
+            return Ok(Some(index));
+        }
+    }
+    Ok(None)
+}
+
+fn decode_secret(text: &str) -> PyResult<Vec<u8>> {
+    let cleaned: String = text
+        .chars()
+        .filter(|c| !c.is_whitespace() && *c != '=' && *c != '-')
+        .map(|c| c.to_ascii_uppercase())
+        .collect();
+    BASE32_NOPAD
+        .decode(cleaned.as_bytes())
+        .map_err(|e| PyValueError::new_err(format!("invalid base32 OTP secret: {e}")))
+}
+
+fn normalize_recovery_code(code: &str) -> String {
+    code.chars()
+        .filter(|c| !c.is_whitespace() && *c != '-')
+        .map(|c| c.to_ascii_lowercase())
+        .collect()
+}
+
+/// Percent-encode everything but RFC 3986 unreserved characters.
+fn uri_encode(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for byte in text.bytes() {
+        if byte.is_ascii_alphanumeric() || b"-._~".contains(&byte) {
+            out.push(byte as char);
+        } else {
+            out.push_str(&format!("%{byte:02X}"));
+        }
+    }
+    out
+}
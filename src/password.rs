@@ -0,0 +1,283 @@
+use argon2::password_hash::{PasswordHash, PasswordHasher, PasswordVerifier, SaltString};
+use argon2::{Argon2, Params as Argon2Params, Version};
+use base64::engine::general_purpose::STANDARD_NO_PAD;
+use base64::Engine;
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+use scrypt::{Params as ScryptParams, Scrypt};
+
+use crate::crypto::{hmac_sha256, random_bytes, secret_bytes};
+
+const SALT_LEN: usize = 16;
+/// bcrypt only looks at the first 72 bytes of its input.
+const BCRYPT_MAX_INPUT: usize = 72;
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Policy {
+    Argon2id {
+        memory_cost: u32,
+        time_cost: u32,
+        parallelism: u32,
+    },
+    Scrypt {
+        log_n: u8,
+        r: u32,
+        p: u32,
+    },
+    Bcrypt {
+        cost: u32,
+    },
+}
+
+/// Algorithm and cost parameters for new password hashes.
+///
+/// Build one with `argon2id()`, `scrypt()` or `bcrypt()`; the defaults follow
+/// the OWASP password storage recommendations. `PasswordPolicy()` is the
+/// Argon2id default.
+#[pyclass(frozen, module = "haske")]
+#[derive(Clone, Copy)]
+pub struct PasswordPolicy {
+    policy: Policy,
+}
+
+impl Default for PasswordPolicy {
+    fn default() -> Self {
+        Self {
+            policy: Policy::Argon2id {
+                memory_cost: 19 * 1024,
+                time_cost: 2,
+                parallelism: 1,
+            },
+        }
+    }
+}
+
+#[pymethods]
+impl PasswordPolicy {
+    #[new]
+    fn new() -> Self {
+        Self::default()
+    }
+
+    /// Argon2id; `memory_cost` is in KiB.
+    #[staticmethod]
+    #[pyo3(signature = (memory_cost=19 * 1024, time_cost=2, parallelism=1))]
+    fn argon2id(memory_cost: u32, time_cost: u32, parallelism: u32) -> PyResult<Self> {
+        Argon2Params::new(memory_cost, time_cost, parallelism, None)
+            .map_err(|e| PyValueError::new_err(format!("invalid argon2id parameters: {e}")))?;
+        Ok(Self {
+            policy: Policy::Argon2id {
+                memory_cost,
+                time_cost,
+                parallelism,
+            },
+        })
+    }
+
+    /// scrypt with `N = 2**log_n`.
+    #[staticmethod]
+    #[pyo3(signature = (log_n=17, r=8, p=1))]
+    fn scrypt(log_n: u8, r: u32, p: u32) -> PyResult<Self> {
+        ScryptParams::new(log_n, r, p, ScryptParams::RECOMMENDED_LEN)
+            .map_err(|e| PyValueError::new_err(format!("invalid scrypt parameters: {e}")))?;
+        Ok(Self {
+            policy: Policy::Scrypt { log_n, r, p },
+        })
+    }
+
+    /// bcrypt with a work factor of `2**cost`.
+    #[staticmethod]
+    #[pyo3(signature = (cost=12))]
+    fn bcrypt(cost: u32) -> PyResult<Self> {
+        if !(4..=31).contains(&cost) {
+            return Err(PyValueError::new_err(
+                "bcrypt cost must be between 4 and 31",
+            ));
+        }
+        Ok(Self {
+            policy: Policy::Bcrypt { cost },
+        })
+    }
+
+    #[getter]
+    fn algorithm(&self) -> &'static str {
+        match self.policy {
+            Policy::Argon2id { .. } => "argon2id",
+            Policy::Scrypt { .. } => "scrypt",
+            Policy::Bcrypt { .. } => "bcrypt",
+        }
+    }
+
+    fn __repr__(&self) -> String {
+        match self.policy {
+            Policy::Argon2id {
+                memory_cost,
+                time_cost,
+                parallelism,
+            } => format!(
+                "PasswordPolicy.argon2id(memory_cost={memory_cost}, time_cost={time_cost}, parallelism={parallelism})"
+            ),
+            Policy::Scrypt { log_n, r, p } => {
+                format!("PasswordPolicy.scrypt(log_n={log_n}, r={r}, p={p})")
+            }
+            Policy::Bcrypt { cost } => format!("PasswordPolicy.bcrypt(cost={cost})"),
+        }
+    }
+}
+
+/// Hash `password` into a self-describing string.
+///
+/// Argon2id and scrypt produce PHC strings (`$argon2id$v=19$m=...`), bcrypt
+/// its usual `$2b$` form. With a `pepper` the password is first keyed with
+/// HMAC-SHA256, so the stored hash is useless without the server secret.
+#[pyfunction]
+#[pyo3(signature = (password, policy=None, pepper=None))]
+pub fn hash_password(
+    py: Python<'_>,
+    password: &str,
+    policy: Option<PasswordPolicy>,
+    pepper: Option<&Bound<'_, PyAny>>,
+) -> PyResult<String> {
+    let input = peppered(password, pepper)?;
+    py.detach(|| hash_bytes(&input, policy.unwrap_or_default()))
+        .map_err(PyValueError::new_err)
+}
+
+/// Check `password` against a hash from `hash_password`.
+///
+/// Also accepts `$2a$`/`$2y$` bcrypt and `$pbkdf2-sha256$` PHC hashes made
+/// elsewhere. Raises `ValueError` if the hash format is not recognised.
+#[pyfunction]
+#[pyo3(signature = (password, hash, pepper=None))]
+pub fn verify_password(
+    py: Python<'_>,
+    password: &str,
+    hash: &str,
+    pepper: Option<&Bound<'_, PyAny>>,
+) -> PyResult<bool> {
+    let input = peppered(password, pepper)?;
+    verify_bytes(py, &input, hash)
+}
+
+/// Whether `hash` was made with a different algorithm or different cost
+/// parameters than `policy` (Argon2id defaults when omitted), meaning it
+/// should be replaced after the next successful login.
+#[pyfunction]
+#[pyo3(signature = (hash, policy=None))]
+pub fn needs_rehash(hash: &str, policy: Option<PasswordPolicy>) -> PyResult<bool> {
+    let policy = policy.unwrap_or_default().policy;
+    if is_bcrypt(hash) {
+        let cost = hash
+            .get(4..6)
+            .and_then(|cost| cost.parse::<u32>().ok())
+            .ok_or_else(unknown_format)?;
+        return Ok(policy != Policy::Bcrypt { cost });
+    }
+    let parsed = parse_phc(hash)?;
+    let current = match parsed.algorithm.as_str() {
+        "argon2id" if parsed.version == Some(Version::V0x13.into()) => {
+            let params = Argon2Params::try_from(&parsed).map_err(|_| unknown_format())?;
+            Policy::Argon2id {
+                memory_cost: params.m_cost(),
+                time_cost: params.t_cost(),
+                parallelism: params.p_cost(),
+            }
+        }
+        "scrypt" => {
+            let params = ScryptParams::try_from(&parsed).map_err(|_| unknown_format())?;
+            Policy::Scrypt {
+                log_n: params.log_n(),
+                r: params.r(),
+                p: params.p(),
+            }
+        }
+        "argon2id" | "argon2i" | "argon2d" | "pbkdf2-sha256" | "pbkdf2-sha512" => return Ok(true),
+        _ => return Err(unknown_format()),
+    };
+    Ok(current != policy)
+}
+
+/// Hash raw bytes as `hash_password` does, without touching Python.
+pub(crate) fn hash_bytes(input: &[u8], policy: PasswordPolicy) -> Result<String, String> {
+    let salt = random_bytes(SALT_LEN);
+    match policy.policy {
+        Policy::Argon2id {
+            memory_cost,
+            time_cost,
+            parallelism,
+        } => {
+            let params = Argon2Params::new(memory_cost, time_cost, parallelism, None)
+                .map_err(|e| e.to_string())?;
+            Argon2::new(argon2::Algorithm::Argon2id, Version::V0x13, params)
+                .hash_password(input, &salt_string(&salt)?)
+                .map(|hash| hash.to_string())
+                .map_err(|e| e.to_string())
+        }
+        Policy::Scrypt { log_n, r, p } => {
+            let params = ScryptParams::new(log_n, r, p, ScryptParams::RECOMMENDED_LEN)
+                .map_err(|e| e.to_string())?;
+            Scrypt
+                .hash_password_customized(input, None, None, params, &salt_string(&salt)?)
+                .map(|hash| hash.to_string())
+                .map_err(|e| e.to_string())
+        }
+        Policy::Bcrypt { cost } => {
+            if input.len() > BCRYPT_MAX_INPUT {
+                return Err(format!(
+                    "bcrypt passwords are limited to {BCRYPT_MAX_INPUT} bytes; use a pepper or argon2id"
+                ));
+            }
+            let salt: [u8; SALT_LEN] = salt.try_into().expect("salt has SALT_LEN bytes");
+            bcrypt::hash_with_salt(input, cost, salt)
+                .map(|parts| parts.format_for_version(bcrypt::Version::TwoB))
+                .map_err(|e| e.to_string())
+        }
+    }
+}
+
+/// Check raw bytes against any hash format `verify_password` accepts.
+pub(crate) fn verify_bytes(py: Python<'_>, input: &[u8], hash: &str) -> PyResult<bool> {
+    if is_bcrypt(hash) {
+        return py
+            .detach(|| bcrypt::verify(input, hash))
+            .map_err(|e| PyValueError::new_err(format!("malformed bcrypt hash: {e}")));
+    }
+    let parsed = parse_phc(hash)?;
+    let algorithm = parsed.algorithm.as_str();
+    let verifier: &(dyn PasswordVerifier + Sync) = match algorithm {
+        "argon2id" | "argon2i" | "argon2d" => &Argon2::default(),
+        "scrypt" => &Scrypt,
+        "pbkdf2-sha256" | "pbkdf2-sha512" => &pbkdf2::Pbkdf2,
+        _ => return Err(unknown_format()),
+    };
+    Ok(py.detach(|| verifier.verify_password(input, &parsed).is_ok()))
+}
+
+fn peppered(password: &str, pepper: Option<&Bound<'_, PyAny>>) -> PyResult<Vec<u8>> {
+    let Some(pepper) = pepper else {
+        return Ok(password.as_bytes().to_vec());
+    };
+    let pepper = secret_bytes(pepper)?;
+    // Base64 keeps the input free of NUL bytes and within bcrypt's limit.
+    Ok(STANDARD_NO_PAD
+        .encode(hmac_sha256(&pepper, password.as_bytes()))
+        .into_bytes())
+}
+
+fn salt_string(salt: &[u8]) -> Result<SaltString, String> {
+    SaltString::encode_b64(salt).map_err(|e| e.to_string())
+}
+
+fn is_bcrypt(hash: &str) -> bool {
+    ["$2a$", "$2b$", "$2x$", "$2y$"]
+        .iter()
+        .any(|prefix| hash.starts_with(prefix))
+}
+
+fn parse_phc(hash: &str) -> PyResult<PasswordHash<'_>> {
+    PasswordHash::new(hash).map_err(|_| unknown_format())
+}
+
+fn unknown_format() -> PyErr {
+    PyValueError::new_err("unrecognised password hash format")
+}
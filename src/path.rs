@@ -0,0 +1,235 @@
+use std::fmt::Write;
+
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+use regex::Regex;
+
+use crate::converters::Converter;
+
+/// Maximum number of parameters accepted in a single path.
+const MAX_PARAMS: usize = 20;
+
+/// One piece of a path segment: literal text or a `<converter:name>` parameter.
+pub(crate) enum Piece<'a> {
+    Literal(&'a str),
+    Param { name: &'a str, converter: Converter },
+}
+
+/// A parsed path segment, as stored in the router tree.
+pub(crate) enum Segment {
+    Static(String),
+    Param {
+        name: String,
+        converter: Converter,
+    },
+    /// Literal text mixed with parameters inside one segment, e.g. `<name>.json`.
+    Pattern {
+        source: String,
+        regex: Regex,
+        params: Vec<(String, Converter)>,
+    },
+    /// `<path:name>`; must be the final segment.
+    CatchAll {
+        name: String,
+    },
+}
+
+/// Compile a route pattern into an anchored regex with named groups.
+///
+/// Accepts `:name`, `<name>` and `<converter:name>` parameters.
+#[pyfunction]
+pub fn compile_path(path: &str) -> PyResult<String> {
+    let segments = split_segments(path)?;
+
+    let mut pattern = String::with_capacity(path.len() + 16);
+    pattern.push('^');
+    let mut params = 0;
+    for segment in segments {
+        pattern.push('/');
+        for piece in parse_pieces(segment)? {
+            match piece {
+                Piece::Literal(text) => pattern.push_str(&regex::escape(text)),
+                Piece::Param { name, converter } => {
+                    params += 1;
+                    pattern.push_str("(?P<");
+                    pattern.push_str(name);
+                    pattern.push('>');
+                    pattern.push_str(&converter.regex());
+                    pattern.push(')');
+                }
+            }
+        }
+    }
+    pattern.push('$');
+
+    if params > MAX_PARAMS {
+        return Err(PyValueError::new_err(format!(
+            "too many parameters in path (max {MAX_PARAMS})"
+        )));
+    }
+    Regex::new(&pattern)
+        .map_err(|e| PyValueError::new_err(format!("invalid regex generated: {e}")))?;
+    Ok(pattern)
+}
+
+/// Match `path` against a regex pattern, returning the named captures in order.
+#[pyfunction]
+pub fn match_path(pattern: &str, path: &str) -> PyResult<Option<Vec<(String, String)>>> {
+    let re = Regex::new(pattern)
+        .map_err(|e| PyValueError::new_err(format!("invalid regex pattern: {e}")))?;
+    Ok(re.captures(path).map(|caps| {
+        re.capture_names()
+            .flatten()
+            .filter_map(|name| {
+                caps.name(name)
+                    .map(|m| (name.to_string(), m.as_str().to_string()))
+            })
+            .collect()
+    }))
+}
+
+/// Parse a route pattern into router segments.
+pub(crate) fn parse_pattern(path: &str) -> PyResult<Vec<Segment>> {
+    let raw = split_segments(path)?;
+    let last = raw.len() - 1;
+    let mut names: Vec<&str> = Vec::new();
+    let mut segments = Vec::with_capacity(raw.len());
+
+    for (i, segment) in raw.into_iter().enumerate() {
+        let pieces = parse_pieces(segment)?;
+        for piece in &pieces {
+            if let Piece::Param { name, .. } = piece {
+                if names.contains(name) {
+                    return Err(PyValueError::new_err(format!(
+                        "duplicate parameter '{name}' in path {path}"
+                    )));
+                }
+                names.push(name);
+            }
+        }
+        if names.len() > MAX_PARAMS {
+            return Err(PyValueError::new_err(format!(
+                "too many parameters in path (max {MAX_PARAMS})"
+            )));
+        }
+
+        segments.push(match pieces.as_slice() {
+            [] => Segment::Static(String::new()),
+            [Piece::Literal(text)] => Segment::Static((*text).to_owned()),
+            [Piece::Param {
+                name,
+                converter: Converter::Path,
+            }] if i == last => Segment::CatchAll {
+                name: (*name).to_owned(),
+            },
+            [Piece::Param { name, converter }] if *converter != Converter::Path => Segment::Param {
+                name: (*name).to_owned(),
+                converter: *converter,
+            },
+            _ => segment_pattern(segment, &pieces)?,
+        });
+    }
+    Ok(segments)
+}
+
+/// Strip the leading `/` and split into segments; `/` itself is one empty segment.
+pub(crate) fn split_segments(path: &str) -> PyResult<Vec<&str>> {
+    path.strip_prefix('/')
+        .map(|rest| rest.split('/').collect())
+        .ok_or_else(|| PyValueError::new_err("path must start with /"))
+}
+
+pub(crate) fn parse_pieces(segment: &str) -> PyResult<Vec<Piece<'_>>> {
+    if let Some(name) = segment.strip_prefix(':') {
+        if !is_identifier(name) {
+            return Err(PyValueError::new_err("path contains invalid pattern"));
+        }
+        return Ok(vec![Piece::Param {
+            name,
+            converter: Converter::Str,
+        }]);
+    }
+
+    let mut pieces = Vec::new();
+    let mut rest = segment;
+    while let Some(open) = rest.find('<') {
+        let close = rest[open..]
+            .find('>')
+            .map(|c| open + c)
+            .ok_or_else(|| PyValueError::new_err(format!("unclosed '<' in segment '{segment}'")))?;
+        if open > 0 {
+            pieces.push(Piece::Literal(&rest[..open]));
+        }
+        let spec = &rest[open + 1..close];
+        let (converter_name, name) = spec.split_once(':').unwrap_or(("str", spec));
+        if !is_identifier(name) {
+            return Err(PyValueError::new_err(format!(
+                "invalid parameter name '{name}' in segment '{segment}'"
+            )));
+        }
+        let converter = Converter::from_name(converter_name).ok_or_else(|| {
+            PyValueError::new_err(format!("unknown path converter '{converter_name}'"))
+        })?;
+        pieces.push(Piece::Param { name, converter });
+        rest = &rest[close + 1..];
+    }
+    if !rest.is_empty() {
+        pieces.push(Piece::Literal(rest));
+    }
+    Ok(pieces)
+}
+
+fn segment_pattern(segment: &str, pieces: &[Piece<'_>]) -> PyResult<Segment> {
+    let mut regex = String::from("^");
+    let mut params = Vec::new();
+    for piece in pieces {
+        match piece {
+            Piece::Literal(text) => regex.push_str(&regex::escape(text)),
+            Piece::Param {
+                converter: Converter::Path,
+                ..
+            } => {
+                return Err(PyValueError::new_err(
+                    "the path converter must span the final segment",
+                ))
+            }
+            Piece::Param { name, converter } => {
+                // Named groups: custom converter regexes may add their own.
+                regex.push_str("(?P<");
+                regex.push_str(name);
+                regex.push('>');
+                regex.push_str(&converter.regex());
+                regex.push(')');
+                params.push(((*name).to_owned(), *converter));
+            }
+        }
+    }
+    regex.push('$');
+    Ok(Segment::Pattern {
+        source: segment.to_owned(),
+        regex: Regex::new(&regex)
+            .map_err(|e| PyValueError::new_err(format!("invalid regex generated: {e}")))?,
+        params,
+    })
+}
+
+pub(crate) fn is_identifier(name: &str) -> bool {
+    let mut chars = name.chars();
+    match chars.next() {
+        Some(c) if c == '_' || c.is_ascii_alphabetic() => {}
+        _ => return false,
+    }
+    chars.all(|c| c == '_' || c.is_ascii_alphanumeric())
+}
+
+/// Percent-encode `text` onto `out`, keeping RFC 3986 unreserved characters
+/// and, when `keep_slash` is set, `/`.
+pub(crate) fn push_encoded(out: &mut String, text: &str, keep_slash: bool) {
+    for byte in text.bytes() {
+        if byte.is_ascii_alphanumeric() || b"-._~".contains(&byte) || (keep_slash && byte == b'/') {
+            out.push(byte as char);
+        } else {
+            let _ = write!(out, "%{byte:02X}");
+        }
+    }
+}
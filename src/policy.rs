@@ -0,0 +1,608 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::Path;
+
+use pyo3::create_exception;
+use pyo3::exceptions::{PyAttributeError, PyValueError};
+use pyo3::prelude::*;
+use pyo3::types::PyDict;
+use serde::Deserialize;
+use serde_json::Value;
+
+use crate::json::{py_to_value, py_to_value_with};
+use crate::json_stream::stdlib_fallback;
+
+create_exception!(
+    haske,
+    PolicyError,
+    PyValueError,
+    "Raised when a policy document is invalid."
+);
+
+// Policy documents as written in TOML or JSON.
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct PolicyDoc {
+    #[serde(default = "default_roles_attr")]
+    roles_attr: String,
+    #[serde(default)]
+    roles: BTreeMap<String, RoleDoc>,
+    #[serde(default)]
+    rules: Vec<RuleDoc>,
+}
+
+fn default_roles_attr() -> String {
+    "roles".to_owned()
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RoleDoc {
+    #[serde(default)]
+    inherits: Vec<String>,
+    #[serde(default)]
+    permissions: Vec<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RuleDoc {
+    name: String,
+    #[serde(default)]
+    effect: Effect,
+    actions: Vec<String>,
+    #[serde(default)]
+    roles: Vec<String>,
+    #[serde(default)]
+    when: Vec<ConditionDoc>,
+}
+
+#[derive(Deserialize, Default, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+enum Effect {
+    #[default]
+    Allow,
+    Deny,
+}
+
+/// `attr` plus exactly one test.
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ConditionDoc {
+    attr: String,
+    equals: Option<Value>,
+    not_equals: Option<Value>,
+    equals_attr: Option<String>,
+    not_equals_attr: Option<String>,
+    #[serde(rename = "in")]
+    one_of: Option<Vec<Value>>,
+    contains: Option<Value>,
+    exists: Option<bool>,
+}
+
+// Compiled policy.
+
+#[derive(Clone, Copy)]
+enum Root {
+    Subject,
+    Resource,
+    Context,
+}
+
+/// A dotted attribute reference such as `resource.owner_id`.
+struct AttrPath {
+    root: Root,
+    segments: Vec<String>,
+}
+
+impl AttrPath {
+    fn parse(text: &str) -> Result<Self, String> {
+        let mut parts = text.split('.');
+        let root = match parts.next() {
+            Some("subject") => Root::Subject,
+            Some("resource") => Root::Resource,
+            Some("context") => Root::Context,
+            _ => {
+                return Err(format!(
+                    "attribute '{text}' must start with 'subject.', 'resource.' or 'context.'"
+                ))
+            }
+        };
+        let segments: Vec<String> = parts.map(str::to_owned).collect();
+        if segments.is_empty() || segments.iter().any(String::is_empty) {
+            return Err(format!("invalid attribute '{text}'"));
+        }
+        Ok(Self { root, segments })
+    }
+}
+
+enum Test {
+    Equals(Value),
+    NotEquals(Value),
+    EqualsAttr(AttrPath),
+    NotEqualsAttr(AttrPath),
+    In(Vec<Value>),
+    Contains(Value),
+    Exists(bool),
+}
+
+struct Condition {
+    attr: AttrPath,
+    test: Test,
+}
+
+impl Condition {
+    fn compile(doc: ConditionDoc) -> Result<Self, String> {
+        let attr = AttrPath::parse(&doc.attr)?;
+        let mut tests = Vec::new();
+        if let Some(value) = doc.equals {
+            tests.push(Test::Equals(value));
+        }
+        if let Some(value) = doc.not_equals {
+            tests.push(Test::NotEquals(value));
+        }
+        if let Some(other) = doc.equals_attr {
+            tests.push(Test::EqualsAttr(AttrPath::parse(&other)?));
+        }
+        if let Some(other) = doc.not_equals_attr {
+            tests.push(Test::NotEqualsAttr(AttrPath::parse(&other)?));
+        }
+        if let Some(values) = doc.one_of {
+            tests.push(Test::In(values));
+        }
+        if let Some(value) = doc.contains {
+            tests.push(Test::Contains(value));
+        }
+        if let Some(expected) = doc.exists {
+            tests.push(Test::Exists(expected));
+        }
+        if tests.len() != 1 {
+            return Err(format!(
+                "condition on '{}' needs exactly one of equals, not_equals, equals_attr, not_equals_attr, in, contains or exists",
+                doc.attr
+            ));
+        }
+        Ok(Self {
+            attr,
+            test: tests.remove(0),
+        })
+    }
+
+    /// Whether the condition holds. Every test but `exists` fails when an
+    /// attribute is missing or null, so a rule never fires on absent data.
+    fn holds(&self, attrs: &Attributes<'_, '_>) -> PyResult<bool> {
+        let value = attrs.lookup(&self.attr)?.filter(|v| !v.is_null());
+        Ok(match (&self.test, value) {
+            (Test::Exists(expected), value) => value.is_some() == *expected,
+            (_, None) => false,
+            (Test::Equals(expected), Some(value)) => same(&value, expected),
+            (Test::NotEquals(expected), Some(value)) => !same(&value, expected),
+            (Test::EqualsAttr(other), Some(value)) => attrs
+                .lookup(other)?
+                .is_some_and(|other| same(&value, &other)),
+            (Test::NotEqualsAttr(other), Some(value)) => attrs
+                .lookup(other)?
+                .is_some_and(|other| !other.is_null() && !same(&value, &other)),
+            (Test::In(options), Some(value)) => options.iter().any(|o| same(&value, o)),
+            (Test::Contains(item), Some(Value::Array(items))) => {
+                items.iter().any(|v| same(v, item))
+            }
+            (Test::Contains(item), Some(Value::String(text))) => {
+                item.as_str().is_some_and(|s| text.contains(s))
+            }
+            (Test::Contains(_), Some(_)) => false,
+        })
+    }
+}
+
+struct Rule {
+    name: String,
+    effect: Effect,
+    actions: Vec<String>,
+    roles: Vec<String>,
+    when: Vec<Condition>,
+}
+
+/// The objects a policy is evaluated against.
+struct Attributes<'a, 'py> {
+    subject: &'a Bound<'py, PyAny>,
+    resource: Option<&'a Bound<'py, PyAny>>,
+    context: Option<&'a Bound<'py, PyAny>>,
+}
+
+impl<'py> Attributes<'_, 'py> {
+    /// The value at `path`, or `None` if any step along it is missing.
+    fn lookup(&self, path: &AttrPath) -> PyResult<Option<Value>> {
+        let root = match path.root {
+            Root::Subject => Some(self.subject),
+            Root::Resource => self.resource,
+            Root::Context => self.context,
+        };
+        let Some(root) = root else {
+            return Ok(None);
+        };
+        lookup_in(root, &path.segments)
+    }
+}
+
+/// Follow `segments` through dict keys or attributes of `obj`.
+fn lookup_in(obj: &Bound<'_, PyAny>, segments: &[String]) -> PyResult<Option<Value>> {
+    let mut current = obj.clone();
+    for segment in segments {
+        if current.is_none() {
+            return Ok(None);
+        }
+        let next = if let Ok(dict) = current.downcast::<PyDict>() {
+            dict.get_item(segment)?
+        } else {
+            match current.getattr(segment.as_str()) {
+                Ok(value) => Some(value),
+                Err(e) if e.is_instance_of::<PyAttributeError>(obj.py()) => None,
+                Err(e) => return Err(e),
+            }
+        };
+        let Some(next) = next else {
+            return Ok(None);
+        };
+        current = next;
+    }
+    py_to_value_with(&current, stdlib_fallback).map(Some)
+}
+
+/// JSON equality, except that numbers compare by value (`1 == 1.0`).
+fn same(a: &Value, b: &Value) -> bool {
+    match (a, b) {
+        (Value::Number(x), Value::Number(y)) => match (x.as_i64(), y.as_i64()) {
+            (Some(x), Some(y)) => x == y,
+            _ => x.as_f64() == y.as_f64(),
+        },
+        _ => a == b,
+    }
+}
+
+/// `*` matches every action, `posts:*` every action starting with `posts:`.
+fn action_matches(pattern: &str, action: &str) -> bool {
+    match pattern.strip_suffix('*') {
+        Some(prefix) => action.starts_with(prefix),
+        None => pattern == action,
+    }
+}
+
+/// Outcome of `Policy.explain`. Truthy when the action is allowed.
+#[pyclass(frozen, module = "haske")]
+pub struct Decision {
+    /// Whether the action is allowed.
+    #[pyo3(get)]
+    allowed: bool,
+    /// Name of the rule that decided, if a rule did.
+    #[pyo3(get)]
+    rule: Option<String>,
+    /// Role whose permissions allowed the action, if one did.
+    #[pyo3(get)]
+    role: Option<String>,
+    /// Human-readable account of the decision.
+    #[pyo3(get)]
+    reason: String,
+}
+
+#[pymethods]
+impl Decision {
+    fn __bool__(&self) -> bool {
+        self.allowed
+    }
+
+    fn __repr__(&self) -> String {
+        format!(
+            "Decision(allowed={}, reason=\"{}\")",
+            if self.allowed { "True" } else { "False" },
+            self.reason
+        )
+    }
+}
+
+/// Authorization policy: role permissions plus attribute-based rules.
+///
+/// Roles grant permissions (`posts:read`, `posts:*`, `*`) and may inherit
+/// other roles. Rules allow or deny actions when conditions on the subject,
+/// resource and context hold, e.g. `resource.owner_id` equal to `subject.id`.
+/// A matching deny rule always wins; otherwise a role permission or an allow
+/// rule grants the action, and anything else is denied.
+///
+/// Subjects, resources and contexts are dicts or objects; attributes are
+/// looked up by key or by attribute name. The subject's roles are read from
+/// its `roles` attribute (a list or a single name) unless `roles_attr` says
+/// otherwise.
+#[pyclass(frozen, module = "haske")]
+pub struct Policy {
+    roles_attr: Vec<String>,
+    /// Each role with the roles it inherits, transitively, itself included.
+    expanded: BTreeMap<String, Vec<String>>,
+    /// Each role's own permissions.
+    permissions: BTreeMap<String, Vec<String>>,
+    rules: Vec<Rule>,
+}
+
+impl Policy {
+    fn compile(doc: PolicyDoc) -> Result<Self, String> {
+        let roles_attr: Vec<String> = doc.roles_attr.split('.').map(str::to_owned).collect();
+        if roles_attr.iter().any(String::is_empty) {
+            return Err(format!("invalid roles_attr '{}'", doc.roles_attr));
+        }
+
+        let mut expanded = BTreeMap::new();
+        for name in doc.roles.keys() {
+            let mut seen = Vec::new();
+            expand_role(&doc.roles, name, &mut Vec::new(), &mut seen)?;
+            expanded.insert(name.clone(), seen);
+        }
+        let permissions = doc
+            .roles
+            .into_iter()
+            .map(|(name, role)| (name, role.permissions))
+            .collect();
+
+        let mut names = BTreeSet::new();
+        let mut rules = Vec::with_capacity(doc.rules.len());
+        for rule in doc.rules {
+            if !names.insert(rule.name.clone()) {
+                return Err(format!("duplicate rule '{}'", rule.name));
+            }
+            if rule.actions.is_empty() {
+                return Err(format!("rule '{}' lists no actions", rule.name));
+            }
+            if let Some(role) = rule.roles.iter().find(|r| !expanded.contains_key(*r)) {
+                return Err(format!("rule '{}' names unknown role '{role}'", rule.name));
+            }
+            let when = rule
+                .when
+                .into_iter()
+                .map(Condition::compile)
+                .collect::<Result<_, _>>()
+                .map_err(|e| format!("rule '{}': {e}", rule.name))?;
+            rules.push(Rule {
+                name: rule.name,
+                effect: rule.effect,
+                actions: rule.actions,
+                roles: rule.roles,
+                when,
+            });
+        }
+
+        Ok(Self {
+            roles_attr,
+            expanded,
+            permissions,
+            rules,
+        })
+    }
+
+    fn from_value(value: Value) -> PyResult<Self> {
+        let doc = serde_json::from_value(value).map_err(|e| PolicyError::new_err(e.to_string()))?;
+        Self::compile(doc).map_err(PolicyError::new_err)
+    }
+
+    /// The subject's roles with everything they inherit, in order.
+    fn subject_roles(&self, subject: &Bound<'_, PyAny>) -> PyResult<Vec<&str>> {
+        let assigned = match lookup_in(subject, &self.roles_attr)? {
+            Some(Value::String(role)) => vec![role],
+            Some(Value::Array(items)) => items
+                .into_iter()
+                .filter_map(|item| match item {
+                    Value::String(role) => Some(role),
+                    _ => None,
+                })
+                .collect(),
+            _ => Vec::new(),
+        };
+        let mut roles: Vec<&str> = Vec::new();
+        for role in &assigned {
+            // Roles the policy does not define grant nothing.
+            for inherited in self.expanded.get(role).into_iter().flatten() {
+                if !roles.contains(&inherited.as_str()) {
+                    roles.push(inherited);
+                }
+            }
+        }
+        Ok(roles)
+    }
+
+    fn rule_matches(
+        &self,
+        rule: &Rule,
+        action: &str,
+        roles: &[&str],
+        attrs: &Attributes<'_, '_>,
+    ) -> PyResult<bool> {
+        if !rule.actions.iter().any(|p| action_matches(p, action)) {
+            return Ok(false);
+        }
+        if !rule.roles.is_empty() && !rule.roles.iter().any(|r| roles.contains(&r.as_str())) {
+            return Ok(false);
+        }
+        for condition in &rule.when {
+            if !condition.holds(attrs)? {
+                return Ok(false);
+            }
+        }
+        Ok(true)
+    }
+
+    fn decide(&self, attrs: &Attributes<'_, '_>, action: &str) -> PyResult<Decision> {
+        let roles = self.subject_roles(attrs.subject)?;
+
+        for rule in self.rules.iter().filter(|r| r.effect == Effect::Deny) {
+            if self.rule_matches(rule, action, &roles, attrs)? {
+                return Ok(Decision {
+                    allowed: false,
+                    rule: Some(rule.name.clone()),
+                    role: None,
+                    reason: format!("rule '{}' denies '{action}'", rule.name),
+                });
+            }
+        }
+
+        for role in &roles {
+            let granted = self.permissions[*role]
+                .iter()
+                .find(|p| action_matches(p, action));
+            if let Some(permission) = granted {
+                return Ok(Decision {
+                    allowed: true,
+                    rule: None,
+                    role: Some((*role).to_owned()),
+                    reason: format!("role '{role}' grants '{action}' through '{permission}'"),
+                });
+            }
+        }
+
+        for rule in self.rules.iter().filter(|r| r.effect == Effect::Allow) {
+            if self.rule_matches(rule, action, &roles, attrs)? {
+                return Ok(Decision {
+                    allowed: true,
+                    rule: Some(rule.name.clone()),
+                    role: None,
+                    reason: format!("rule '{}' allows '{action}'", rule.name),
+                });
+            }
+        }
+
+        Ok(Decision {
+            allowed: false,
+            rule: None,
+            role: None,
+            reason: format!("no role or rule allows '{action}'"),
+        })
+    }
+}
+
+/// Depth-first walk of `name`'s inheritance, appending each role once to
+/// `seen` and failing on unknown roles and cycles.
+fn expand_role(
+    roles: &BTreeMap<String, RoleDoc>,
+    name: &str,
+    path: &mut Vec<String>,
+    seen: &mut Vec<String>,
+) -> Result<(), String> {
+    if path.iter().any(|r| r == name) {
+        path.push(name.to_owned());
+        return Err(format!("role inheritance cycle: {}", path.join(" -> ")));
+    }
+    let Some(role) = roles.get(name) else {
+        return Err(format!(
+            "role '{}' inherits unknown role '{name}'",
+            path.last().map_or("", String::as_str)
+        ));
+    };
+    if seen.iter().any(|r| r == name) {
+        return Ok(());
+    }
+    seen.push(name.to_owned());
+    path.push(name.to_owned());
+    for parent in &role.inherits {
+        expand_role(roles, parent, path, seen)?;
+    }
+    path.pop();
+    Ok(())
+}
+
+#[pymethods]
+impl Policy {
+    /// Load a policy from a `.toml` or `.json` file.
+    #[staticmethod]
+    fn from_file(path: &str) -> PyResult<Self> {
+        let text = std::fs::read_to_string(path)?;
+        match Path::new(path).extension().and_then(|e| e.to_str()) {
+            Some("toml") => Self::from_toml(&text),
+            Some("json") => Self::from_json(&text),
+            _ => Err(PolicyError::new_err(format!(
+                "cannot tell the format of '{path}'; use a .toml or .json file"
+            ))),
+        }
+    }
+
+    #[staticmethod]
+    fn from_toml(text: &str) -> PyResult<Self> {
+        let doc = toml::from_str(text).map_err(|e| PolicyError::new_err(e.to_string()))?;
+        Self::compile(doc).map_err(PolicyError::new_err)
+    }
+
+    #[staticmethod]
+    fn from_json(text: &str) -> PyResult<Self> {
+        let value = serde_json::from_str(text).map_err(|e| PolicyError::new_err(e.to_string()))?;
+        Self::from_value(value)
+    }
+
+    /// Build a policy from a dict shaped like the file format.
+    #[staticmethod]
+    fn from_dict(document: &Bound<'_, PyDict>) -> PyResult<Self> {
+        Self::from_value(py_to_value(document.as_any())?)
+    }
+
+    /// Every role the policy defines.
+    #[getter]
+    fn roles(&self) -> Vec<String> {
+        self.expanded.keys().cloned().collect()
+    }
+
+    /// The roles `subject` holds, directly or through inheritance.
+    fn effective_roles(&self, subject: &Bound<'_, PyAny>) -> PyResult<Vec<String>> {
+        Ok(self
+            .subject_roles(subject)?
+            .into_iter()
+            .map(str::to_owned)
+            .collect())
+    }
+
+    /// Permissions of `role`, inherited ones included.
+    fn permissions(&self, role: &str) -> PyResult<Vec<String>> {
+        let Some(roles) = self.expanded.get(role) else {
+            return Err(PolicyError::new_err(format!("unknown role '{role}'")));
+        };
+        let mut permissions = Vec::new();
+        for permission in roles.iter().flat_map(|r| &self.permissions[r]) {
+            if !permissions.contains(permission) {
+                permissions.push(permission.clone());
+            }
+        }
+        Ok(permissions)
+    }
+
+    /// Whether `subject` may perform `action` on `resource`.
+    #[pyo3(signature = (subject, action, resource=None, context=None))]
+    fn can(
+        &self,
+        subject: &Bound<'_, PyAny>,
+        action: &str,
+        resource: Option<&Bound<'_, PyAny>>,
+        context: Option<&Bound<'_, PyAny>>,
+    ) -> PyResult<bool> {
+        self.explain(subject, action, resource, context)
+            .map(|decision| decision.allowed)
+    }
+
+    /// Like `can`, but returns a `Decision` naming the rule or role that
+    /// allowed or denied the action.
+    #[pyo3(signature = (subject, action, resource=None, context=None))]
+    fn explain(
+        &self,
+        subject: &Bound<'_, PyAny>,
+        action: &str,
+        resource: Option<&Bound<'_, PyAny>>,
+        context: Option<&Bound<'_, PyAny>>,
+    ) -> PyResult<Decision> {
+        let attrs = Attributes {
+            subject,
+            resource: resource.filter(|r| !r.is_none()),
+            context: context.filter(|c| !c.is_none()),
+        };
+        self.decide(&attrs, action)
+    }
+
+    fn __repr__(&self) -> String {
+        format!(
+            "Policy(roles={}, rules={})",
+            self.expanded.len(),
+            self.rules.len()
+        )
+    }
+}
@@ -0,0 +1,548 @@
+use std::collections::{BTreeSet, HashMap};
+
+use pyo3::exceptions::{PyLookupError, PyValueError};
+use pyo3::prelude::*;
+use pyo3::types::{PyDict, PyList, PyTuple};
+use regex::Regex;
+
+use crate::converters::Converter;
+use crate::dispatch::Dispatch;
+use crate::path::{parse_pattern, parse_pieces, push_encoded, split_segments, Piece, Segment};
+
+struct Endpoint {
+    method: String,
+    handler: Py<PyAny>,
+}
+
+struct ParamEdge {
+    name: String,
+    converter: Converter,
+    node: Node,
+}
+
+struct PatternEdge {
+    source: String,
+    regex: Regex,
+    params: Vec<(String, Converter)>,
+    node: Node,
+}
+
+struct CatchAllEdge {
+    name: String,
+    endpoints: Vec<Endpoint>,
+}
+
+/// One path segment position in the route tree.
+///
+/// Children are tried in order: static segments, typed parameters (most
+/// specific converter first), mixed literal/parameter segments, then the
+/// `path` catch-all. Lookup cost depends on path depth, not route count.
+#[derive(Default)]
+struct Node {
+    statics: HashMap<String, Node>,
+    params: Vec<ParamEdge>,
+    patterns: Vec<PatternEdge>,
+    catch_alls: Vec<CatchAllEdge>,
+    endpoints: Vec<Endpoint>,
+}
+
+/// A captured parameter value, converted to Python once a route is selected.
+struct Capture<'a> {
+    name: &'a str,
+    converter: Converter,
+    value: &'a str,
+}
+
+impl Node {
+    fn insert(&mut self, mut segments: std::vec::IntoIter<Segment>) -> &mut Vec<Endpoint> {
+        let Some(segment) = segments.next() else {
+            return &mut self.endpoints;
+        };
+        let child = match segment {
+            Segment::Static(text) => self.statics.entry(text).or_default(),
+            Segment::Param { name, converter } => {
+                let same = |e: &ParamEdge| e.name == name && e.converter == converter;
+                if !self.params.iter().any(same) {
+                    self.params.push(ParamEdge {
+                        name: name.clone(),
+                        converter,
+                        node: Node::default(),
+                    });
+                    // Stable sort: equal converters keep registration order.
+                    self.params.sort_by_key(|e| e.converter.priority());
+                }
+                let index = self
+                    .params
+                    .iter()
+                    .position(same)
+                    .expect("edge inserted above");
+                &mut self.params[index].node
+            }
+            Segment::Pattern {
+                source,
+                regex,
+                params,
+            } => {
+                let index = match self.patterns.iter().position(|e| e.source == source) {
+                    Some(index) => index,
+                    None => {
+                        self.patterns.push(PatternEdge {
+                            source,
+                            regex,
+                            params,
+                            node: Node::default(),
+                        });
+                        self.patterns.len() - 1
+                    }
+                };
+                &mut self.patterns[index].node
+            }
+            Segment::CatchAll { name } => {
+                let index = match self.catch_alls.iter().position(|e| e.name == name) {
+                    Some(index) => index,
+                    None => {
+                        self.catch_alls.push(CatchAllEdge {
+                            name,
+                            endpoints: Vec::new(),
+                        });
+                        self.catch_alls.len() - 1
+                    }
+                };
+                return &mut self.catch_alls[index].endpoints;
+            }
+        };
+        child.insert(segments)
+    }
+
+    /// The endpoints `insert` would extend for `segments`, without changing
+    /// the tree; empty when the route is new.
+    fn find(&self, segments: &[Segment]) -> &[Endpoint] {
+        let Some((segment, rest)) = segments.split_first() else {
+            return &self.endpoints;
+        };
+        let child = match segment {
+            Segment::Static(text) => self.statics.get(text),
+            Segment::Param { name, converter } => self
+                .params
+                .iter()
+                .find(|e| &e.name == name && e.converter == *converter)
+                .map(|e| &e.node),
+            Segment::Pattern { source, .. } => self
+                .patterns
+                .iter()
+                .find(|e| &e.source == source)
+                .map(|e| &e.node),
+            Segment::CatchAll { name } => {
+                return self
+                    .catch_alls
+                    .iter()
+                    .find(|e| &e.name == name)
+                    .map_or(&[], |e| &e.endpoints);
+            }
+        };
+        child.map_or(&[], |child| child.find(rest))
+    }
+
+    /// Depth-first lookup with backtracking. Every path match whose methods do
+    /// not include `method` contributes to `allowed`.
+    fn walk<'a>(
+        &'a self,
+        rest: &'a str,
+        method: &str,
+        captures: &mut Vec<Capture<'a>>,
+        allowed: &mut BTreeSet<&'a str>,
+    ) -> Option<&'a Endpoint> {
+        let (segment, tail) = match rest.split_once('/') {
+            Some((segment, tail)) => (segment, Some(tail)),
+            None => (rest, None),
+        };
+
+        if let Some(child) = self.statics.get(segment) {
+            if let Some(endpoint) = child.descend(tail, method, captures, allowed) {
+                return Some(endpoint);
+            }
+        }
+
+        for edge in &self.params {
+            if !edge.converter.matches(segment) {
+                continue;
+            }
+            captures.push(Capture {
+                name: &edge.name,
+                converter: edge.converter,
+                value: segment,
+            });
+            if let Some(endpoint) = edge.node.descend(tail, method, captures, allowed) {
+                return Some(endpoint);
+            }
+            captures.pop();
+        }
+
+        for edge in &self.patterns {
+            let Some(caps) = edge.regex.captures(segment) else {
+                continue;
+            };
+            let mark = captures.len();
+            for (name, converter) in &edge.params {
+                if let Some(value) = caps.name(name) {
+                    captures.push(Capture {
+                        name,
+                        converter: *converter,
+                        value: value.as_str(),
+                    });
+                }
+            }
+            if let Some(endpoint) = edge.node.descend(tail, method, captures, allowed) {
+                return Some(endpoint);
+            }
+            captures.truncate(mark);
+        }
+
+        for edge in &self.catch_alls {
+            if let Some(endpoint) = select(&edge.endpoints, method, allowed) {
+                captures.push(Capture {
+                    name: &edge.name,
+                    converter: Converter::Path,
+                    value: rest,
+                });
+                return Some(endpoint);
+            }
+        }
+        None
+    }
+
+    fn descend<'a>(
+        &'a self,
+        tail: Option<&'a str>,
+        method: &str,
+        captures: &mut Vec<Capture<'a>>,
+        allowed: &mut BTreeSet<&'a str>,
+    ) -> Option<&'a Endpoint> {
+        match tail {
+            Some(tail) => self.walk(tail, method, captures, allowed),
+            None => select(&self.endpoints, method, allowed),
+        }
+    }
+}
+
+/// Pick the endpoint for `method`, letting `HEAD` fall back to `GET`.
+fn select<'a>(
+    endpoints: &'a [Endpoint],
+    method: &str,
+    allowed: &mut BTreeSet<&'a str>,
+) -> Option<&'a Endpoint> {
+    let found = endpoints.iter().find(|e| e.method == method).or_else(|| {
+        (method == "HEAD")
+            .then(|| endpoints.iter().find(|e| e.method == "GET"))
+            .flatten()
+    });
+    if found.is_none() {
+        for endpoint in endpoints {
+            allowed.insert(&endpoint.method);
+            if endpoint.method == "GET" {
+                allowed.insert("HEAD");
+            }
+        }
+    }
+    found
+}
+
+/// Outcome of `HaskeApp.resolve`.
+///
+/// `status` is 200 with `handler` and `params` set, 405 with
+/// `allowed_methods` listing what the path accepts, or 404.
+#[pyclass(frozen, get_all)]
+pub struct RouteMatch {
+    status: u16,
+    handler: Option<Py<PyAny>>,
+    params: Py<PyDict>,
+    allowed_methods: Vec<String>,
+}
+
+#[pymethods]
+impl RouteMatch {
+    fn __bool__(&self) -> bool {
+        self.status == 200
+    }
+
+    fn __repr__(&self) -> String {
+        format!("RouteMatch(status={})", self.status)
+    }
+}
+
+/// Registered route, kept for introspection alongside the tree.
+struct RouteInfo {
+    pattern: String,
+    methods: Vec<String>,
+    name: Option<String>,
+}
+
+/// Radix tree HTTP router used by `Haske` to short-circuit Starlette.
+///
+/// Routes use the same syntax as `@app.route`: `/user/<int:id>`,
+/// `/files/<path:rest>`, `/report.<str:fmt>` or `/user/:id`.
+#[pyclass]
+#[derive(Default)]
+pub struct HaskeApp {
+    root: Node,
+    routes: Vec<RouteInfo>,
+    /// Added to every response sent by `dispatch`.
+    default_headers: Vec<(String, String)>,
+}
+
+impl HaskeApp {
+    fn lookup<'a>(
+        &'a self,
+        method: &str,
+        path: &'a str,
+        captures: &mut Vec<Capture<'a>>,
+        allowed: &mut BTreeSet<&'a str>,
+    ) -> Option<&'a Endpoint> {
+        let rest = path.strip_prefix('/')?;
+        self.root
+            .walk(rest, &method.to_ascii_uppercase(), captures, allowed)
+    }
+}
+
+#[pymethods]
+impl HaskeApp {
+    #[new]
+    fn new() -> Self {
+        Self::default()
+    }
+
+    /// Register `handler` for a comma separated list of methods and a route pattern.
+    ///
+    /// `name` makes the route available to `url_path_for`.
+    #[pyo3(signature = (method, path, handler, name=None))]
+    fn add_route(
+        &mut self,
+        py: Python<'_>,
+        method: &str,
+        path: &str,
+        handler: Py<PyAny>,
+        name: Option<String>,
+    ) -> PyResult<()> {
+        let methods: Vec<String> = method
+            .split(',')
+            .map(|m| m.trim().to_ascii_uppercase())
+            .filter(|m| !m.is_empty())
+            .collect();
+        if methods.is_empty() {
+            return Err(PyValueError::new_err(
+                "at least one HTTP method is required",
+            ));
+        }
+
+        let segments = parse_pattern(path)?;
+        let existing = self.root.find(&segments);
+        if let Some(dup) = methods
+            .iter()
+            .find(|m| existing.iter().any(|e| &e.method == *m))
+        {
+            return Err(PyValueError::new_err(format!(
+                "route {dup} {path} is already registered"
+            )));
+        }
+        let endpoints = self.root.insert(segments.into_iter());
+        endpoints.extend(methods.iter().map(|m| Endpoint {
+            method: m.clone(),
+            handler: handler.clone_ref(py),
+        }));
+        self.routes.push(RouteInfo {
+            pattern: path.to_owned(),
+            methods,
+            name,
+        });
+        Ok(())
+    }
+
+    /// Return `(handler, params)` for a matching route, or `None`.
+    fn match_request<'py>(
+        &self,
+        py: Python<'py>,
+        method: &str,
+        path: &str,
+    ) -> PyResult<Option<(Py<PyAny>, Bound<'py, PyDict>)>> {
+        let mut captures = Vec::new();
+        let mut allowed = BTreeSet::new();
+        match self.lookup(method, path, &mut captures, &mut allowed) {
+            Some(endpoint) => Ok(Some((
+                endpoint.handler.clone_ref(py),
+                params_dict(py, &captures)?,
+            ))),
+            None => Ok(None),
+        }
+    }
+
+    /// Resolve a request, distinguishing unknown paths (404) from known paths
+    /// requested with an unsupported method (405).
+    fn resolve(&self, py: Python<'_>, method: &str, path: &str) -> PyResult<RouteMatch> {
+        let mut captures = Vec::new();
+        let mut allowed = BTreeSet::new();
+        let endpoint = self.lookup(method, path, &mut captures, &mut allowed);
+        Ok(match endpoint {
+            Some(endpoint) => RouteMatch {
+                status: 200,
+                handler: Some(endpoint.handler.clone_ref(py)),
+                params: params_dict(py, &captures)?.unbind(),
+                allowed_methods: Vec::new(),
+            },
+            None => RouteMatch {
+                status: if allowed.is_empty() { 404 } else { 405 },
+                handler: None,
+                params: PyDict::new(py).unbind(),
+                allowed_methods: allowed.into_iter().map(str::to_owned).collect(),
+            },
+        })
+    }
+
+    /// ASGI entry point: `await app.dispatch(scope, receive, send)`.
+    ///
+    /// Matched routes are served natively; known paths requested with the
+    /// wrong method get a 405 with `Allow`, except `OPTIONS` so CORS
+    /// preflights reach the middleware. Resolves to `False` when the request
+    /// was not handled. Handler exceptions propagate to the caller.
+    fn dispatch(
+        &self,
+        py: Python<'_>,
+        scope: Bound<'_, PyAny>,
+        receive: Py<PyAny>,
+        send: Py<PyAny>,
+    ) -> PyResult<Dispatch> {
+        let method: String = scope.get_item("method")?.extract()?;
+        let path: String = scope.get_item("path")?.extract()?;
+        let (method, path) = (method.as_str(), path.as_str());
+
+        let mut dispatch = Dispatch::new(
+            scope.clone().unbind(),
+            receive,
+            send,
+            self.default_headers.clone(),
+            method.eq_ignore_ascii_case("HEAD"),
+        );
+        let mut captures = Vec::new();
+        let mut allowed = BTreeSet::new();
+        match self.lookup(method, path, &mut captures, &mut allowed) {
+            Some(endpoint) => dispatch.route(
+                py,
+                endpoint.handler.clone_ref(py),
+                params_dict(py, &captures)?.unbind(),
+            )?,
+            None if !allowed.is_empty() && !method.eq_ignore_ascii_case("OPTIONS") => {
+                let allow = allowed.into_iter().collect::<Vec<_>>().join(", ");
+                dispatch.reply(py, 405, "Method Not Allowed", &[("allow", allow)])?
+            }
+            None => {}
+        }
+        Ok(dispatch)
+    }
+
+    /// Headers added to every response produced by `dispatch`.
+    fn set_default_headers(&mut self, headers: Vec<(String, String)>) {
+        self.default_headers = headers;
+    }
+
+    /// Build the path of the route registered as `name`.
+    ///
+    /// Parameters are formatted by their converter and percent-encoded;
+    /// keyword arguments the pattern does not use become the query string.
+    /// Raises `LookupError` for an unknown name and `ValueError` for missing
+    /// or unconvertible parameters.
+    #[pyo3(signature = (name, /, **params))]
+    fn url_path_for(&self, name: &str, params: Option<&Bound<'_, PyDict>>) -> PyResult<String> {
+        let route = self
+            .routes
+            .iter()
+            .find(|r| r.name.as_deref() == Some(name))
+            .ok_or_else(|| PyLookupError::new_err(format!("no route named '{name}'")))?;
+
+        let mut url = String::with_capacity(route.pattern.len());
+        let mut used = Vec::new();
+        let mut missing = Vec::new();
+        for segment in split_segments(&route.pattern)? {
+            url.push('/');
+            for piece in parse_pieces(segment)? {
+                let (param, converter) = match piece {
+                    Piece::Literal(text) => {
+                        url.push_str(text);
+                        continue;
+                    }
+                    Piece::Param { name, converter } => (name, converter),
+                };
+                let value = match params {
+                    Some(params) => params.get_item(param)?,
+                    None => None,
+                };
+                let Some(value) = value else {
+                    missing.push(param);
+                    continue;
+                };
+                let text = converter.to_string(&value)?;
+                if !converter.matches(&text) {
+                    return Err(PyValueError::new_err(format!(
+                        "value '{text}' for parameter '{param}' of route '{name}' does not match the '{}' converter",
+                        converter.name()
+                    )));
+                }
+                push_encoded(&mut url, &text, converter == Converter::Path);
+                used.push(param);
+            }
+        }
+        if !missing.is_empty() {
+            return Err(PyValueError::new_err(format!(
+                "missing parameters for route '{name}': {}",
+                missing.join(", ")
+            )));
+        }
+
+        let mut separator = '?';
+        for (key, value) in params.into_iter().flat_map(|p| p.iter()) {
+            let key = key.str()?.to_cow()?.into_owned();
+            if used.contains(&key.as_str()) {
+                continue;
+            }
+            let values = if value.is_instance_of::<PyList>() || value.is_instance_of::<PyTuple>() {
+                value.try_iter()?.collect::<PyResult<Vec<_>>>()?
+            } else {
+                vec![value]
+            };
+            for value in values {
+                url.push(separator);
+                separator = '&';
+                push_encoded(&mut url, &key, false);
+                url.push('=');
+                push_encoded(&mut url, &value.str()?.to_cow()?, false);
+            }
+        }
+        Ok(url)
+    }
+
+    /// List registered routes as `(methods, pattern)` pairs.
+    fn routes(&self) -> Vec<(Vec<String>, String)> {
+        self.routes
+            .iter()
+            .map(|r| (r.methods.clone(), r.pattern.clone()))
+            .collect()
+    }
+
+    fn route_count(&self) -> usize {
+        self.routes.len()
+    }
+
+    fn clear_routes(&mut self) {
+        self.root = Node::default();
+        self.routes.clear();
+    }
+}
+
+fn params_dict<'py>(py: Python<'py>, captures: &[Capture<'_>]) -> PyResult<Bound<'py, PyDict>> {
+    let params = PyDict::new(py);
+    for capture in captures {
+        params.set_item(
+            capture.name,
+            capture.converter.to_python(py, capture.value)?,
+        )?;
+    }
+    Ok(params)
+}
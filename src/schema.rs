@@ -0,0 +1,135 @@
+use std::collections::HashMap;
+use std::sync::{Arc, LazyLock};
+
+use jsonschema::Validator;
+use parking_lot::RwLock;
+use pyo3::create_exception;
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+use pyo3::types::{PyBytes, PyDict, PyList, PyString};
+use serde_json::Value;
+
+use crate::json::py_to_value;
+
+create_exception!(
+    haske,
+    SchemaError,
+    PyValueError,
+    "Raised when a JSON Schema document cannot be compiled."
+);
+
+/// Schema JSON text and the `validate_formats` flag it was compiled with.
+type SchemaKey = (String, bool);
+
+/// Compiled schemas kept by `compile_schema`.
+static COMPILED: LazyLock<RwLock<HashMap<SchemaKey, Arc<Validator>>>> =
+    LazyLock::new(|| RwLock::new(HashMap::new()));
+
+/// Upper bound on `COMPILED`; the cache starts over once it is reached.
+const MAX_COMPILED: usize = 256;
+
+/// A JSON Schema (draft 2020-12) compiled once and reused for every validation.
+///
+/// `validate` takes the raw request body and returns a list of errors, each a
+/// dict with `pointer` (JSON pointer into the instance), `keyword` (the
+/// failing schema keyword) and `message`. An empty list means valid.
+#[pyclass(frozen, module = "haske")]
+pub struct JsonSchema {
+    validator: Arc<Validator>,
+}
+
+#[pymethods]
+impl JsonSchema {
+    /// Compile `schema`, given as a dict/bool or as JSON text.
+    ///
+    /// `format` is only asserted when `validate_formats` is set, as the
+    /// specification leaves it an annotation by default.
+    #[new]
+    #[pyo3(signature = (schema, validate_formats=false))]
+    fn new(schema: &Bound<'_, PyAny>, validate_formats: bool) -> PyResult<Self> {
+        Ok(Self {
+            validator: compile(&schema_value(schema)?, validate_formats)?,
+        })
+    }
+
+    /// Validate a JSON document given as `bytes` or `str`.
+    ///
+    /// Raises `ValueError` if the document is not valid JSON.
+    fn validate<'py>(&self, data: &Bound<'py, PyAny>) -> PyResult<Bound<'py, PyList>> {
+        let instance = parse_document(data)?;
+        errors_to_py(data.py(), &self.validator, &instance)
+    }
+
+    /// Validate an already decoded Python object.
+    fn validate_python<'py>(&self, obj: &Bound<'py, PyAny>) -> PyResult<Bound<'py, PyList>> {
+        errors_to_py(obj.py(), &self.validator, &py_to_value(obj)?)
+    }
+
+    /// Whether a JSON document (`bytes` or `str`) is valid; invalid JSON is not.
+    fn is_valid(&self, data: &Bound<'_, PyAny>) -> bool {
+        parse_document(data).is_ok_and(|instance| self.validator.is_valid(&instance))
+    }
+}
+
+/// Compile `schema`, reusing an earlier compilation of an identical schema.
+///
+/// Lets callers such as `Request.validate_json` pass plain dicts on every
+/// request without paying for compilation each time.
+#[pyfunction]
+#[pyo3(signature = (schema, validate_formats=false))]
+pub fn compile_schema(schema: &Bound<'_, PyAny>, validate_formats: bool) -> PyResult<JsonSchema> {
+    let value = schema_value(schema)?;
+    let key = (value.to_string(), validate_formats);
+    if let Some(validator) = COMPILED.read().get(&key) {
+        return Ok(JsonSchema {
+            validator: Arc::clone(validator),
+        });
+    }
+    let validator = compile(&value, validate_formats)?;
+    let mut compiled = COMPILED.write();
+    if compiled.len() >= MAX_COMPILED {
+        compiled.clear();
+    }
+    compiled.insert(key, Arc::clone(&validator));
+    Ok(JsonSchema { validator })
+}
+
+fn schema_value(schema: &Bound<'_, PyAny>) -> PyResult<Value> {
+    if schema.is_instance_of::<PyString>() || schema.is_instance_of::<PyBytes>() {
+        parse_document(schema).map_err(|e| SchemaError::new_err(e.to_string()))
+    } else {
+        py_to_value(schema)
+    }
+}
+
+fn compile(schema: &Value, validate_formats: bool) -> PyResult<Arc<Validator>> {
+    jsonschema::draft202012::options()
+        .should_validate_formats(validate_formats)
+        .build(schema)
+        .map(Arc::new)
+        .map_err(|e| SchemaError::new_err(format!("invalid schema: {e}")))
+}
+
+fn parse_document(data: &Bound<'_, PyAny>) -> PyResult<Value> {
+    let parsed = match data.downcast::<PyBytes>() {
+        Ok(bytes) => serde_json::from_slice(bytes.as_bytes()),
+        Err(_) => serde_json::from_str(&data.downcast::<PyString>()?.to_cow()?),
+    };
+    parsed.map_err(|e| PyValueError::new_err(format!("invalid JSON: {e}")))
+}
+
+fn errors_to_py<'py>(
+    py: Python<'py>,
+    validator: &Validator,
+    instance: &Value,
+) -> PyResult<Bound<'py, PyList>> {
+    let errors = PyList::empty(py);
+    for error in validator.iter_errors(instance) {
+        let entry = PyDict::new(py);
+        entry.set_item("pointer", error.instance_path().as_str())?;
+        entry.set_item("keyword", error.kind().keyword())?;
+        entry.set_item("message", error.to_string())?;
+        errors.append(entry)?;
+    }
+    Ok(errors)
+}
@@ -0,0 +1,605 @@
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use parking_lot::Mutex;
+use pyo3::create_exception;
+use pyo3::exceptions::{PyRuntimeError, PyTypeError, PyValueError};
+use pyo3::prelude::*;
+use pyo3::types::PyDict;
+use rusqlite::{params, Connection, OptionalExtension};
+
+use crate::crypto::random_bytes;
+use crate::json::{py_to_value, value_to_py};
+
+create_exception!(
+    haske,
+    SessionStoreError,
+    PyRuntimeError,
+    "Raised when a session store backend fails, e.g. on a database error."
+);
+
+/// Bytes of randomness in a session id.
+const SESSION_ID_LEN: usize = 32;
+
+/// The memory backend sweeps expired records once it holds this many.
+const MIN_SWEEP_SIZE: usize = 1024;
+
+type BackendResult<T> = Result<T, String>;
+
+/// A stored session; times are seconds since the Unix epoch.
+struct Record {
+    user_id: Option<String>,
+    data: String,
+    expires_at: f64,
+    touched_at: f64,
+}
+
+/// Storage for session records. Expired records are never returned, and
+/// `update`/`touch` leave missing or expired ones alone.
+trait Backend: Send + Sync {
+    fn name(&self) -> &'static str;
+    fn insert(&self, id: &str, record: Record) -> BackendResult<()>;
+    fn load(&self, id: &str, now: f64) -> BackendResult<Option<Record>>;
+    fn touch(&self, id: &str, expires_at: f64, now: f64) -> BackendResult<bool>;
+    fn update(&self, id: &str, data: &str, expires_at: f64, now: f64) -> BackendResult<bool>;
+    fn remove(&self, id: &str) -> BackendResult<bool>;
+    fn remove_user(&self, user_id: &str) -> BackendResult<usize>;
+    fn user_sessions(&self, user_id: &str, now: f64) -> BackendResult<Vec<String>>;
+    fn purge(&self, now: f64) -> BackendResult<usize>;
+    fn count(&self, now: f64) -> BackendResult<usize>;
+}
+
+struct MemoryInner {
+    records: HashMap<String, Record>,
+    by_user: HashMap<String, HashSet<String>>,
+    /// Record count that triggers the next sweep of expired records.
+    sweep_at: usize,
+}
+
+impl Default for MemoryInner {
+    fn default() -> Self {
+        Self {
+            records: HashMap::new(),
+            by_user: HashMap::new(),
+            sweep_at: MIN_SWEEP_SIZE,
+        }
+    }
+}
+
+impl MemoryInner {
+    fn remove(&mut self, id: &str) -> bool {
+        let Some(record) = self.records.remove(id) else {
+            return false;
+        };
+        if let Some(user_id) = record.user_id {
+            if let Some(ids) = self.by_user.get_mut(&user_id) {
+                ids.remove(id);
+                if ids.is_empty() {
+                    self.by_user.remove(&user_id);
+                }
+            }
+        }
+        true
+    }
+
+    fn live(&mut self, id: &str, now: f64) -> Option<&mut Record> {
+        if self.records.get(id)?.expires_at <= now {
+            self.remove(id);
+            return None;
+        }
+        self.records.get_mut(id)
+    }
+
+    fn purge(&mut self, now: f64) -> usize {
+        let expired: Vec<String> = self
+            .records
+            .iter()
+            .filter(|(_, record)| record.expires_at <= now)
+            .map(|(id, _)| id.clone())
+            .collect();
+        for id in &expired {
+            self.remove(id);
+        }
+        expired.len()
+    }
+}
+
+/// Sessions in a process-local map; lost on restart and not shared between
+/// workers. Sessions that are never read again are swept out once the map
+/// has doubled since the last sweep, so it stays within about twice the
+/// live sessions.
+#[derive(Default)]
+struct MemoryBackend {
+    inner: Mutex<MemoryInner>,
+}
+
+impl Backend for MemoryBackend {
+    fn name(&self) -> &'static str {
+        "memory"
+    }
+
+    fn insert(&self, id: &str, record: Record) -> BackendResult<()> {
+        let mut inner = self.inner.lock();
+        if inner.records.len() >= inner.sweep_at {
+            inner.purge(record.touched_at);
+            inner.sweep_at = (inner.records.len() * 2).max(MIN_SWEEP_SIZE);
+        }
+        if let Some(user_id) = &record.user_id {
+            inner
+                .by_user
+                .entry(user_id.clone())
+                .or_default()
+                .insert(id.to_owned());
+        }
+        inner.records.insert(id.to_owned(), record);
+        Ok(())
+    }
+
+    fn load(&self, id: &str, now: f64) -> BackendResult<Option<Record>> {
+        Ok(self.inner.lock().live(id, now).map(|record| Record {
+            user_id: record.user_id.clone(),
+            data: record.data.clone(),
+            ..*record
+        }))
+    }
+
+    fn touch(&self, id: &str, expires_at: f64, now: f64) -> BackendResult<bool> {
+        let mut inner = self.inner.lock();
+        let Some(record) = inner.live(id, now) else {
+            return Ok(false);
+        };
+        record.expires_at = expires_at;
+        record.touched_at = now;
+        Ok(true)
+    }
+
+    fn update(&self, id: &str, data: &str, expires_at: f64, now: f64) -> BackendResult<bool> {
+        let mut inner = self.inner.lock();
+        let Some(record) = inner.live(id, now) else {
+            return Ok(false);
+        };
+        record.data = data.to_owned();
+        record.expires_at = expires_at;
+        record.touched_at = now;
+        Ok(true)
+    }
+
+    fn remove(&self, id: &str) -> BackendResult<bool> {
+        Ok(self.inner.lock().remove(id))
+    }
+
+    fn remove_user(&self, user_id: &str) -> BackendResult<usize> {
+        let mut inner = self.inner.lock();
+        let ids = inner.by_user.remove(user_id).unwrap_or_default();
+        for id in &ids {
+            inner.records.remove(id);
+        }
+        Ok(ids.len())
+    }
+
+    fn user_sessions(&self, user_id: &str, now: f64) -> BackendResult<Vec<String>> {
+        let inner = self.inner.lock();
+        let Some(ids) = inner.by_user.get(user_id) else {
+            return Ok(Vec::new());
+        };
+        Ok(ids
+            .iter()
+            .filter(|id| inner.records.get(*id).is_some_and(|r| r.expires_at > now))
+            .cloned()
+            .collect())
+    }
+
+    fn purge(&self, now: f64) -> BackendResult<usize> {
+        Ok(self.inner.lock().purge(now))
+    }
+
+    fn count(&self, now: f64) -> BackendResult<usize> {
+        let inner = self.inner.lock();
+        Ok(inner
+            .records
+            .values()
+            .filter(|record| record.expires_at > now)
+            .count())
+    }
+}
+
+/// Sessions in a SQLite database file, shared by every worker on the host
+/// and kept across restarts. Expired rows are skipped on read and deleted
+/// by `purge`.
+struct SqliteBackend {
+    conn: Mutex<Connection>,
+}
+
+impl SqliteBackend {
+    fn open(path: &Path) -> BackendResult<Self> {
+        let conn = Connection::open(path).map_err(db_error)?;
+        conn.busy_timeout(std::time::Duration::from_secs(5))
+            .map_err(db_error)?;
+        conn.execute_batch(
+            "PRAGMA journal_mode = WAL;
+             PRAGMA synchronous = NORMAL;
+             CREATE TABLE IF NOT EXISTS haske_sessions (
+                 id TEXT PRIMARY KEY,
+                 user_id TEXT,
+                 data TEXT NOT NULL,
+                 expires_at REAL NOT NULL,
+                 touched_at REAL NOT NULL
+             );
+             CREATE INDEX IF NOT EXISTS haske_sessions_user ON haske_sessions (user_id);
+             CREATE INDEX IF NOT EXISTS haske_sessions_expiry ON haske_sessions (expires_at);",
+        )
+        .map_err(db_error)?;
+        Ok(Self {
+            conn: Mutex::new(conn),
+        })
+    }
+}
+
+impl Backend for SqliteBackend {
+    fn name(&self) -> &'static str {
+        "sqlite"
+    }
+
+    fn insert(&self, id: &str, record: Record) -> BackendResult<()> {
+        self.conn
+            .lock()
+            .execute(
+                "INSERT INTO haske_sessions (id, user_id, data, expires_at, touched_at)
+                 VALUES (?1, ?2, ?3, ?4, ?5)",
+                params![
+                    id,
+                    record.user_id,
+                    record.data,
+                    record.expires_at,
+                    record.touched_at
+                ],
+            )
+            .map(drop)
+            .map_err(db_error)
+    }
+
+    fn load(&self, id: &str, now: f64) -> BackendResult<Option<Record>> {
+        self.conn
+            .lock()
+            .query_row(
+                "SELECT user_id, data, expires_at, touched_at FROM haske_sessions
+                 WHERE id = ?1 AND expires_at > ?2",
+                params![id, now],
+                |row| {
+                    Ok(Record {
+                        user_id: row.get(0)?,
+                        data: row.get(1)?,
+                        expires_at: row.get(2)?,
+                        touched_at: row.get(3)?,
+                    })
+                },
+            )
+            .optional()
+            .map_err(db_error)
+    }
+
+    fn touch(&self, id: &str, expires_at: f64, now: f64) -> BackendResult<bool> {
+        self.conn
+            .lock()
+            .execute(
+                "UPDATE haske_sessions SET expires_at = ?2, touched_at = ?3
+                 WHERE id = ?1 AND expires_at > ?3",
+                params![id, expires_at, now],
+            )
+            .map(|changed| changed > 0)
+            .map_err(db_error)
+    }
+
+    fn update(&self, id: &str, data: &str, expires_at: f64, now: f64) -> BackendResult<bool> {
+        self.conn
+            .lock()
+            .execute(
+                "UPDATE haske_sessions SET data = ?2, expires_at = ?3, touched_at = ?4
+                 WHERE id = ?1 AND expires_at > ?4",
+                params![id, data, expires_at, now],
+            )
+            .map(|changed| changed > 0)
+            .map_err(db_error)
+    }
+
+    fn remove(&self, id: &str) -> BackendResult<bool> {
+        self.conn
+            .lock()
+            .execute("DELETE FROM haske_sessions WHERE id = ?1", params![id])
+            .map(|changed| changed > 0)
+            .map_err(db_error)
+    }
+
+    fn remove_user(&self, user_id: &str) -> BackendResult<usize> {
+        self.conn
+            .lock()
+            .execute(
+                "DELETE FROM haske_sessions WHERE user_id = ?1",
+                params![user_id],
+            )
+            .map_err(db_error)
+    }
+
+    fn user_sessions(&self, user_id: &str, now: f64) -> BackendResult<Vec<String>> {
+        let conn = self.conn.lock();
+        let mut stmt = conn
+            .prepare_cached("SELECT id FROM haske_sessions WHERE user_id = ?1 AND expires_at > ?2")
+            .map_err(db_error)?;
+        let ids = stmt
+            .query_map(params![user_id, now], |row| row.get(0))
+            .map_err(db_error)?;
+        ids.collect::<Result<_, _>>().map_err(db_error)
+    }
+
+    fn purge(&self, now: f64) -> BackendResult<usize> {
+        self.conn
+            .lock()
+            .execute(
+                "DELETE FROM haske_sessions WHERE expires_at <= ?1",
+                params![now],
+            )
+            .map_err(db_error)
+    }
+
+    fn count(&self, now: f64) -> BackendResult<usize> {
+        self.conn
+            .lock()
+            .query_row(
+                "SELECT COUNT(*) FROM haske_sessions WHERE expires_at > ?1",
+                params![now],
+                |row| row.get(0),
+            )
+            .map_err(db_error)
+    }
+}
+
+fn db_error(error: rusqlite::Error) -> String {
+    format!("session database error: {error}")
+}
+
+/// A session loaded from a `SessionStore`.
+///
+/// Mutate `data` in place and pass the session to `SessionStore.save`; the
+/// store only writes it back if the data changed since it was loaded.
+#[pyclass(module = "haske")]
+pub struct Session {
+    #[pyo3(get)]
+    id: String,
+    #[pyo3(get)]
+    user_id: Option<String>,
+    #[pyo3(get)]
+    data: Py<PyDict>,
+    #[pyo3(get)]
+    expires_at: f64,
+    /// Serialized data as last read from or written to the store.
+    stored: String,
+}
+
+#[pymethods]
+impl Session {
+    /// Whether `data` differs from the stored copy.
+    #[getter]
+    fn modified(&self, py: Python<'_>) -> PyResult<bool> {
+        Ok(serialize(self.data.bind(py))? != self.stored)
+    }
+
+    fn __repr__(&self) -> String {
+        format!(
+            "Session(id='{}', user_id={})",
+            self.id,
+            self.user_id
+                .as_deref()
+                .map_or_else(|| "None".to_owned(), |id| format!("'{id}'"))
+        )
+    }
+}
+
+/// Server-side session storage with sliding expiration.
+///
+/// Each access pushes the expiry `ttl` seconds into the future; to keep reads
+/// cheap the new expiry is only written once `touch_interval` seconds have
+/// passed since the last write. Sessions can be tied to a user id, which
+/// `revoke_user` uses to end all of that user's sessions at once. User ids
+/// are stored as strings.
+#[pyclass(frozen, module = "haske")]
+pub struct SessionStore {
+    backend: Box<dyn Backend>,
+    ttl: f64,
+    touch_interval: f64,
+}
+
+impl SessionStore {
+    fn with_backend(backend: Box<dyn Backend>, ttl: f64, touch_interval: f64) -> PyResult<Self> {
+        if ttl <= 0.0 || touch_interval < 0.0 {
+            return Err(PyValueError::new_err(
+                "ttl must be positive and touch_interval non-negative",
+            ));
+        }
+        Ok(Self {
+            backend,
+            ttl,
+            touch_interval,
+        })
+    }
+}
+
+#[pymethods]
+impl SessionStore {
+    /// Store sessions in this process's memory.
+    #[staticmethod]
+    #[pyo3(signature = (ttl=3600.0, touch_interval=60.0))]
+    fn memory(ttl: f64, touch_interval: f64) -> PyResult<Self> {
+        Self::with_backend(Box::<MemoryBackend>::default(), ttl, touch_interval)
+    }
+
+    /// Store sessions in the SQLite database at `path`, creating it if needed.
+    #[staticmethod]
+    #[pyo3(signature = (path, ttl=3600.0, touch_interval=60.0))]
+    fn sqlite(py: Python<'_>, path: PathBuf, ttl: f64, touch_interval: f64) -> PyResult<Self> {
+        let backend = py
+            .detach(|| SqliteBackend::open(&path))
+            .map_err(SessionStoreError::new_err)?;
+        Self::with_backend(Box::new(backend), ttl, touch_interval)
+    }
+
+    #[getter]
+    fn backend(&self) -> &'static str {
+        self.backend.name()
+    }
+
+    #[getter]
+    fn ttl(&self) -> f64 {
+        self.ttl
+    }
+
+    /// Start a new session holding `data`, optionally owned by `user_id`.
+    #[pyo3(signature = (data=None, user_id=None))]
+    fn create(
+        &self,
+        py: Python<'_>,
+        data: Option<Bound<'_, PyDict>>,
+        user_id: Option<&Bound<'_, PyAny>>,
+    ) -> PyResult<Session> {
+        let data = data.unwrap_or_else(|| PyDict::new(py));
+        let stored = serialize(&data)?;
+        let user_id = user_id.map(user_key).transpose()?;
+        let id = URL_SAFE_NO_PAD.encode(random_bytes(SESSION_ID_LEN));
+        let now = now();
+        let record = Record {
+            user_id: user_id.clone(),
+            data: stored.clone(),
+            expires_at: now + self.ttl,
+            touched_at: now,
+        };
+        py.detach(|| self.backend.insert(&id, record))
+            .map_err(SessionStoreError::new_err)?;
+        Ok(Session {
+            id,
+            user_id,
+            data: data.unbind(),
+            expires_at: now + self.ttl,
+            stored,
+        })
+    }
+
+    /// Load a live session, extending its expiry; `None` if it does not exist,
+    /// expired or was revoked.
+    fn get(&self, py: Python<'_>, session_id: &str) -> PyResult<Option<Session>> {
+        let now = now();
+        let loaded = py.detach(|| -> BackendResult<Option<Record>> {
+            let Some(mut record) = self.backend.load(session_id, now)? else {
+                return Ok(None);
+            };
+            if now - record.touched_at >= self.touch_interval {
+                record.expires_at = now + self.ttl;
+                if !self.backend.touch(session_id, record.expires_at, now)? {
+                    return Ok(None);
+                }
+            }
+            Ok(Some(record))
+        });
+        let Some(record) = loaded.map_err(SessionStoreError::new_err)? else {
+            return Ok(None);
+        };
+        let value: serde_json::Value = serde_json::from_str(&record.data)
+            .map_err(|e| SessionStoreError::new_err(format!("corrupt session data: {e}")))?;
+        let data = value_to_py(py, &value)?
+            .downcast_into::<PyDict>()
+            .map_err(|_| SessionStoreError::new_err("corrupt session data: not an object"))?;
+        Ok(Some(Session {
+            id: session_id.to_owned(),
+            user_id: record.user_id,
+            data: data.unbind(),
+            expires_at: record.expires_at,
+            stored: record.data,
+        }))
+    }
+
+    /// Write `session` back if its data changed, also extending its expiry.
+    ///
+    /// Returns whether anything was written; a revoked or expired session is
+    /// not brought back.
+    fn save(&self, session: &Bound<'_, Session>) -> PyResult<bool> {
+        let py = session.py();
+        let mut session = session.borrow_mut();
+        let data = serialize(session.data.bind(py))?;
+        if data == session.stored {
+            return Ok(false);
+        }
+        let now = now();
+        let expires_at = now + self.ttl;
+        let id = session.id.clone();
+        let written = py
+            .detach(|| self.backend.update(&id, &data, expires_at, now))
+            .map_err(SessionStoreError::new_err)?;
+        if written {
+            session.stored = data;
+            session.expires_at = expires_at;
+        }
+        Ok(written)
+    }
+
+    /// End one session; returns whether it existed.
+    fn revoke(&self, py: Python<'_>, session_id: &str) -> PyResult<bool> {
+        py.detach(|| self.backend.remove(session_id))
+            .map_err(SessionStoreError::new_err)
+    }
+
+    /// End every session of `user_id` ("log out everywhere"); returns how
+    /// many were removed.
+    fn revoke_user(&self, user_id: &Bound<'_, PyAny>) -> PyResult<usize> {
+        let key = user_key(user_id)?;
+        user_id
+            .py()
+            .detach(|| self.backend.remove_user(&key))
+            .map_err(SessionStoreError::new_err)
+    }
+
+    /// Ids of the live sessions of `user_id`.
+    fn user_sessions(&self, user_id: &Bound<'_, PyAny>) -> PyResult<Vec<String>> {
+        let key = user_key(user_id)?;
+        user_id
+            .py()
+            .detach(|| self.backend.user_sessions(&key, now()))
+            .map_err(SessionStoreError::new_err)
+    }
+
+    /// Delete expired sessions; returns how many were removed.
+    fn purge_expired(&self, py: Python<'_>) -> PyResult<usize> {
+        py.detach(|| self.backend.purge(now()))
+            .map_err(SessionStoreError::new_err)
+    }
+
+    fn __len__(&self, py: Python<'_>) -> PyResult<usize> {
+        py.detach(|| self.backend.count(now()))
+            .map_err(SessionStoreError::new_err)
+    }
+
+    fn __repr__(&self) -> String {
+        format!(
+            "SessionStore(backend='{}', ttl={})",
+            self.backend.name(),
+            self.ttl
+        )
+    }
+}
+
+fn serialize(data: &Bound<'_, PyDict>) -> PyResult<String> {
+    Ok(py_to_value(data.as_any())?.to_string())
+}
+
+fn user_key(user_id: &Bound<'_, PyAny>) -> PyResult<String> {
+    if user_id.is_none() {
+        return Err(PyTypeError::new_err("user_id must not be None"));
+    }
+    Ok(user_id.str()?.to_cow()?.into_owned())
+}
+
+fn now() -> f64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0.0, |elapsed| elapsed.as_secs_f64())
+}
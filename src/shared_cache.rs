@@ -0,0 +1,568 @@
+use std::fs::{File, OpenOptions};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU32, AtomicU64, Ordering};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use memmap2::MmapMut;
+use pyo3::exceptions::{PyPermissionError, PyRuntimeError, PyValueError};
+use pyo3::prelude::*;
+use pyo3::types::PyBytes;
+
+/// Identifies a segment and its layout version.
+const MAGIC: u64 = u64::from_le_bytes(*b"HKSHMC01");
+
+/// Segment header: magic, init state, geometry and the LRU clock.
+const HEADER: usize = 64;
+const MAGIC_AT: usize = 0;
+const STATE_AT: usize = 8;
+const STRIPES_AT: usize = 12;
+const SLOTS_AT: usize = 16;
+const SLOT_SIZE_AT: usize = 20;
+const CLOCK_AT: usize = 24;
+
+/// Each stripe starts with its lock (the holder's pid, 0 when free) on a
+/// cache line of its own.
+const STRIPE_HEADER: usize = 64;
+
+/// Slot header: key hash (0 when empty), expiry as Unix time, last use on
+/// the LRU clock, key and value lengths. Key and value bytes follow.
+const SLOT_HEADER: usize = 32;
+const HASH_AT: usize = 0;
+const EXPIRES_AT: usize = 8;
+const USED_AT: usize = 16;
+const KEY_LEN_AT: usize = 24;
+const VALUE_LEN_AT: usize = 28;
+
+const MAX_STRIPES: usize = 64;
+
+const UNINITIALIZED: u32 = 0;
+const INITIALIZING: u32 = 1;
+const READY: u32 = 2;
+
+/// How long to wait for another process to initialize a new segment.
+const INIT_TIMEOUT: Duration = Duration::from_secs(5);
+
+fn now() -> f64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0.0, |elapsed| elapsed.as_secs_f64())
+}
+
+/// FNV-1a, so every process hashes a key the same way; never 0, which
+/// marks an empty slot.
+fn hash(key: &[u8]) -> u64 {
+    let hash = key.iter().fold(0xcbf2_9ce4_8422_2325_u64, |hash, &byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3)
+    });
+    hash.max(1)
+}
+
+/// Whether process `pid` still exists, to recover a lock it died holding.
+#[cfg(unix)]
+fn alive(pid: u32) -> bool {
+    // SAFETY: signal 0 only checks that the process exists.
+    let status = unsafe { libc::kill(pid as libc::pid_t, 0) };
+    status == 0 || std::io::Error::last_os_error().raw_os_error() != Some(libc::ESRCH)
+}
+
+#[cfg(not(unix))]
+fn alive(_pid: u32) -> bool {
+    true
+}
+
+/// Refuse a segment another user created or can read or write: its values
+/// are unpickled, so whoever can write them can run code in this process.
+#[cfg(unix)]
+fn check_private(file: &File, path: &Path) -> PyResult<()> {
+    use std::os::unix::fs::MetadataExt;
+    let metadata = file.metadata()?;
+    // SAFETY: geteuid has no preconditions and cannot fail.
+    let euid = unsafe { libc::geteuid() };
+    if metadata.uid() != euid || metadata.mode() & 0o077 != 0 {
+        return Err(PyPermissionError::new_err(format!(
+            "shared cache segment '{}' is owned by another user or accessible to \
+             others; remove it or choose another name",
+            path.display()
+        )));
+    }
+    Ok(())
+}
+
+#[cfg(not(unix))]
+fn check_private(_file: &File, _path: &Path) -> PyResult<()> {
+    Ok(())
+}
+
+fn segment_path(name: &str) -> PyResult<PathBuf> {
+    if name.is_empty()
+        || name.starts_with('.')
+        || !name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '.'))
+    {
+        return Err(PyValueError::new_err(format!(
+            "invalid shared cache name '{name}': use letters, digits, '_', '-' and '.'"
+        )));
+    }
+    let shm = PathBuf::from("/dev/shm");
+    let dir = if shm.is_dir() {
+        shm
+    } else {
+        std::env::temp_dir()
+    };
+    Ok(dir.join(format!("haske-cache-{name}")))
+}
+
+/// Holds a stripe's lock until dropped.
+struct StripeGuard<'a>(&'a AtomicU32);
+
+impl Drop for StripeGuard<'_> {
+    fn drop(&mut self) {
+        self.0.store(0, Ordering::Release);
+    }
+}
+
+/// A mapped segment: a header and `stripes` stripes of `slots` fixed-size
+/// slots, each guarded by the stripe's spinlock.
+struct Segment {
+    /// Keeps the mapping alive; accessed through `base`.
+    _map: MmapMut,
+    base: *mut u8,
+    stripes: usize,
+    slots: usize,
+    slot_size: usize,
+    stride: usize,
+}
+
+// SAFETY: the mapping lives as long as the segment, and every access to it
+// goes through atomics or happens under a stripe lock.
+unsafe impl Send for Segment {}
+unsafe impl Sync for Segment {}
+
+impl Segment {
+    fn size(stripes: usize, slots: usize, stride: usize) -> usize {
+        HEADER + stripes * (STRIPE_HEADER + slots * stride)
+    }
+
+    fn open(path: &Path, stripes: usize, slots: usize, slot_size: usize) -> PyResult<Self> {
+        let stride = (SLOT_HEADER + slot_size).next_multiple_of(8);
+        let size = Self::size(stripes, slots, stride);
+        let mut options = OpenOptions::new();
+        options.read(true).write(true).create(true).truncate(false);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::OpenOptionsExt;
+            options.mode(0o600).custom_flags(libc::O_NOFOLLOW);
+        }
+        let file = options.open(path)?;
+        check_private(&file, path)?;
+        let len = file.metadata()?.len();
+        if len == 0 {
+            file.set_len(size as u64)?;
+        } else if len != size as u64 {
+            return Err(Self::mismatch(path));
+        }
+        // SAFETY: the file is only written through this type, which checks
+        // the layout before use; other processes map it the same way.
+        let mut map = unsafe { MmapMut::map_mut(&file)? };
+        let segment = Self {
+            base: map.as_mut_ptr(),
+            _map: map,
+            stripes,
+            slots,
+            slot_size,
+            stride,
+        };
+        segment.initialize(path)?;
+        Ok(segment)
+    }
+
+    /// Write the header of a new segment, or wait for the process writing
+    /// it, then check the layout matches ours.
+    fn initialize(&self, path: &Path) -> PyResult<()> {
+        let state = self.u32_at(STATE_AT);
+        if state
+            .compare_exchange(
+                UNINITIALIZED,
+                INITIALIZING,
+                Ordering::AcqRel,
+                Ordering::Acquire,
+            )
+            .is_ok()
+        {
+            self.u64_at(MAGIC_AT).store(MAGIC, Ordering::Relaxed);
+            self.u32_at(STRIPES_AT)
+                .store(self.stripes as u32, Ordering::Relaxed);
+            self.u32_at(SLOTS_AT)
+                .store(self.slots as u32, Ordering::Relaxed);
+            self.u32_at(SLOT_SIZE_AT)
+                .store(self.slot_size as u32, Ordering::Relaxed);
+            state.store(READY, Ordering::Release);
+        }
+        let started = Instant::now();
+        while state.load(Ordering::Acquire) != READY {
+            if started.elapsed() > INIT_TIMEOUT {
+                return Err(PyRuntimeError::new_err(format!(
+                    "shared cache segment '{}' was never initialized",
+                    path.display()
+                )));
+            }
+            std::thread::yield_now();
+        }
+        let matches = self.u64_at(MAGIC_AT).load(Ordering::Relaxed) == MAGIC
+            && self.u32_at(STRIPES_AT).load(Ordering::Relaxed) as usize == self.stripes
+            && self.u32_at(SLOTS_AT).load(Ordering::Relaxed) as usize == self.slots
+            && self.u32_at(SLOT_SIZE_AT).load(Ordering::Relaxed) as usize == self.slot_size;
+        if !matches {
+            return Err(Self::mismatch(path));
+        }
+        Ok(())
+    }
+
+    fn mismatch(path: &Path) -> PyErr {
+        PyValueError::new_err(format!(
+            "shared cache segment '{}' has another layout; remove it with SharedCache.unlink() \
+             or use the same max_entries and slot_size in every process",
+            path.display()
+        ))
+    }
+
+    fn u32_at(&self, offset: usize) -> &AtomicU32 {
+        // SAFETY: callers pass 4-aligned offsets inside the mapping, which
+        // is page-aligned, and the word is only ever accessed atomically.
+        unsafe { &*self.base.add(offset).cast::<AtomicU32>() }
+    }
+
+    fn u64_at(&self, offset: usize) -> &AtomicU64 {
+        // SAFETY: as for `u32_at`, with 8-aligned offsets.
+        unsafe { &*self.base.add(offset).cast::<AtomicU64>() }
+    }
+
+    fn stripe_at(&self, stripe: usize) -> usize {
+        HEADER + stripe * (STRIPE_HEADER + self.slots * self.stride)
+    }
+
+    fn slot_at(&self, stripe: usize, slot: usize) -> usize {
+        self.stripe_at(stripe) + STRIPE_HEADER + slot * self.stride
+    }
+
+    /// Take the stripe's lock, taking it over from a process that died
+    /// holding it.
+    fn lock(&self, stripe: usize) -> StripeGuard<'_> {
+        let lock = self.u32_at(self.stripe_at(stripe));
+        let pid = std::process::id();
+        let mut spins = 0u32;
+        loop {
+            match lock.compare_exchange_weak(0, pid, Ordering::Acquire, Ordering::Relaxed) {
+                Ok(_) => return StripeGuard(lock),
+                Err(owner) => {
+                    spins = spins.wrapping_add(1);
+                    if spins < 64 {
+                        std::hint::spin_loop();
+                        continue;
+                    }
+                    if spins.is_multiple_of(1024)
+                        && owner != 0
+                        && owner != pid
+                        && !alive(owner)
+                        && lock
+                            .compare_exchange(owner, pid, Ordering::Acquire, Ordering::Relaxed)
+                            .is_ok()
+                    {
+                        return StripeGuard(lock);
+                    }
+                    std::thread::yield_now();
+                }
+            }
+        }
+    }
+
+    fn stripe_of(&self, hash: u64) -> usize {
+        (hash % self.stripes as u64) as usize
+    }
+
+    fn data(&self, slot: usize, start: usize, len: usize) -> Vec<u8> {
+        let mut data = vec![0; len];
+        // SAFETY: the range lies in the slot's data area (lengths are
+        // bounded by `slot_size` when written) and the stripe is locked.
+        unsafe {
+            std::ptr::copy_nonoverlapping(
+                self.base.add(slot + SLOT_HEADER + start),
+                data.as_mut_ptr(),
+                len,
+            );
+        }
+        data
+    }
+
+    fn lengths(&self, slot: usize) -> (usize, usize) {
+        let key_len = self.u32_at(slot + KEY_LEN_AT).load(Ordering::Relaxed) as usize;
+        let value_len = self.u32_at(slot + VALUE_LEN_AT).load(Ordering::Relaxed) as usize;
+        if key_len + value_len > self.slot_size {
+            // Only a torn write from a process that died mid-`set` gets here.
+            return (0, 0);
+        }
+        (key_len, value_len)
+    }
+
+    fn live(&self, slot: usize, now: f64) -> bool {
+        self.u64_at(slot + HASH_AT).load(Ordering::Relaxed) != 0
+            && f64::from_bits(self.u64_at(slot + EXPIRES_AT).load(Ordering::Relaxed)) > now
+    }
+
+    /// The slot holding `key` in a locked stripe.
+    fn find(&self, stripe: usize, hash: u64, key: &[u8]) -> Option<usize> {
+        (0..self.slots)
+            .map(|slot| self.slot_at(stripe, slot))
+            .find(|&slot| {
+                self.u64_at(slot + HASH_AT).load(Ordering::Relaxed) == hash && {
+                    let (key_len, _) = self.lengths(slot);
+                    key_len == key.len() && self.data(slot, 0, key_len) == key
+                }
+            })
+    }
+
+    fn tick(&self) -> u64 {
+        self.u64_at(CLOCK_AT).fetch_add(1, Ordering::Relaxed)
+    }
+
+    fn get(&self, key: &[u8]) -> Option<Vec<u8>> {
+        let hash = hash(key);
+        let stripe = self.stripe_of(hash);
+        let _guard = self.lock(stripe);
+        let slot = self.find(stripe, hash, key)?;
+        if !self.live(slot, now()) {
+            self.u64_at(slot + HASH_AT).store(0, Ordering::Relaxed);
+            return None;
+        }
+        self.u64_at(slot + USED_AT)
+            .store(self.tick(), Ordering::Relaxed);
+        let (key_len, value_len) = self.lengths(slot);
+        Some(self.data(slot, key_len, value_len))
+    }
+
+    fn set(&self, key: &[u8], value: &[u8], ttl: Duration) -> bool {
+        if key.len() + value.len() > self.slot_size {
+            self.delete(key);
+            return false;
+        }
+        let hash = hash(key);
+        let stripe = self.stripe_of(hash);
+        let now = now();
+        let _guard = self.lock(stripe);
+        // The key's own slot, else a free or expired one, else the least
+        // recently used.
+        let slot = self.find(stripe, hash, key).unwrap_or_else(|| {
+            let slots = (0..self.slots).map(|slot| self.slot_at(stripe, slot));
+            slots
+                .clone()
+                .find(|&slot| !self.live(slot, now))
+                .or_else(|| {
+                    slots.min_by_key(|&slot| self.u64_at(slot + USED_AT).load(Ordering::Relaxed))
+                })
+                .expect("a stripe has at least one slot")
+        });
+        // Empty the slot while it is rewritten, so a torn write is never
+        // mistaken for an entry.
+        self.u64_at(slot + HASH_AT).store(0, Ordering::Relaxed);
+        // SAFETY: key and value fit the slot's data area (checked above)
+        // and the stripe is locked.
+        unsafe {
+            let data = self.base.add(slot + SLOT_HEADER);
+            std::ptr::copy_nonoverlapping(key.as_ptr(), data, key.len());
+            std::ptr::copy_nonoverlapping(value.as_ptr(), data.add(key.len()), value.len());
+        }
+        self.u32_at(slot + KEY_LEN_AT)
+            .store(key.len() as u32, Ordering::Relaxed);
+        self.u32_at(slot + VALUE_LEN_AT)
+            .store(value.len() as u32, Ordering::Relaxed);
+        self.u64_at(slot + EXPIRES_AT)
+            .store((now + ttl.as_secs_f64()).to_bits(), Ordering::Relaxed);
+        self.u64_at(slot + USED_AT)
+            .store(self.tick(), Ordering::Relaxed);
+        self.u64_at(slot + HASH_AT).store(hash, Ordering::Release);
+        true
+    }
+
+    fn delete(&self, key: &[u8]) -> bool {
+        let hash = hash(key);
+        let stripe = self.stripe_of(hash);
+        let _guard = self.lock(stripe);
+        let Some(slot) = self.find(stripe, hash, key) else {
+            return false;
+        };
+        let live = self.live(slot, now());
+        self.u64_at(slot + HASH_AT).store(0, Ordering::Relaxed);
+        live
+    }
+
+    /// Visit every slot holding an unexpired entry, one stripe at a time.
+    fn each_live(&self, mut visit: impl FnMut(usize)) {
+        let now = now();
+        for stripe in 0..self.stripes {
+            let _guard = self.lock(stripe);
+            (0..self.slots)
+                .map(|slot| self.slot_at(stripe, slot))
+                .filter(|&slot| self.live(slot, now))
+                .for_each(&mut visit);
+        }
+    }
+
+    fn clear(&self) {
+        for stripe in 0..self.stripes {
+            let _guard = self.lock(stripe);
+            for slot in 0..self.slots {
+                self.u64_at(self.slot_at(stripe, slot) + HASH_AT)
+                    .store(0, Ordering::Relaxed);
+            }
+        }
+    }
+}
+
+/// Cache of bytes in a named shared-memory segment, shared by every
+/// process on the host that opens the same `name`, such as the workers
+/// started by `Haske.run(workers=...)`.
+///
+/// The segment holds `max_entries` fixed-size slots of `slot_size` bytes
+/// (key plus value) in striped, spinlocked buckets. Expiry is stored as
+/// wall-clock time, so all processes agree on it, and a full bucket evicts
+/// its least recently used entry. Values too large for a slot are not
+/// stored.
+///
+/// The segment outlives the processes using it (until `unlink`, or a
+/// reboot), so a restarted server finds its cache warm; every process must
+/// open it with the same `max_entries` and `slot_size`. The file is private
+/// to the user that created it, and a segment anyone else could write is
+/// refused.
+#[pyclass(frozen, module = "haske")]
+pub struct SharedCache {
+    segment: Segment,
+    name: String,
+    path: PathBuf,
+    ttl: Duration,
+}
+
+#[pymethods]
+impl SharedCache {
+    #[new]
+    #[pyo3(signature = (name, max_entries=1024, slot_size=4096, time_to_live=300.0))]
+    fn new(name: &str, max_entries: usize, slot_size: usize, time_to_live: f64) -> PyResult<Self> {
+        if slot_size == 0 || slot_size > u32::MAX as usize {
+            return Err(PyValueError::new_err(
+                "slot_size must be between 1 and 4294967295 bytes",
+            ));
+        }
+        let ttl = Duration::try_from_secs_f64(time_to_live)
+            .map_err(|_| PyValueError::new_err("time_to_live must be a non-negative number"))?;
+        let max_entries = max_entries.max(1);
+        let stripes = max_entries.min(MAX_STRIPES);
+        let slots = max_entries.div_ceil(stripes);
+        let path = segment_path(name)?;
+        Ok(Self {
+            segment: Segment::open(&path, stripes, slots, slot_size)?,
+            name: name.to_owned(),
+            path,
+            ttl,
+        })
+    }
+
+    /// Remove the segment named `name`, returning whether it existed.
+    /// Processes that have it open keep using their mapping.
+    #[staticmethod]
+    fn unlink(name: &str) -> PyResult<bool> {
+        match std::fs::remove_file(segment_path(name)?) {
+            Ok(()) => Ok(true),
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(false),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    #[getter]
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Path of the file backing the segment.
+    #[getter]
+    fn path(&self) -> String {
+        self.path.display().to_string()
+    }
+
+    /// Number of slots, the most entries the cache can hold.
+    #[getter]
+    fn capacity(&self) -> usize {
+        self.segment.stripes * self.segment.slots
+    }
+
+    #[getter]
+    fn slot_size(&self) -> usize {
+        self.segment.slot_size
+    }
+
+    fn get<'py>(&self, py: Python<'py>, key: &str) -> Option<Bound<'py, PyBytes>> {
+        self.segment
+            .get(key.as_bytes())
+            .map(|value| PyBytes::new(py, &value))
+    }
+
+    /// Store `value`, returning whether it fit in a slot. `ttl` (seconds)
+    /// overrides the default time-to-live.
+    #[pyo3(signature = (key, value, *, ttl=None))]
+    fn set(&self, key: &str, value: &[u8], ttl: Option<f64>) -> PyResult<bool> {
+        let ttl = match ttl {
+            Some(seconds) => Duration::try_from_secs_f64(seconds)
+                .map_err(|_| PyValueError::new_err("ttl must be a non-negative number"))?,
+            None => self.ttl,
+        };
+        Ok(self.segment.set(key.as_bytes(), value, ttl))
+    }
+
+    fn delete(&self, key: &str) -> bool {
+        self.segment.delete(key.as_bytes())
+    }
+
+    fn clear(&self) {
+        self.segment.clear();
+    }
+
+    /// Keys of the unexpired entries.
+    fn keys(&self) -> Vec<String> {
+        let mut keys = Vec::new();
+        self.segment.each_live(|slot| {
+            let (key_len, _) = self.segment.lengths(slot);
+            if let Ok(key) = String::from_utf8(self.segment.data(slot, 0, key_len)) {
+                keys.push(key);
+            }
+        });
+        keys
+    }
+
+    fn size(&self) -> usize {
+        let mut size = 0;
+        self.segment.each_live(|_| size += 1);
+        size
+    }
+
+    fn __len__(&self) -> usize {
+        self.size()
+    }
+
+    fn __contains__(&self, key: &str) -> bool {
+        let key = key.as_bytes();
+        let hash = hash(key);
+        let stripe = self.segment.stripe_of(hash);
+        let _guard = self.segment.lock(stripe);
+        self.segment
+            .find(stripe, hash, key)
+            .is_some_and(|slot| self.segment.live(slot, now()))
+    }
+
+    fn __repr__(&self) -> String {
+        format!(
+            "SharedCache(name='{}', capacity={}, slot_size={})",
+            self.name,
+            self.capacity(),
+            self.segment.slot_size
+        )
+    }
+}
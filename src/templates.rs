@@ -0,0 +1,73 @@
+use pyo3::prelude::*;
+use pyo3::types::PyDict;
+
+/// Marker emitted by `precompile_template` in place of `{{ name }}`.
+const VAR_PREFIX: &str = "__HASKE_VAR_";
+const VAR_SUFFIX: &str = "__";
+
+/// Substitute `{{ name }}` placeholders (or precompiled markers) from `context`.
+///
+/// Placeholders without a matching context key are left untouched.
+#[pyfunction]
+pub fn render_template(template_src: &str, context: &Bound<'_, PyDict>) -> PyResult<String> {
+    let mut out = String::with_capacity(template_src.len());
+    let mut rest = template_src;
+
+    while let Some((start, end, name)) = next_placeholder(rest) {
+        out.push_str(&rest[..start]);
+        match context.get_item(name)? {
+            Some(value) => out.push_str(&value.str()?.to_cow()?),
+            None => out.push_str(&rest[start..end]),
+        }
+        rest = &rest[end..];
+    }
+    out.push_str(rest);
+    Ok(out)
+}
+
+/// Normalize `{{ name }}` placeholders into fixed markers so rendering is a plain scan.
+#[pyfunction]
+pub fn precompile_template(template_src: &str) -> String {
+    let mut out = String::with_capacity(template_src.len());
+    let mut rest = template_src;
+
+    while let Some(open) = rest.find("{{") {
+        let Some(close) = rest[open..].find("}}") else {
+            break;
+        };
+        let name = rest[open + 2..open + close].trim();
+        out.push_str(&rest[..open]);
+        if crate::path::is_identifier(name) {
+            out.push_str(VAR_PREFIX);
+            out.push_str(name);
+            out.push_str(VAR_SUFFIX);
+        } else {
+            out.push_str(&rest[open..open + close + 2]);
+        }
+        rest = &rest[open + close + 2..];
+    }
+    out.push_str(rest);
+    out
+}
+
+/// Locate the next `{{ name }}` or precompiled marker, returning `(start, end, name)`.
+fn next_placeholder(src: &str) -> Option<(usize, usize, &str)> {
+    let braces = src.find("{{").and_then(|open| {
+        let close = src[open..].find("}}")?;
+        Some((open, open + close + 2, src[open + 2..open + close].trim()))
+    });
+    let marker = src.find(VAR_PREFIX).and_then(|open| {
+        let name_start = open + VAR_PREFIX.len();
+        let len = src[name_start..].find(VAR_SUFFIX)?;
+        Some((
+            open,
+            name_start + len + VAR_SUFFIX.len(),
+            &src[name_start..name_start + len],
+        ))
+    });
+
+    match (braces, marker) {
+        (Some(b), Some(m)) => Some(if b.0 <= m.0 { b } else { m }),
+        (found, None) | (None, found) => found,
+    }
+}
@@ -0,0 +1,444 @@
+use std::collections::{HashMap, VecDeque};
+use std::sync::Arc;
+
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use parking_lot::{Mutex, RwLock};
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+use pyo3::types::PyBytes;
+use sha1::{Digest, Sha1};
+
+use crate::crypto::random_bytes;
+
+/// GUID appended to `Sec-WebSocket-Key` by RFC 6455 §4.2.2.
+const WS_GUID: &str = "258EAFA5-E914-47DA-95CA-C5AB0DC85B11";
+
+pub(crate) const OPCODE_CONTINUATION: u8 = 0x0;
+pub(crate) const OPCODE_TEXT: u8 = 0x1;
+pub(crate) const OPCODE_BINARY: u8 = 0x2;
+pub(crate) const OPCODE_CLOSE: u8 = 0x8;
+pub(crate) const OPCODE_PING: u8 = 0x9;
+pub(crate) const OPCODE_PONG: u8 = 0xA;
+
+/// Compute the `Sec-WebSocket-Accept` header value for a client key.
+#[pyfunction]
+pub fn websocket_accept_key(key: &str) -> String {
+    let mut sha = Sha1::new();
+    sha.update(key.trim().as_bytes());
+    sha.update(WS_GUID.as_bytes());
+    STANDARD.encode(sha.finalize())
+}
+
+/// Maximum payload size of a control frame (RFC 6455 §5.5).
+const MAX_CONTROL_PAYLOAD: usize = 125;
+
+/// Check that `frame_data` holds one complete, well-formed frame.
+#[pyfunction]
+pub fn validate_websocket_frame(frame_data: &[u8]) -> bool {
+    let Ok(header) = FrameHeader::read(frame_data) else {
+        return false;
+    };
+    let rsv = frame_data[0] & 0x70;
+    let complete = header.offset.checked_add(header.payload_len) == Some(frame_data.len());
+    let known = matches!(
+        header.opcode,
+        OPCODE_CONTINUATION
+            | OPCODE_TEXT
+            | OPCODE_BINARY
+            | OPCODE_CLOSE
+            | OPCODE_PING
+            | OPCODE_PONG
+    );
+    let control_ok = header.opcode < OPCODE_CLOSE
+        || (header.is_final && header.payload_len <= MAX_CONTROL_PAYLOAD);
+    rsv == 0 && complete && known && control_ok
+}
+
+#[pyfunction]
+pub fn get_frame_type(frame_data: &[u8]) -> Option<u8> {
+    frame_data.first().map(|b| b & 0x0F)
+}
+
+#[pyfunction]
+pub fn get_payload_length(frame_data: &[u8]) -> PyResult<usize> {
+    FrameHeader::read(frame_data).map(|h| h.payload_len)
+}
+
+#[pyfunction]
+pub fn is_final_frame(frame_data: &[u8]) -> bool {
+    frame_data.first().is_some_and(|b| b & 0x80 != 0)
+}
+
+#[pyfunction]
+pub fn is_masked_frame(frame_data: &[u8]) -> bool {
+    frame_data.get(1).is_some_and(|b| b & 0x80 != 0)
+}
+
+/// A single RFC 6455 frame. Payloads are always stored unmasked.
+#[pyclass]
+#[derive(Clone)]
+pub struct WebSocketFrame {
+    #[pyo3(get)]
+    pub opcode: u8,
+    pub payload: Vec<u8>,
+    #[pyo3(get)]
+    pub is_final: bool,
+    #[pyo3(get)]
+    pub is_masked: bool,
+}
+
+#[pymethods]
+impl WebSocketFrame {
+    #[new]
+    #[pyo3(signature = (opcode, payload, is_final=true, is_masked=false))]
+    fn new(opcode: u8, payload: Vec<u8>, is_final: bool, is_masked: bool) -> Self {
+        Self {
+            opcode,
+            payload,
+            is_final,
+            is_masked,
+        }
+    }
+
+    #[getter]
+    fn payload<'py>(&self, py: Python<'py>) -> Bound<'py, PyBytes> {
+        PyBytes::new(py, &self.payload)
+    }
+
+    #[staticmethod]
+    fn parse(frame_data: &[u8]) -> PyResult<Self> {
+        let header = FrameHeader::read(frame_data)?;
+        let end = header
+            .offset
+            .checked_add(header.payload_len)
+            .filter(|end| *end <= frame_data.len())
+            .ok_or_else(|| PyValueError::new_err("incomplete frame"))?;
+
+        let mut payload = frame_data[header.offset..end].to_vec();
+        if let Some(mask) = header.mask {
+            apply_mask(&mut payload, mask);
+        }
+        Ok(Self {
+            opcode: header.opcode,
+            payload,
+            is_final: header.is_final,
+            is_masked: header.mask.is_some(),
+        })
+    }
+
+    #[staticmethod]
+    fn text(text: &str) -> Self {
+        Self::new(OPCODE_TEXT, text.as_bytes().to_vec(), true, false)
+    }
+
+    #[staticmethod]
+    fn binary(data: Vec<u8>) -> Self {
+        Self::new(OPCODE_BINARY, data, true, false)
+    }
+
+    #[staticmethod]
+    fn ping(data: Vec<u8>) -> Self {
+        Self::new(OPCODE_PING, data, true, false)
+    }
+
+    #[staticmethod]
+    fn pong(data: Vec<u8>) -> Self {
+        Self::new(OPCODE_PONG, data, true, false)
+    }
+
+    #[staticmethod]
+    #[pyo3(signature = (code=1000, reason=""))]
+    fn close(code: u16, reason: &str) -> Self {
+        let mut payload = code.to_be_bytes().to_vec();
+        payload.extend_from_slice(reason.as_bytes());
+        Self::new(OPCODE_CLOSE, payload, true, false)
+    }
+
+    /// Serialize the frame; masked frames get a fresh random masking key.
+    fn to_bytes<'py>(&self, py: Python<'py>) -> Bound<'py, PyBytes> {
+        PyBytes::new(py, &self.encode())
+    }
+
+    fn __repr__(&self) -> String {
+        format!(
+            "WebSocketFrame(opcode={}, len={}, is_final={}, is_masked={})",
+            self.opcode,
+            self.payload.len(),
+            self.is_final,
+            self.is_masked
+        )
+    }
+}
+
+impl WebSocketFrame {
+    fn encode(&self) -> Vec<u8> {
+        let len = self.payload.len();
+        let mut out = Vec::with_capacity(len + 14);
+        out.push(((self.is_final as u8) << 7) | (self.opcode & 0x0F));
+
+        let mask_bit = (self.is_masked as u8) << 7;
+        if len < 126 {
+            out.push(mask_bit | len as u8);
+        } else if len <= u16::MAX as usize {
+            out.push(mask_bit | 126);
+            out.extend_from_slice(&(len as u16).to_be_bytes());
+        } else {
+            out.push(mask_bit | 127);
+            out.extend_from_slice(&(len as u64).to_be_bytes());
+        }
+
+        if self.is_masked {
+            let mut mask = [0u8; 4];
+            mask.copy_from_slice(&random_bytes(4));
+            out.extend_from_slice(&mask);
+            let start = out.len();
+            out.extend_from_slice(&self.payload);
+            apply_mask(&mut out[start..], mask);
+        } else {
+            out.extend_from_slice(&self.payload);
+        }
+        out
+    }
+}
+
+/// Decoded fixed and extended header fields of a frame.
+pub(crate) struct FrameHeader {
+    pub is_final: bool,
+    pub opcode: u8,
+    pub mask: Option<[u8; 4]>,
+    pub payload_len: usize,
+    /// Offset of the first payload byte.
+    pub offset: usize,
+}
+
+impl FrameHeader {
+    pub(crate) fn read(data: &[u8]) -> PyResult<Self> {
+        if data.len() < 2 {
+            return Err(PyValueError::new_err("frame too short"));
+        }
+        let is_final = data[0] & 0x80 != 0;
+        let opcode = data[0] & 0x0F;
+        let masked = data[1] & 0x80 != 0;
+
+        let (payload_len, mut offset) = match data[1] & 0x7F {
+            126 => {
+                let bytes = data
+                    .get(2..4)
+                    .ok_or_else(|| PyValueError::new_err("invalid frame length"))?;
+                (u16::from_be_bytes([bytes[0], bytes[1]]) as usize, 4)
+            }
+            127 => {
+                let bytes = data
+                    .get(2..10)
+                    .ok_or_else(|| PyValueError::new_err("invalid frame length"))?;
+                let mut buf = [0u8; 8];
+                buf.copy_from_slice(bytes);
+                let len = usize::try_from(u64::from_be_bytes(buf))
+                    .map_err(|_| PyValueError::new_err("invalid frame length"))?;
+                (len, 10)
+            }
+            len => (len as usize, 2),
+        };
+
+        let mask = if masked {
+            let bytes = data
+                .get(offset..offset + 4)
+                .ok_or_else(|| PyValueError::new_err("invalid mask length"))?;
+            offset += 4;
+            Some([bytes[0], bytes[1], bytes[2], bytes[3]])
+        } else {
+            None
+        };
+
+        Ok(Self {
+            is_final,
+            opcode,
+            mask,
+            payload_len,
+            offset,
+        })
+    }
+}
+
+fn apply_mask(payload: &mut [u8], mask: [u8; 4]) {
+    for (i, byte) in payload.iter_mut().enumerate() {
+        *byte ^= mask[i % 4];
+    }
+}
+
+/// Bounded message log for one broadcast channel.
+struct Channel {
+    messages: VecDeque<Vec<u8>>,
+    capacity: usize,
+    /// Sequence number of the oldest message still in `messages`.
+    first_seq: u64,
+}
+
+impl Channel {
+    fn new(capacity: usize) -> Self {
+        Self {
+            messages: VecDeque::new(),
+            capacity: capacity.max(1),
+            first_seq: 0,
+        }
+    }
+
+    fn next_seq(&self) -> u64 {
+        self.first_seq + self.messages.len() as u64
+    }
+
+    fn push(&mut self, message: Vec<u8>) {
+        if self.messages.len() == self.capacity {
+            self.messages.pop_front();
+            self.first_seq += 1;
+        }
+        self.messages.push_back(message);
+    }
+
+    fn clear(&mut self) {
+        self.first_seq = self.next_seq();
+        self.messages.clear();
+    }
+}
+
+type SharedChannel = Arc<Mutex<Channel>>;
+
+/// In-process pub/sub hub keeping the last `capacity` messages per channel.
+#[pyclass(frozen)]
+#[derive(Default)]
+pub struct WebSocketManager {
+    channels: RwLock<HashMap<String, SharedChannel>>,
+}
+
+impl WebSocketManager {
+    fn channel(&self, channel_id: &str) -> PyResult<SharedChannel> {
+        self.channels
+            .read()
+            .get(channel_id)
+            .cloned()
+            .ok_or_else(|| PyValueError::new_err("Channel not found"))
+    }
+}
+
+#[pymethods]
+impl WebSocketManager {
+    #[new]
+    fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create `channel_id` if it does not exist yet.
+    #[pyo3(signature = (channel_id, capacity=1000))]
+    fn create_channel(&self, channel_id: String, capacity: usize) {
+        self.channels
+            .write()
+            .entry(channel_id)
+            .or_insert_with(|| Arc::new(Mutex::new(Channel::new(capacity))));
+    }
+
+    /// Append `message` to the channel, returning the number of attached receivers.
+    fn broadcast(&self, channel_id: &str, message: Vec<u8>) -> PyResult<usize> {
+        let channel = self.channel(channel_id)?;
+        channel.lock().push(message);
+        // One reference is held by the manager map and one by this call.
+        Ok(Arc::strong_count(&channel).saturating_sub(2))
+    }
+
+    /// Return the most recent message of the channel, if any.
+    fn get_message<'py>(
+        &self,
+        py: Python<'py>,
+        channel_id: &str,
+    ) -> PyResult<Option<Bound<'py, PyBytes>>> {
+        let channel = self.channel(channel_id)?;
+        let channel = channel.lock();
+        Ok(channel.messages.back().map(|m| PyBytes::new(py, m)))
+    }
+
+    fn get_receiver(&self, channel_id: &str) -> PyResult<WebSocketReceiver> {
+        let channel = self.channel(channel_id)?;
+        let position = channel.lock().next_seq();
+        Ok(WebSocketReceiver {
+            channel_id: channel_id.to_owned(),
+            channel: Mutex::new(Some(channel)),
+            position: Mutex::new(position),
+        })
+    }
+
+    fn clear_channel(&self, channel_id: &str) -> PyResult<()> {
+        self.channel(channel_id)?.lock().clear();
+        Ok(())
+    }
+
+    fn remove_channel(&self, channel_id: &str) {
+        self.channels.write().remove(channel_id);
+    }
+
+    fn list_channels(&self) -> Vec<String> {
+        self.channels.read().keys().cloned().collect()
+    }
+}
+
+/// Cursor over a channel's messages; each receiver sees every message once.
+#[pyclass(frozen)]
+pub struct WebSocketReceiver {
+    channel_id: String,
+    channel: Mutex<Option<SharedChannel>>,
+    position: Mutex<u64>,
+}
+
+#[pymethods]
+impl WebSocketReceiver {
+    #[new]
+    fn new(channel_id: String) -> Self {
+        Self {
+            channel_id,
+            channel: Mutex::new(None),
+            position: Mutex::new(0),
+        }
+    }
+
+    /// Return the next unread message, or `None` when caught up.
+    ///
+    /// Receivers created directly must pass the manager on each call; those
+    /// from `WebSocketManager.get_receiver` are already attached.
+    #[pyo3(signature = (manager=None))]
+    fn recv<'py>(
+        &self,
+        py: Python<'py>,
+        manager: Option<&WebSocketManager>,
+    ) -> PyResult<Option<Bound<'py, PyBytes>>> {
+        let channel = match (manager, self.channel.lock().clone()) {
+            (Some(manager), _) => manager.channel(&self.channel_id)?,
+            (None, Some(channel)) => channel,
+            (None, None) => {
+                return Err(PyValueError::new_err(
+                    "receiver is not attached to a manager",
+                ))
+            }
+        };
+
+        let channel = channel.lock();
+        let mut position = self.position.lock();
+        // Messages older than the buffer were dropped; skip ahead to the oldest
+        // kept. A position past the end means the channel was recreated.
+        if *position < channel.first_seq || *position > channel.next_seq() {
+            *position = channel.first_seq;
+        }
+        let index = (*position - channel.first_seq) as usize;
+        Ok(channel.messages.get(index).map(|message| {
+            *position += 1;
+            PyBytes::new(py, message)
+        }))
+    }
+
+    fn get_position(&self) -> u64 {
+        *self.position.lock()
+    }
+
+    /// Rewind to the oldest message still buffered.
+    fn reset(&self) {
+        *self.position.lock() = 0;
+    }
+}
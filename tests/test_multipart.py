@@ -0,0 +1,55 @@
+import asyncio
+
+import pytest
+
+pytest.importorskip("haske.haske")
+
+from haske.exceptions import HaskeError
+from haske.request import Request
+from _haske_core import MultipartParser, MultipartLimitError
+
+BOUNDARY = "haskeboundary"
+CONTENT_TYPE = f"multipart/form-data; boundary={BOUNDARY}"
+
+
+def field(name, value):
+    return (f'--{BOUNDARY}\r\nContent-Disposition: form-data; name="{name}"\r\n\r\n'
+            .encode() + value + b"\r\n")
+
+
+def body(*parts):
+    return b"".join(parts) + f"--{BOUNDARY}--\r\n".encode()
+
+
+def post(payload, chunk_size=64 * 1024, **limits):
+    chunks = [payload[i:i + chunk_size] for i in range(0, len(payload), chunk_size)]
+
+    async def receive():
+        chunk = chunks.pop(0)
+        return {"type": "http.request", "body": chunk, "more_body": bool(chunks)}
+
+    scope = {"type": "http", "method": "POST", "path": "/",
+             "headers": [(b"content-type", CONTENT_TYPE.encode())]}
+    return asyncio.run(Request(scope, receive, None).form(**limits))
+
+
+def test_fields_within_limits_are_parsed():
+    assert post(body(field("a", b"1"), field("b", b"x" * 1000))) == {"a": "1", "b": "x" * 1000}
+
+
+def test_oversized_field_is_rejected_by_default():
+    with pytest.raises(HaskeError) as info:
+        post(body(field("big", b"x" * (2 * 1024 * 1024))))
+    assert info.value.status_code == 413
+
+
+def test_field_limit_is_configurable():
+    assert post(body(field("big", b"x" * 2048)), max_field_size=4096)["big"] == "x" * 2048
+    with pytest.raises(HaskeError):
+        post(body(field("big", b"x" * 2048)), max_field_size=1024)
+
+
+def test_too_many_parts_are_rejected():
+    parser = MultipartParser(CONTENT_TYPE, max_parts=3)
+    with pytest.raises(MultipartLimitError):
+        parser.feed(body(*(field(f"f{i}", b"v") for i in range(4))))